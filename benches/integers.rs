@@ -0,0 +1,51 @@
+//! 整数を多く扱う処理の速さを測るベンチマーク
+//! 外部のクレートを使わないので、`std::time::Instant`で測った時間を表示する。
+
+use std::cell::RefCell;
+use std::hint::black_box;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use monkey_rs::environment::Environment;
+use monkey_rs::evaluator::Eval;
+use monkey_rs::lexer::Lexer;
+use monkey_rs::object::Object;
+use monkey_rs::parser::Parser;
+
+/// 繰り返しの回数
+const ITERATIONS: i64 = 10_000_000;
+
+/// 関数を実行するのにかかった時間を返す関数
+fn measure(f: impl FnOnce()) -> Duration {
+    let start = Instant::now();
+    f();
+    start.elapsed()
+}
+
+fn main() {
+    let cached = measure(|| {
+        for i in 0..ITERATIONS {
+            black_box(Object::integer(black_box(i & 0xff)));
+        }
+    });
+    let direct = measure(|| {
+        for i in 0..ITERATIONS {
+            black_box(Object::Integer {
+                value: black_box(i & 0xff),
+            });
+        }
+    });
+    println!("Object::integer (small integers): {:?}", cached);
+    println!("Object::Integer (direct):         {:?}", direct);
+
+    // 整数の演算を繰り返すスクリプトを評価する
+    let source = "let sum = 0; for (let i = 0; i < 100000; i = i + 1) { sum = sum + 1; }; sum;";
+    let program = Parser::new(Lexer::new(source))
+        .parse_program()
+        .expect("fail parse program.");
+    let evaluated = measure(|| {
+        let env = Rc::new(RefCell::new(Environment::new()));
+        black_box(Eval::new(env).eval_program(&program));
+    });
+    println!("tight loop of 100000 iterations:  {:?}", evaluated);
+}
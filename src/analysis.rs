@@ -0,0 +1,610 @@
+use std::borrow::Borrow;
+use std::collections::HashSet;
+
+use crate::ast::{Expression, Node, Program, Statement};
+
+/// return文より後にあって実行されることのない文を探す関数
+/// 評価を止めるエラーではなく警告として、ブロックごとに最初の実行されない文の位置をメッセージにして返す。
+/// if式はすべての分岐がreturnするときだけ、その後の文を実行されないものとみなす。
+pub fn unreachable(program: &Program) -> Vec<String> {
+    let mut diagnostics = vec![];
+    check_statements(&program.statements, &mut diagnostics);
+    return diagnostics;
+}
+
+/// 文の並びを先頭から調べる関数。必ずreturnするならtrue
+/// プログラム直下の文とブロックの文の両方を受け取る。
+fn check_statements<S: Borrow<Statement>>(statements: &[S], diagnostics: &mut Vec<String>) -> bool {
+    for (i, stmt) in statements.iter().enumerate() {
+        if !check_statement(stmt.borrow(), diagnostics) {
+            continue;
+        }
+        if let Some(next) = statements.get(i + 1) {
+            let (line, column) = next.borrow().get_token().get_position();
+            diagnostics.push(format!(
+                "{}行{}列の文はreturnの後にあるので実行されません。",
+                line, column
+            ));
+            // 実行されない文の中も調べるが、同じブロックで警告するのは最初の文だけにする
+            for rest in statements[i + 1..].iter() {
+                check_statement(rest.borrow(), diagnostics);
+            }
+        }
+        return true;
+    }
+    return false;
+}
+
+/// 文の中を調べる関数。必ずreturnするならtrue
+fn check_statement(statement: &Statement, diagnostics: &mut Vec<String>) -> bool {
+    match statement {
+        Statement::LetStatement {
+            token: _,
+            name: _,
+            value,
+            doc: _,
+        } => {
+            check_expression(value, diagnostics);
+            false
+        }
+        Statement::AssignStatement {
+            token: _,
+            name: _,
+            value,
+        } => check_expression(value, diagnostics),
+        Statement::ReturnStatement {
+            token: _,
+            return_value,
+        } => {
+            check_expression(return_value, diagnostics);
+            true
+        }
+        Statement::ExpressionStatement {
+            token: _,
+            expression,
+        } => check_expression(expression, diagnostics),
+        Statement::BlockStatement {
+            token: _,
+            statements,
+        } => check_statements(statements, diagnostics),
+        // 本体は少なくとも一度は評価される
+        Statement::DoWhileStatement {
+            token: _,
+            body,
+            condition,
+        } => {
+            let body = check_statement(body, diagnostics);
+            let cond = check_expression(condition, diagnostics);
+            body || cond
+        }
+        // 条件が最初から偽なら本体と更新の文は評価されない
+        Statement::ForStatement {
+            token: _,
+            init,
+            condition,
+            update,
+            body,
+        } => {
+            let init = match init {
+                Some(init) => check_statement(init, diagnostics),
+                None => false,
+            };
+            let cond = match condition {
+                Some(condition) => check_expression(condition, diagnostics),
+                None => false,
+            };
+            if let Some(update) = update {
+                check_statement(update, diagnostics);
+            }
+            check_statement(body, diagnostics);
+            init || cond
+        }
+        // 空の配列なら本体は評価されない
+        Statement::ForInStatement {
+            token: _,
+            variable: _,
+            iterable,
+            body,
+        } => {
+            let iterable = check_expression(iterable, diagnostics);
+            check_statement(body, diagnostics);
+            iterable
+        }
+        Statement::BreakStatement { token: _ } | Statement::ContinueStatement { token: _ } => false,
+        // 読み込んだファイルのreturnはそのファイルの評価を止めるだけ
+        Statement::ImportStatement { token: _, path: _ } => false,
+    }
+}
+
+/// 式の中のブロックを調べる関数。評価すると必ずreturnするならtrue
+/// 関数本体のreturnは関数から抜けるだけなので、関数リテラルは外側の文の到達可能性に影響しない。
+fn check_expression(expression: &Expression, diagnostics: &mut Vec<String>) -> bool {
+    match expression {
+        Expression::Identifier { token: _, value: _ }
+        | Expression::IntegerLiteral { token: _, value: _ }
+        | Expression::FloatLiteral { token: _, value: _ }
+        | Expression::BooleanLiteral { token: _, value: _ }
+        | Expression::StringLiteral { token: _, value: _ }
+        | Expression::NullLiteral { token: _ } => false,
+        Expression::FunctionLiteral {
+            token: _,
+            parameters: _,
+            body,
+            name: _,
+        } => {
+            check_statement(body, diagnostics);
+            false
+        }
+        Expression::PrefixExpression {
+            token: _,
+            operator: _,
+            right_exp,
+        } => check_expression(right_exp, diagnostics),
+        Expression::InfixExpression {
+            token: _,
+            operator: _,
+            left_exp,
+            right_exp,
+        } => {
+            let left = check_expression(left_exp, diagnostics);
+            let right = check_expression(right_exp, diagnostics);
+            left || right
+        }
+        Expression::IfExpression {
+            token: _,
+            condition,
+            consequence,
+            alternative,
+        } => {
+            let cond = check_expression(condition, diagnostics);
+            let cons = check_statement(consequence, diagnostics);
+            let alt = match alternative {
+                Some(alt) => check_statement(alt, diagnostics),
+                None => false,
+            };
+            cond || (cons && alt)
+        }
+        Expression::SwitchExpression {
+            token: _,
+            subject,
+            arms,
+            default,
+        } => {
+            let returned = check_expression(subject, diagnostics);
+            for (pattern, value) in arms.iter() {
+                check_expression(pattern, diagnostics);
+                check_expression(value, diagnostics);
+            }
+            if let Some(default) = default {
+                check_expression(default, diagnostics);
+            }
+            returned
+        }
+        Expression::ArrayLiteral { token: _, elements } => check_expressions(elements, diagnostics),
+        Expression::HashLiteral { token: _, pairs } => {
+            let mut returned = false;
+            for (key, value) in pairs.iter() {
+                returned |= check_expression(key, diagnostics);
+                returned |= check_expression(value, diagnostics);
+            }
+            returned
+        }
+        Expression::ArrayPattern {
+            token: _,
+            names: _,
+            rest: _,
+        } => false,
+        Expression::CallExpression {
+            token: _,
+            function,
+            arguments,
+        } => {
+            let func = check_expression(function, diagnostics);
+            let args = check_expressions(arguments, diagnostics);
+            func || args
+        }
+        Expression::IndexExpression {
+            token: _,
+            left,
+            index,
+        } => {
+            let left = check_expression(left, diagnostics);
+            let index = check_expression(index, diagnostics);
+            left || index
+        }
+        Expression::SpreadExpression { token: _, value } => check_expression(value, diagnostics),
+        Expression::MemberExpression {
+            token: _,
+            object,
+            property: _,
+        } => check_expression(object, diagnostics),
+        Expression::RangeLiteral {
+            token: _,
+            start,
+            end,
+        } => {
+            let start = check_expression(start, diagnostics);
+            let end = check_expression(end, diagnostics);
+            start || end
+        }
+    }
+}
+
+/// 式の並びを調べる関数。いずれかの式が必ずreturnするならtrue
+fn check_expressions(expressions: &[Box<Expression>], diagnostics: &mut Vec<String>) -> bool {
+    let mut returned = false;
+    for expression in expressions.iter() {
+        returned |= check_expression(expression, diagnostics);
+    }
+    return returned;
+}
+
+/// 同じスコープでlet文が識別子を再宣言している箇所を探す関数
+/// 評価器は再宣言を許して値を上書きするので、厳しく確かめたいときだけ使う。
+/// スコープは評価器の環境と同じく、プログラム直下と関数本体、for文全体、for-in文の繰り返しごとに作られる。
+/// if式やdo-while文のブロックは外側と同じスコープなので、その中のlet文も外側の宣言と比べる。
+/// 内側のスコープで外側の識別子を隠すのは再宣言とみなさない。
+pub fn shadowing(program: &Program) -> Vec<String> {
+    let mut diagnostics = vec![];
+    let mut scope = HashSet::new();
+    for stmt in program.statements.iter() {
+        declare_statement(stmt, &mut scope, &mut diagnostics);
+    }
+    return diagnostics;
+}
+
+/// 文が宣言する識別子を現在のスコープに加え、文の中のスコープも調べる関数
+fn declare_statement(
+    statement: &Statement,
+    scope: &mut HashSet<String>,
+    diagnostics: &mut Vec<String>,
+) {
+    match statement {
+        Statement::LetStatement {
+            token,
+            name,
+            value,
+            doc: _,
+        } => {
+            // 束縛する前に右辺を評価するので、右辺の関数は宣言前のスコープで調べてよい
+            declare_expression(value, scope, diagnostics);
+            for ident in bound_names(name) {
+                if !scope.insert(ident.to_string()) {
+                    let (line, column) = token.get_position();
+                    diagnostics.push(format!(
+                        "{}行{}列のlet文は同じスコープの識別子{}を再宣言しています。",
+                        line, column, ident
+                    ));
+                }
+            }
+        }
+        Statement::AssignStatement {
+            token: _,
+            name: _,
+            value,
+        } => declare_expression(value, scope, diagnostics),
+        Statement::ReturnStatement {
+            token: _,
+            return_value,
+        } => declare_expression(return_value, scope, diagnostics),
+        Statement::ExpressionStatement {
+            token: _,
+            expression,
+        } => declare_expression(expression, scope, diagnostics),
+        Statement::BlockStatement {
+            token: _,
+            statements,
+        } => {
+            for stmt in statements.iter() {
+                declare_statement(stmt, scope, diagnostics);
+            }
+        }
+        Statement::DoWhileStatement {
+            token: _,
+            body,
+            condition,
+        } => {
+            declare_statement(body, scope, diagnostics);
+            declare_expression(condition, scope, diagnostics);
+        }
+        Statement::ForStatement {
+            token: _,
+            init,
+            condition,
+            update,
+            body,
+        } => {
+            let mut loop_scope = HashSet::new();
+            if let Some(init) = init {
+                declare_statement(init, &mut loop_scope, diagnostics);
+            }
+            if let Some(condition) = condition {
+                declare_expression(condition, &mut loop_scope, diagnostics);
+            }
+            declare_statement(body, &mut loop_scope, diagnostics);
+            if let Some(update) = update {
+                declare_statement(update, &mut loop_scope, diagnostics);
+            }
+        }
+        Statement::ForInStatement {
+            token: _,
+            variable,
+            iterable,
+            body,
+        } => {
+            declare_expression(iterable, scope, diagnostics);
+            let mut iteration_scope: HashSet<String> = bound_names(variable)
+                .into_iter()
+                .map(|name| name.to_string())
+                .collect();
+            declare_statement(body, &mut iteration_scope, diagnostics);
+        }
+        // 読み込むファイルが宣言する識別子はパースしただけでは分からない
+        Statement::BreakStatement { token: _ }
+        | Statement::ContinueStatement { token: _ }
+        | Statement::ImportStatement { token: _, path: _ } => {}
+    }
+}
+
+/// 式の中にある関数本体のスコープを調べる関数
+/// 式そのものは識別子を宣言しないが、if式のブロックは現在のスコープに宣言する。
+fn declare_expression(
+    expression: &Expression,
+    scope: &mut HashSet<String>,
+    diagnostics: &mut Vec<String>,
+) {
+    match expression {
+        Expression::Identifier { token: _, value: _ }
+        | Expression::IntegerLiteral { token: _, value: _ }
+        | Expression::FloatLiteral { token: _, value: _ }
+        | Expression::BooleanLiteral { token: _, value: _ }
+        | Expression::StringLiteral { token: _, value: _ }
+        | Expression::NullLiteral { token: _ }
+        | Expression::ArrayPattern {
+            token: _,
+            names: _,
+            rest: _,
+        } => {}
+        Expression::FunctionLiteral {
+            token: _,
+            parameters,
+            body,
+            name: _,
+        } => {
+            let mut function_scope = HashSet::new();
+            for parameter in parameters.iter() {
+                for name in bound_names(parameter) {
+                    function_scope.insert(name.to_string());
+                }
+            }
+            declare_statement(body, &mut function_scope, diagnostics);
+        }
+        Expression::PrefixExpression {
+            token: _,
+            operator: _,
+            right_exp,
+        } => declare_expression(right_exp, scope, diagnostics),
+        Expression::InfixExpression {
+            token: _,
+            operator: _,
+            left_exp,
+            right_exp,
+        } => {
+            declare_expression(left_exp, scope, diagnostics);
+            declare_expression(right_exp, scope, diagnostics);
+        }
+        Expression::IfExpression {
+            token: _,
+            condition,
+            consequence,
+            alternative,
+        } => {
+            declare_expression(condition, scope, diagnostics);
+            declare_statement(consequence, scope, diagnostics);
+            if let Some(alt) = alternative {
+                declare_statement(alt, scope, diagnostics);
+            }
+        }
+        Expression::SwitchExpression {
+            token: _,
+            subject,
+            arms,
+            default,
+        } => {
+            declare_expression(subject, scope, diagnostics);
+            for (pattern, value) in arms.iter() {
+                declare_expression(pattern, scope, diagnostics);
+                declare_expression(value, scope, diagnostics);
+            }
+            if let Some(default) = default {
+                declare_expression(default, scope, diagnostics);
+            }
+        }
+        Expression::ArrayLiteral { token: _, elements } => {
+            for element in elements.iter() {
+                declare_expression(element, scope, diagnostics);
+            }
+        }
+        Expression::HashLiteral { token: _, pairs } => {
+            for (key, value) in pairs.iter() {
+                declare_expression(key, scope, diagnostics);
+                declare_expression(value, scope, diagnostics);
+            }
+        }
+        Expression::CallExpression {
+            token: _,
+            function,
+            arguments,
+        } => {
+            declare_expression(function, scope, diagnostics);
+            for argument in arguments.iter() {
+                declare_expression(argument, scope, diagnostics);
+            }
+        }
+        Expression::IndexExpression {
+            token: _,
+            left,
+            index,
+        } => {
+            declare_expression(left, scope, diagnostics);
+            declare_expression(index, scope, diagnostics);
+        }
+        Expression::MemberExpression {
+            token: _,
+            object,
+            property: _,
+        } => declare_expression(object, scope, diagnostics),
+        Expression::RangeLiteral {
+            token: _,
+            start,
+            end,
+        } => {
+            declare_expression(start, scope, diagnostics);
+            declare_expression(end, scope, diagnostics);
+        }
+        Expression::SpreadExpression { token: _, value } => {
+            declare_expression(value, scope, diagnostics)
+        }
+    }
+}
+
+/// let文の左辺や関数の仮引数が束縛する識別子の名前を返す関数
+fn bound_names(target: &Expression) -> Vec<&str> {
+    match target {
+        Expression::Identifier { token: _, value } => vec![value.as_str()],
+        Expression::ArrayPattern {
+            token: _,
+            names,
+            rest,
+        } => names
+            .iter()
+            .chain(rest.iter())
+            .flat_map(|name| bound_names(name))
+            .collect(),
+        Expression::SpreadExpression { token: _, value } => bound_names(value),
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::analysis::{shadowing, unreachable};
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn analyze(input: &str) -> Vec<String> {
+        let program = Parser::new(Lexer::new(input)).parse_program();
+        unreachable(&program.expect("fail parse program."))
+    }
+
+    #[test]
+    fn test_unreachable_after_return() {
+        let tests = [
+            (
+                "let f = fn() { return 1; 2; };",
+                vec!["1行26列の文はreturnの後にあるので実行されません。"],
+            ),
+            // 同じブロックでは最初の文だけを警告する
+            (
+                "let f = fn() {\n  return 1;\n  let x = 2;\n  x;\n};",
+                vec!["3行3列の文はreturnの後にあるので実行されません。"],
+            ),
+            // すべての分岐がreturnするif式の後
+            (
+                "fn(x) {\n  if (x) { return 1; } else { return 2; };\n  3;\n};",
+                vec!["3行3列の文はreturnの後にあるので実行されません。"],
+            ),
+            // 入れ子のブロックはそれぞれ調べる
+            (
+                "fn() {\n  if (true) { return 1; 2; };\n  fn() { return 3; 4; };\n};",
+                vec![
+                    "2行25列の文はreturnの後にあるので実行されません。",
+                    "3行20列の文はreturnの後にあるので実行されません。",
+                ],
+            ),
+            (
+                "return 1;\nlet x = 2;",
+                vec!["2行1列の文はreturnの後にあるので実行されません。"],
+            ),
+            // do-whileの本体は必ず評価される
+            (
+                "fn() {\n  do { return 1; } while (true);\n  2;\n};",
+                vec!["3行3列の文はreturnの後にあるので実行されません。"],
+            ),
+        ];
+        for (input, expect) in tests.iter() {
+            assert_eq!(analyze(input), *expect, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_reachable_code() {
+        let inputs = [
+            "let f = fn() { 1; return 2; };",
+            // 片方の分岐だけがreturnするif式の後は実行されうる
+            "fn(x) { if (x) { return 1; }; 2; };",
+            "fn(x) { if (x) { 1; } else { return 2; }; 3; };",
+            // 関数本体のreturnは外側の文に影響しない
+            "let f = fn() { return 1; }; f();",
+            "fn() { switch (1) { 1 => fn() { return 1; }, _ => 2 }; 3; };",
+        ];
+        for input in inputs.iter() {
+            assert!(analyze(input).is_empty(), "{}", input);
+        }
+    }
+
+    fn shadowed(input: &str) -> Vec<String> {
+        let program = Parser::new(Lexer::new(input)).parse_program();
+        shadowing(&program.expect("fail parse program."))
+    }
+
+    #[test]
+    fn test_shadowing_in_same_scope() {
+        let tests = [
+            (
+                "let x = 1;\nlet x = 2;",
+                vec!["2行1列のlet文は同じスコープの識別子xを再宣言しています。"],
+            ),
+            // if式のブロックは外側と同じスコープ
+            (
+                "let x = 1; if (true) { let x = 2; };",
+                vec!["1行24列のlet文は同じスコープの識別子xを再宣言しています。"],
+            ),
+            // 仮引数も関数本体と同じスコープで宣言される
+            (
+                "fn(a, ...rest) { let rest = 1; let [a, b] = rest; };",
+                vec![
+                    "1行18列のlet文は同じスコープの識別子restを再宣言しています。",
+                    "1行32列のlet文は同じスコープの識別子aを再宣言しています。",
+                ],
+            ),
+            (
+                "for (let i = 0; i < 3; i = i + 1) { let i = 5; }",
+                vec!["1行37列のlet文は同じスコープの識別子iを再宣言しています。"],
+            ),
+            (
+                "for (x in [1]) { let x = 2; }",
+                vec!["1行18列のlet文は同じスコープの識別子xを再宣言しています。"],
+            ),
+        ];
+        for (input, expect) in tests.iter() {
+            assert_eq!(shadowed(input), *expect, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_shadowing_in_nested_scope() {
+        let inputs = [
+            "let x = 1; let f = fn() { let x = 2; x; };",
+            "let x = 1; let f = fn(x) { x; };",
+            "let i = 0; for (let i = 0; i < 3; i = i + 1) {}",
+            "let x = 1; for (x in [1, 2]) { let y = x; }",
+            // 代入は宣言ではない
+            "let x = 1; x = 2;",
+            "let f = fn() { let y = 1; }; let g = fn() { let y = 2; };",
+        ];
+        for input in inputs.iter() {
+            assert!(shadowed(input).is_empty(), "{}", input);
+        }
+    }
+}
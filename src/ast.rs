@@ -1,5 +1,7 @@
-use std::fmt::Write;
+use std::fmt;
+use std::hash::{Hash, Hasher};
 
+use crate::lexer::escape_string;
 use crate::token::Token;
 
 /// ノード
@@ -9,7 +11,7 @@ pub trait Node: ToString {
 }
 
 /// 文用のノード
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
 pub enum Statement {
     // ここにStatementに関する構造体を定義していく
     ExpressionStatement {
@@ -23,9 +25,23 @@ pub enum Statement {
     LetStatement {
         // Token::LET
         token: Token,
+        // 束縛対象の変数名、Expression::IdentifierかExpression::ArrayPatternのみ
         name: Box<Expression>,
-        // 束縛対象の変数名、Expression::Identifierのみ
-        value: Box<Expression>, // 束縛する対象
+        // 束縛する対象
+        value: Box<Expression>,
+        // 直前の行に書かれたドキュメント用のコメント
+        doc: Option<String>,
+    },
+    /// 代入文用のノード
+    /// <name> = <value>;
+    /// 新しく束縛せずに、すでに束縛されている一番内側の識別子の値を置き換える。
+    AssignStatement {
+        // Token::ASSIGN
+        token: Token,
+        // 代入先のExpression::Identifier
+        name: Box<Expression>,
+        // 代入する値
+        value: Box<Expression>,
     },
     /// return文用のノード
     /// <token> <return_value>;
@@ -33,57 +49,167 @@ pub enum Statement {
     ReturnStatement {
         // Token::Return
         token: Token,
-        return_value: Box<Expression>, // 戻り値
+        // 戻り値
+        return_value: Box<Expression>,
     },
     /// 波括弧の中にあるいくつかの式の集まり
     BlockStatement {
         token: Token,
         statements: Vec<Box<Statement>>,
     },
+    /// do-while文用のノード
+    /// do <body> while (<condition>);
+    DoWhileStatement {
+        // Token::DO
+        token: Token,
+        // 繰り返す本体。Statement::BlockStatementのこと
+        body: Box<Statement>,
+        // 本体を評価した後に判定する条件
+        condition: Box<Expression>,
+    },
+    /// for文用のノード
+    /// for (<init>; <condition>; <update>) <body>
+    ForStatement {
+        // Token::FOR
+        token: Token,
+        // 最初に一度だけ評価するlet文か代入文か式文
+        init: Option<Box<Statement>>,
+        // 本体を評価する前に毎回判定する条件。省略すると常に繰り返す
+        condition: Option<Box<Expression>>,
+        // 本体を評価した後に毎回評価する代入文か式文
+        update: Option<Box<Statement>>,
+        // 繰り返す本体。Statement::BlockStatementのこと
+        body: Box<Statement>,
+    },
+    /// 要素を順に取り出すfor文用のノード
+    /// for (<variable> in <iterable>) <body>
+    ForInStatement {
+        // Token::FOR
+        token: Token,
+        // 要素を束縛するExpression::Identifier
+        variable: Box<Expression>,
+        // 要素を取り出す配列や文字列の式
+        iterable: Box<Expression>,
+        // 繰り返す本体。Statement::BlockStatementのこと
+        body: Box<Statement>,
+    },
+    /// break文用のノード
+    /// 一番内側のループを抜ける
+    BreakStatement { token: Token },
+    /// continue文用のノード
+    /// 一番内側のループの本体の残りを飛ばして次の繰り返しに進む
+    ContinueStatement { token: Token },
+    /// import文用のノード
+    /// import "<path>";
+    ImportStatement {
+        // Token::IMPORT
+        token: Token,
+        // 読み込むファイルのパス
+        path: String,
+    },
 }
 
-impl ToString for Statement {
-    fn to_string(&self) -> String {
-        let mut s = "".to_string();
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Statement::LetStatement { token, name, value } => {
-                write!(s, "{}", token.get_literal() + " ").unwrap();
-                write!(s, "{}", name.to_string()).unwrap();
+            Statement::LetStatement {
+                token,
+                name,
+                value,
+                doc: _,
+            } => {
+                write!(f, "{} ", token.literal())?;
+                write!(f, "{}", name)?;
                 let v = value.to_string();
-                if v != "".to_string() {
-                    write!(s, " {} {}", "=", &v).unwrap();
+                if !v.is_empty() {
+                    write!(f, " = {}", &v)?;
                 }
-                write!(s, "{}", ";").unwrap();
+                write!(f, ";")?;
+            }
+            Statement::AssignStatement { token, name, value } => {
+                write!(f, "{} {} {};", name, token.literal(), value)?;
             }
             Statement::ReturnStatement {
                 token,
                 return_value,
             } => {
-                write!(s, "{}", token.get_literal() + " ").unwrap();
+                write!(f, "{} ", token.literal())?;
                 let v = return_value.to_string();
-                if v != "".to_string() {
-                    write!(s, " {} {}", " =", &v).unwrap();
+                if !v.is_empty() {
+                    write!(f, "  = {}", &v)?;
                 }
-                write!(s, "{}", ";").unwrap();
+                write!(f, ";")?;
             }
             Statement::ExpressionStatement {
                 token: _,
                 expression,
             } => {
-                write!(s, "{};", expression.to_string()).unwrap();
+                write!(f, "{};", expression)?;
             }
             Statement::BlockStatement {
                 token: _,
                 statements,
             } => {
-                write!(s, "{{").unwrap();
-                for stmt in statements.into_iter() {
-                    write!(s, "{}", stmt.to_string()).unwrap();
+                write!(f, "{{")?;
+                for stmt in statements.iter() {
+                    write!(f, "{}", stmt)?;
                 }
-                write!(s, "}}").unwrap();
+                write!(f, "}}")?;
+            }
+            Statement::DoWhileStatement {
+                token,
+                body,
+                condition,
+            } => {
+                write!(f, "{}{}", token.literal(), body)?;
+                write!(f, "while {};", condition)?;
+            }
+            Statement::ForStatement {
+                token,
+                init,
+                condition,
+                update,
+                body,
+            } => {
+                write!(f, "{} (", token.literal())?;
+                match init {
+                    Some(init) => write!(f, "{} ", init)?,
+                    None => write!(f, "; ")?,
+                }
+                if let Some(condition) = condition {
+                    write!(f, "{}", condition)?;
+                }
+                write!(f, ";")?;
+                if let Some(update) = update {
+                    // 更新の文は閉じ括弧の前なのでセミコロンを付けない
+                    let update = update.to_string();
+                    write!(f, " {}", update.trim_end_matches(';'))?;
+                }
+                write!(f, "){}", body)?;
+            }
+            Statement::ForInStatement {
+                token,
+                variable,
+                iterable,
+                body,
+            } => {
+                write!(
+                    f,
+                    "{} ({} in {}){}",
+                    token.literal(),
+                    variable,
+                    iterable,
+                    body
+                )?;
+            }
+            Statement::BreakStatement { token } | Statement::ContinueStatement { token } => {
+                write!(f, "{};", token.literal())?;
+            }
+            Statement::ImportStatement { token, path } => {
+                write!(f, "{} {};", token.literal(), escape_string(path))?;
             }
         }
-        return s;
+        return Ok(());
     }
 }
 
@@ -94,6 +220,12 @@ impl Node for Statement {
                 token,
                 name: _,
                 value: _,
+                doc: _,
+            } => token.get_literal(),
+            Statement::AssignStatement {
+                token,
+                name: _,
+                value: _,
             } => token.get_literal(),
             Statement::ReturnStatement {
                 token,
@@ -107,6 +239,27 @@ impl Node for Statement {
                 token,
                 statements: _,
             } => token.get_literal(),
+            Statement::DoWhileStatement {
+                token,
+                body: _,
+                condition: _,
+            } => token.get_literal(),
+            Statement::ForStatement {
+                token,
+                init: _,
+                condition: _,
+                update: _,
+                body: _,
+            } => token.get_literal(),
+            Statement::ForInStatement {
+                token,
+                variable: _,
+                iterable: _,
+                body: _,
+            } => token.get_literal(),
+            Statement::BreakStatement { token } => token.get_literal(),
+            Statement::ContinueStatement { token } => token.get_literal(),
+            Statement::ImportStatement { token, path: _ } => token.get_literal(),
         }
     }
 
@@ -116,11 +269,17 @@ impl Node for Statement {
                 token,
                 name: _,
                 value: _,
+                doc: _,
             } => token,
             Statement::ExpressionStatement {
                 token,
                 expression: _,
             } => token,
+            Statement::AssignStatement {
+                token,
+                name: _,
+                value: _,
+            } => token,
             Statement::ReturnStatement {
                 token,
                 return_value: _,
@@ -129,25 +288,54 @@ impl Node for Statement {
                 token,
                 statements: _,
             } => token,
+            Statement::DoWhileStatement {
+                token,
+                body: _,
+                condition: _,
+            } => token,
+            Statement::ForStatement {
+                token,
+                init: _,
+                condition: _,
+                update: _,
+                body: _,
+            } => token,
+            Statement::ForInStatement {
+                token,
+                variable: _,
+                iterable: _,
+                body: _,
+            } => token,
+            Statement::BreakStatement { token } => token,
+            Statement::ContinueStatement { token } => token,
+            Statement::ImportStatement { token, path: _ } => token,
         };
         return tok.clone();
     }
 }
 
 /// 式用のノード
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
 pub enum Expression {
     // ここにExpressionに関する構造体を定義していく
     /// 識別子を表すノード
     Identifier {
-        token: Token,
         // Token::Ident
-        value: String, // 識別子が保持する値
+        token: Token,
+        // 識別子が保持する値
+        value: String,
     },
     /// 整数リテラル用のノード
     IntegerLiteral { token: Token, value: i64 },
+    /// 浮動小数点数リテラル用のノード
+    /// `f64`は`Eq`と`Hash`を実装しないので、値はパーサーが読めることを確かめた字句のまま持つ。
+    FloatLiteral { token: Token, value: String },
     /// 真偽値リテラル用のノード
     BooleanLiteral { token: Token, value: bool },
+    /// 文字列リテラル用のノード
+    StringLiteral { token: Token, value: String },
+    /// nullリテラル用のノード
+    NullLiteral { token: Token },
     /// 関数リテラル用のノード
     FunctionLiteral {
         token: Token,
@@ -155,6 +343,8 @@ pub enum Expression {
         parameters: Vec<Box<Expression>>,
         // 関数本体。Statement::BlockStatementのこと
         body: Statement,
+        // `let <name> = fn...`で束縛したときの名前。無名関数ならNone
+        name: Option<String>,
     },
     /// 前置演算子式用のノード
     PrefixExpression {
@@ -184,7 +374,43 @@ pub enum Expression {
         // Statement::BlockStatementでStatementの集まりを表す。
         consequence: Box<Statement>,
         // Else節。Statement::BlockStatementでStatementの集まりを表す。
-        alternative: Box<Option<Statement>>,
+        alternative: Option<Box<Statement>>,
+    },
+    /// switch式用のノード
+    /// switch (<subject>) { <pattern> => <value>, ..., _ => <default> }
+    SwitchExpression {
+        // 'switch'トークン
+        token: Token,
+        // 分岐の値と比べる式
+        subject: Box<Expression>,
+        // 上から順に比べる値と、一致したときに評価する式の組
+        arms: Vec<(Box<Expression>, Box<Expression>)>,
+        // `_`の分岐で評価する式
+        default: Option<Box<Expression>>,
+    },
+    /// 配列リテラル用のノード
+    ArrayLiteral {
+        // '['トークン
+        token: Token,
+        elements: Vec<Box<Expression>>,
+    },
+    /// ハッシュリテラル用のノード
+    /// {<key>: <value>, <key>: <value>, ...}
+    HashLiteral {
+        // '{'トークン
+        token: Token,
+        // 表示が安定するように書かれた順にキーと値の組を並べて持つ
+        pairs: Vec<(Box<Expression>, Box<Expression>)>,
+    },
+    /// let文で配列を分解して束縛するためのパターン
+    /// [<name>, <name>, ...<rest>]
+    ArrayPattern {
+        // '['トークン
+        token: Token,
+        // 先頭から順に束縛するExpression::Identifierの集まり
+        names: Vec<Box<Expression>>,
+        // 残りの要素を配列として束縛するExpression::Identifier
+        rest: Option<Box<Expression>>,
     },
     /// 関数呼び出し式用のノード
     CallExpression {
@@ -194,43 +420,92 @@ pub enum Expression {
         function: Box<Expression>,
         arguments: Vec<Box<Expression>>,
     },
+    /// 配列を展開する式用のノード
+    /// 関数呼び出しの引数では配列の要素を引数として渡し、関数の最後の引数では残りの引数を配列として受け取る。
+    /// ...<value>
+    SpreadExpression {
+        // '...'トークン
+        token: Token,
+        // 展開する式。関数の引数ではExpression::Identifier
+        value: Box<Expression>,
+    },
+    /// 添字式用のノード
+    /// <left>[<index>]
+    IndexExpression {
+        // '['トークン
+        token: Token,
+        // 添字で要素を取り出す対象の式
+        left: Box<Expression>,
+        index: Box<Expression>,
+    },
+    /// メンバー参照式用のノード
+    /// 今はハッシュのフィールドを取り出すのに使い、構造体を追加するときにもこの式で参照する。
+    /// <object>.<property>
+    MemberExpression {
+        // '.'トークン
+        token: Token,
+        // メンバーを取り出す対象の式
+        object: Box<Expression>,
+        // メンバー名のExpression::Identifier
+        property: Box<Expression>,
+    },
+    /// 範囲リテラル用のノード
+    /// 始まりを含み終わりを含まない整数の範囲をあらわす
+    /// <start>..<end>
+    RangeLiteral {
+        // '..'トークン
+        token: Token,
+        // 範囲の始まりの式
+        start: Box<Expression>,
+        // 範囲の終わりの式
+        end: Box<Expression>,
+    },
 }
 
-impl ToString for Expression {
-    fn to_string(&self) -> String {
-        let mut s = "".to_string();
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Expression::Identifier { token: _, value } => {
-                write!(s, "{}", value).unwrap();
+                write!(f, "{}", value)?;
             }
             Expression::IntegerLiteral { token: _, value } => {
-                write!(s, "{}", value).unwrap();
+                write!(f, "{}", value)?;
+            }
+            Expression::FloatLiteral { token: _, value } => {
+                write!(f, "{}", value)?;
             }
             Expression::BooleanLiteral { token: _, value } => {
-                write!(s, "{}", value).unwrap();
+                write!(f, "{}", value)?;
+            }
+            Expression::StringLiteral { token: _, value } => {
+                write!(f, "{}", escape_string(value))?;
+            }
+            Expression::NullLiteral { token } => {
+                write!(f, "{}", token.literal())?;
             }
             Expression::FunctionLiteral {
                 token,
                 parameters,
                 body,
+                name: _,
             } => {
-                write!(s, "{}(", token.get_literal()).unwrap();
-                for (i, parameter) in parameters.into_iter().enumerate() {
+                write!(f, "{}(", token.literal())?;
+                for (i, parameter) in parameters.iter().enumerate() {
                     if i == 0 {
-                        write!(s, "{}", parameter.to_string()).unwrap();
+                        write!(f, "{}", parameter)?;
                     } else {
-                        write!(s, ", {}", parameter.to_string()).unwrap();
+                        write!(f, ", {}", parameter)?;
                     }
                 }
-                write!(s, ")").unwrap();
-                write!(s, "{}", body.to_string()).unwrap();
+                write!(f, ")")?;
+                write!(f, "{}", body)?;
             }
             Expression::PrefixExpression {
                 token: _,
                 operator,
                 right_exp,
             } => {
-                write!(s, "({}{})", operator, right_exp.to_string()).unwrap();
+                write!(f, "({}{})", operator, right_exp)?;
             }
             Expression::InfixExpression {
                 token: _,
@@ -238,14 +513,7 @@ impl ToString for Expression {
                 left_exp,
                 right_exp,
             } => {
-                write!(
-                    s,
-                    "({} {} {})",
-                    left_exp.to_string(),
-                    operator,
-                    right_exp.to_string()
-                )
-                .unwrap();
+                write!(f, "({} {} {})", left_exp, operator, right_exp)?;
             }
             Expression::IfExpression {
                 token: _,
@@ -253,9 +521,29 @@ impl ToString for Expression {
                 consequence,
                 alternative,
             } => {
-                write!(s, "if {}{}", condition.to_string(), consequence.to_string()).unwrap();
-                if let Some(ref alt) = **alternative {
-                    write!(s, " else{}", alt.to_string()).unwrap();
+                write!(f, "if {}{}", condition, consequence)?;
+                if let Some(alt) = alternative {
+                    write!(f, " else{}", alt)?;
+                }
+            }
+            Expression::SwitchExpression {
+                token,
+                subject,
+                arms,
+                default,
+            } => {
+                let mut elems: Vec<String> = arms
+                    .iter()
+                    .map(|(pattern, value)| format!("{} => {}", pattern, value))
+                    .collect();
+                if let Some(default) = default {
+                    elems.push(format!("_ => {}", default));
+                }
+                write!(f, "{} {} ", token.literal(), subject)?;
+                if elems.is_empty() {
+                    write!(f, "{{}}")?;
+                } else {
+                    write!(f, "{{ {} }}", elems.join(", "))?;
                 }
             }
             Expression::CallExpression {
@@ -263,19 +551,75 @@ impl ToString for Expression {
                 function,
                 arguments,
             } => {
-                write!(s, "{}", function.to_string()).unwrap();
-                write!(s, "(").unwrap();
-                for (i, arg) in arguments.into_iter().enumerate() {
+                write!(f, "{}", function)?;
+                write!(f, "(")?;
+                for (i, arg) in arguments.iter().enumerate() {
+                    if i == 0 {
+                        write!(f, "{}", arg)?;
+                    } else {
+                        write!(f, ", {}", arg)?;
+                    }
+                }
+                write!(f, ")")?;
+            }
+            Expression::IndexExpression {
+                token: _,
+                left,
+                index,
+            } => {
+                write!(f, "({}[{}])", left, index)?;
+            }
+            Expression::MemberExpression {
+                token: _,
+                object,
+                property,
+            } => {
+                write!(f, "({}.{})", object, property)?;
+            }
+            Expression::RangeLiteral {
+                token: _,
+                start,
+                end,
+            } => {
+                write!(f, "({}..{})", start, end)?;
+            }
+            Expression::SpreadExpression { token: _, value } => {
+                write!(f, "...{}", value)?;
+            }
+            Expression::ArrayLiteral { token: _, elements } => {
+                write!(f, "[")?;
+                for (i, element) in elements.iter().enumerate() {
                     if i == 0 {
-                        write!(s, "{}", arg.to_string()).unwrap();
+                        write!(f, "{}", element)?;
                     } else {
-                        write!(s, ", {}", arg.to_string()).unwrap();
+                        write!(f, ", {}", element)?;
+                    }
+                }
+                write!(f, "]")?;
+            }
+            Expression::ArrayPattern {
+                token: _,
+                names,
+                rest,
+            } => {
+                let mut elems: Vec<String> = names.iter().map(|n| n.to_string()).collect();
+                if let Some(rest) = rest {
+                    elems.push(format!("...{}", rest));
+                }
+                write!(f, "[{}]", elems.join(", "))?;
+            }
+            Expression::HashLiteral { token: _, pairs } => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in pairs.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
                     }
+                    write!(f, "{}: {}", key, value)?;
                 }
-                write!(s, ")").unwrap();
+                write!(f, "}}")?;
             }
         }
-        return s;
+        return Ok(());
     }
 }
 
@@ -284,11 +628,15 @@ impl Node for Expression {
         match self {
             Expression::Identifier { token, value: _ } => token.get_literal(),
             Expression::IntegerLiteral { token, value: _ } => token.get_literal(),
+            Expression::FloatLiteral { token, value: _ } => token.get_literal(),
             Expression::BooleanLiteral { token, value: _ } => token.get_literal(),
+            Expression::StringLiteral { token, value: _ } => token.get_literal(),
+            Expression::NullLiteral { token } => token.get_literal(),
             Expression::FunctionLiteral {
                 token,
                 parameters: _,
                 body: _,
+                name: _,
             } => token.get_literal(),
             Expression::PrefixExpression {
                 token,
@@ -307,11 +655,40 @@ impl Node for Expression {
                 consequence: _,
                 alternative: _,
             } => token.get_literal(),
+            Expression::SwitchExpression {
+                token,
+                subject: _,
+                arms: _,
+                default: _,
+            } => token.get_literal(),
             Expression::CallExpression {
                 token,
                 function: _,
                 arguments: _,
             } => token.get_literal(),
+            Expression::ArrayLiteral { token, elements: _ } => token.get_literal(),
+            Expression::HashLiteral { token, pairs: _ } => token.get_literal(),
+            Expression::ArrayPattern {
+                token,
+                names: _,
+                rest: _,
+            } => token.get_literal(),
+            Expression::IndexExpression {
+                token,
+                left: _,
+                index: _,
+            } => token.get_literal(),
+            Expression::MemberExpression {
+                token,
+                object: _,
+                property: _,
+            } => token.get_literal(),
+            Expression::RangeLiteral {
+                token,
+                start: _,
+                end: _,
+            } => token.get_literal(),
+            Expression::SpreadExpression { token, value: _ } => token.get_literal(),
         }
     }
 
@@ -319,11 +696,15 @@ impl Node for Expression {
         let tok = match self {
             Expression::Identifier { token, value: _ } => token,
             Expression::IntegerLiteral { token, value: _ } => token,
+            Expression::FloatLiteral { token, value: _ } => token,
             Expression::BooleanLiteral { token, value: _ } => token,
+            Expression::StringLiteral { token, value: _ } => token,
+            Expression::NullLiteral { token } => token,
             Expression::FunctionLiteral {
                 token,
                 parameters: _,
                 body: _,
+                name: _,
             } => token,
             Expression::PrefixExpression {
                 token,
@@ -342,11 +723,40 @@ impl Node for Expression {
                 consequence: _,
                 alternative: _,
             } => token,
+            Expression::SwitchExpression {
+                token,
+                subject: _,
+                arms: _,
+                default: _,
+            } => token,
             Expression::CallExpression {
                 token,
                 function: _,
                 arguments: _,
             } => token,
+            Expression::ArrayLiteral { token, elements: _ } => token,
+            Expression::HashLiteral { token, pairs: _ } => token,
+            Expression::ArrayPattern {
+                token,
+                names: _,
+                rest: _,
+            } => token,
+            Expression::IndexExpression {
+                token,
+                left: _,
+                index: _,
+            } => token,
+            Expression::MemberExpression {
+                token,
+                object: _,
+                property: _,
+            } => token,
+            Expression::RangeLiteral {
+                token,
+                start: _,
+                end: _,
+            } => token,
+            Expression::SpreadExpression { token, value: _ } => token,
         };
         return tok.clone();
     }
@@ -354,16 +764,22 @@ impl Node for Expression {
 
 impl Expression {
     /// 保持する値のゲッター
+    /// 識別子とリテラルはその値、前置・中置演算子式は演算子、関数呼び出しは呼び出す関数、展開は展開する式の値を返す。
+    /// それ以外の複合的な式は空文字ではなく式全体の文字列表現を返す。
     pub fn get_value(&self) -> String {
         match self {
             Expression::Identifier { token: _, value } => value.to_string(),
             Expression::IntegerLiteral { token: _, value } => format!("{}", value),
+            Expression::FloatLiteral { token: _, value } => value.to_string(),
             Expression::BooleanLiteral { token: _, value } => format!("{}", value),
+            Expression::StringLiteral { token: _, value } => value.to_string(),
+            Expression::NullLiteral { token } => token.get_literal(),
             Expression::FunctionLiteral {
                 token: _,
                 parameters: _,
                 body: _,
-            } => "".to_string(),
+                name: _,
+            } => self.to_string(),
             Expression::PrefixExpression {
                 token: _,
                 operator,
@@ -380,29 +796,92 @@ impl Expression {
                 condition: _,
                 consequence: _,
                 alternative: _,
-            } => "".to_string(),
+            } => self.to_string(),
+            Expression::SwitchExpression {
+                token: _,
+                subject: _,
+                arms: _,
+                default: _,
+            } => self.to_string(),
             Expression::CallExpression {
                 token: _,
                 function,
                 arguments: _,
             } => function.to_string(),
+            Expression::ArrayLiteral {
+                token: _,
+                elements: _,
+            } => self.to_string(),
+            Expression::HashLiteral { token: _, pairs: _ } => self.to_string(),
+            Expression::ArrayPattern {
+                token: _,
+                names: _,
+                rest: _,
+            } => self.to_string(),
+            Expression::IndexExpression {
+                token: _,
+                left: _,
+                index: _,
+            } => self.to_string(),
+            Expression::MemberExpression {
+                token: _,
+                object: _,
+                property: _,
+            } => self.to_string(),
+            Expression::RangeLiteral {
+                token: _,
+                start: _,
+                end: _,
+            } => self.to_string(),
+            Expression::SpreadExpression { token: _, value } => value.get_value(),
         }
     }
 }
 
+/// ソースコード上の範囲
+/// 開始位置と終了位置はどちらも範囲に含まれる文字の(行, 列)で表す。
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub struct Span {
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+}
+
 /// Monkeyプログラムをあらわす構造体
 #[derive(Debug)]
 pub struct Program {
     pub statements: Vec<Statement>,
+    // 最初の文の始まりから最後の文の終わりまでの範囲
+    span: Option<Span>,
+}
+
+/// 位置の違いは無視して文の並びだけで比較する
+impl PartialEq for Program {
+    fn eq(&self, other: &Self) -> bool {
+        return self.statements == other.statements;
+    }
 }
 
-impl ToString for Program {
-    fn to_string(&self) -> String {
-        let mut s = "".to_string();
+impl Eq for Program {}
+
+/// 比較と同じく文の並びだけでハッシュ値を計算する
+impl Hash for Program {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.statements.hash(state);
+    }
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for stmt in self.statements.iter() {
-            write!(s, "{}", stmt.to_string()).unwrap();
+            write!(f, "{}", stmt)?;
         }
-        return s;
+        return Ok(());
+    }
+}
+
+impl Default for Program {
+    fn default() -> Self {
+        return Program::new();
     }
 }
 
@@ -411,12 +890,34 @@ impl Program {
     pub fn new() -> Program {
         return Program {
             statements: Vec::new(),
+            span: None,
         };
     }
 
+    /// ソースコード上の範囲と一緒に文を追加する関数
+    pub fn push_statement(&mut self, statement: Statement, span: Span) {
+        self.span = match self.span {
+            Some(s) => Some(Span {
+                start: s.start,
+                end: span.end,
+            }),
+            None => Some(span),
+        };
+        self.statements.push(statement);
+    }
+
+    /// 最初の文の始まりから最後の文の終わりまでの範囲を返す関数
+    /// 文がなければNoneを返す。
+    pub fn span(&self) -> Option<Span> {
+        if self.statements.is_empty() {
+            return None;
+        }
+        return self.span;
+    }
+
     /// ルートノードであるprogramノードのリテラルを返す
     pub fn token_literal(&self) -> String {
-        if self.statements.len() > 0 {
+        if !self.statements.is_empty() {
             return self.statements[0].token_literal();
         } else {
             return "".to_string();
@@ -426,7 +927,11 @@ impl Program {
 
 #[cfg(test)]
 mod test {
+    use std::collections::HashSet;
+
     use crate::ast::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
     use crate::token::*;
 
     #[test]
@@ -442,8 +947,133 @@ mod test {
                     token: Token::new(TokenType::IDENT, "anotherVar"),
                     value: "anotherVar".to_string(),
                 }),
+                doc: None,
             }],
+            span: None,
         };
         assert_eq!(program.to_string(), "let myVar = anotherVar;".to_string());
     }
+
+    #[test]
+    fn test_display_every_node() {
+        let tests = [
+            // (input, expect)
+            ("let x = 5;", "let x = 5;"),
+            ("x = x + 1;", "x = (x + 1);"),
+            ("return 10;", "return   = 10;"),
+            (
+                "do { x = x - 1; } while (x > 0);",
+                "do{x = (x - 1);}while (x > 0);",
+            ),
+            (
+                "for (let i = 0; i < 3; i = i + 1) { puts(i); };",
+                "for (let i = 0; (i < 3); i = (i + 1)){puts(i);}",
+            ),
+            ("for (; ; ) { break; };", "for (; ;){break;}"),
+            (
+                "for (c in \"ab\") { continue; };",
+                "for (c in \"ab\"){continue;}",
+            ),
+            ("import \"lib/math.mk\";", "import \"lib/math.mk\";"),
+            ("fn(a, b, ...rest) { a; };", "fn(a, b, ...rest){a;};"),
+            ("if (a) { 1; } else { 2; };", "if a{1;} else{2;};"),
+            (
+                "switch (x) { 1 => \"one\", _ => null };",
+                "switch x { 1 => \"one\", _ => null };",
+            ),
+            ("switch (x) {};", "switch x {};"),
+            ("add(1, -2, !true);", "add(1, (-2), (!true));"),
+            ("arr[1 + 2];", "(arr[(1 + 2)]);"),
+            ("h.key.sub;", "((h.key).sub);"),
+            ("0..n;", "(0..n);"),
+            ("[1, \"a\\\"b\", [2]];", "[1, \"a\\\"b\", [2]];"),
+            ("let [a, ...b] = xs;", "let [a, ...b] = xs;"),
+            ("{\"k\": 1, 2: false};", "{\"k\": 1, 2: false};"),
+            ("{};", "{};"),
+        ];
+        for (input, expect) in tests.iter() {
+            let mut parser = Parser::new(Lexer::new(input));
+            let program = parser.parse_program().expect("fail parse program.");
+            assert_eq!(&program.to_string(), expect);
+            assert_eq!(&format!("{}", program), expect);
+        }
+    }
+
+    #[test]
+    fn test_empty_block_to_string() {
+        // 空のブロックは間に空白を入れずに表示する
+        let tests = [
+            ("if (x) {};", "if x{};"),
+            ("if (x) {} else {};", "if x{} else{};"),
+            ("fn() {};", "fn(){};"),
+            ("let f = fn(x) {};", "let f = fn(x){};"),
+            ("do {} while (x);", "do{}while x;"),
+        ];
+        for (input, expect) in tests.iter() {
+            let mut parser = Parser::new(Lexer::new(input));
+            let program = parser.parse_program().expect("fail parse program.");
+            assert_eq!(&program.to_string(), expect);
+        }
+    }
+
+    #[test]
+    fn test_program_hash() {
+        let parse = |input: &str| Parser::new(Lexer::new(input)).parse_program().unwrap();
+        let mut set = HashSet::new();
+        set.insert(parse("let x = 1 + 2; x;"));
+        // 空白や位置が違っても同じ構造なら同じプログラムとして扱う
+        set.insert(parse("let x=1+2;\n  x;"));
+        assert_eq!(set.len(), 1);
+        set.insert(parse("let x = 1 + 3; x;"));
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&parse("let   x = 1 + 2 ; x ;")));
+    }
+
+    #[test]
+    fn test_program_span() {
+        let mut parser = Parser::new(Lexer::new("  \n"));
+        let program = parser.parse_program().expect("fail parse program.");
+        assert_eq!(program.span(), None);
+
+        let input = "  let x = 5;\nfn(a) {\n  a;\n}(x);";
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = parser.parse_program().expect("fail parse program.");
+        assert_eq!(
+            program.span(),
+            Some(Span {
+                start: (1, 3),
+                end: (4, 5),
+            })
+        );
+    }
+
+    #[test]
+    fn test_get_value() {
+        let tests = [
+            // (input, expect)
+            ("x;", "x"),
+            ("5;", "5"),
+            ("true;", "true"),
+            ("null;", "null"),
+            ("-a;", "-"),
+            ("a + b;", "+"),
+            ("add(1, 2);", "add"),
+            ("[1, 2];", "[1, 2]"),
+            ("fn(x, y) { x + y; };", "fn(x, y){(x + y);}"),
+            ("if (x) { 1; } else { 2; };", "if x{1;} else{2;}"),
+        ];
+        for (input, expect) in tests.iter() {
+            let mut parser = Parser::new(Lexer::new(input));
+            let program = parser.parse_program().expect("fail parse program.");
+            if let Statement::ExpressionStatement {
+                token: _,
+                expression,
+            } = &program.statements[0]
+            {
+                assert_eq!(expression.get_value(), *expect);
+            } else {
+                panic!("入力が式文ではありません。{}", input);
+            }
+        }
+    }
 }
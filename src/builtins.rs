@@ -0,0 +1,371 @@
+use std::io::Write;
+
+use crate::object::{BuiltinContext, Object};
+
+/// 言語に組み込まれた関数を名前から探す関数
+/// 環境に同じ名前の束縛があるときは環境の束縛が優先される。
+pub fn lookup(name: &str) -> Option<Object> {
+    let function = match name {
+        "len" => len,
+        "error" => error,
+        "assert" => assert,
+        "assert_eq" => assert_eq,
+        "split" => split,
+        "join" => join,
+        "type" => type_of,
+        "int" => int,
+        "str" => str,
+        "bool" => bool,
+        "hex" => hex,
+        "bin" => bin,
+        "set" => set,
+        "delete" => delete,
+        "arity" => arity,
+        "puts" => puts,
+        "eputs" => eputs,
+        _ => return None,
+    };
+    return Some(Object::Builtin {
+        name: name.to_string(),
+        function,
+    });
+}
+
+/// 引数の数が期待通りでないときのエラーを生成する関数
+fn wrong_number_of_arguments(got: usize, want: usize) -> Object {
+    return Object::error(&format!(
+        "wrong number of arguments. got={}, want={}",
+        got, want
+    ));
+}
+
+/// 文字列の文字数や配列とハッシュの要素数を返す組み込み関数
+fn len(_ctx: &mut BuiltinContext, args: &[Object]) -> Object {
+    if args.len() != 1 {
+        return wrong_number_of_arguments(args.len(), 1);
+    }
+    match &args[0] {
+        Object::Str { value } => Object::integer(value.chars().count() as i64),
+        Object::Array { elements } => Object::integer(elements.len() as i64),
+        Object::Hash { pairs } => Object::integer(pairs.len() as i64),
+        other => Object::error(&format!(
+            "argument to `len` not supported, got {}",
+            other.get_type().to_string()
+        )),
+    }
+}
+
+/// 文字列をメッセージとするエラーを返す組み込み関数
+/// スクリプトから独自のエラーを起こすために使う。
+fn error(_ctx: &mut BuiltinContext, args: &[Object]) -> Object {
+    if args.len() != 1 {
+        return wrong_number_of_arguments(args.len(), 1);
+    }
+    match &args[0] {
+        Object::Str { value } => Object::error(value),
+        other => Object::error(&format!(
+            "argument to `error` must be STRING, got {}",
+            other.get_type().to_string()
+        )),
+    }
+}
+
+/// 引数が真ならNULLを、偽ならエラーを返す組み込み関数
+fn assert(_ctx: &mut BuiltinContext, args: &[Object]) -> Object {
+    if args.len() != 1 {
+        return wrong_number_of_arguments(args.len(), 1);
+    }
+    if args[0].is_truthy() {
+        return Object::NULL;
+    }
+    return Object::error("assertion failed");
+}
+
+/// 2つの引数が等しければNULLを、異なればそれぞれの値を含むエラーを返す組み込み関数
+fn assert_eq(_ctx: &mut BuiltinContext, args: &[Object]) -> Object {
+    if args.len() != 2 {
+        return wrong_number_of_arguments(args.len(), 2);
+    }
+    if args[0] == args[1] {
+        return Object::NULL;
+    }
+    return Object::error(&format!(
+        "assertion failed: {} != {}",
+        args[0].inspect(),
+        args[1].inspect()
+    ));
+}
+
+/// 文字列を区切り文字列で分割した文字列の配列を返す組み込み関数
+/// 区切り文字列が空なら一文字ずつに分割する。分割する文字列が空なら区切り文字列が空でない限り空文字列一つの配列になる。
+fn split(_ctx: &mut BuiltinContext, args: &[Object]) -> Object {
+    if args.len() != 2 {
+        return wrong_number_of_arguments(args.len(), 2);
+    }
+    let (value, sep) = match (&args[0], &args[1]) {
+        (Object::Str { value }, Object::Str { value: sep }) => (value, sep),
+        (Object::Str { value: _ }, other) | (other, _) => {
+            return Object::error(&format!(
+                "argument to `split` must be STRING, got {}",
+                other.get_type().to_string()
+            ))
+        }
+    };
+    let elements: Vec<Object> = if sep.is_empty() {
+        value
+            .chars()
+            .map(|c| Object::Str {
+                value: c.to_string(),
+            })
+            .collect()
+    } else {
+        value
+            .split(sep.as_str())
+            .map(|part| Object::Str {
+                value: part.to_string(),
+            })
+            .collect()
+    };
+    return Object::Array { elements };
+}
+
+/// 文字列の配列を区切り文字列でつないだ文字列を返す組み込み関数
+/// 空の配列なら空文字列を返す。
+fn join(_ctx: &mut BuiltinContext, args: &[Object]) -> Object {
+    if args.len() != 2 {
+        return wrong_number_of_arguments(args.len(), 2);
+    }
+    let (elements, sep) = match (&args[0], &args[1]) {
+        (Object::Array { elements }, Object::Str { value: sep }) => (elements, sep),
+        (Object::Array { elements: _ }, other) => {
+            return Object::error(&format!(
+                "second argument to `join` must be STRING, got {}",
+                other.get_type().to_string()
+            ))
+        }
+        (other, _) => {
+            return Object::error(&format!(
+                "first argument to `join` must be ARRAY, got {}",
+                other.get_type().to_string()
+            ))
+        }
+    };
+    let mut parts: Vec<&str> = Vec::new();
+    for element in elements.iter() {
+        match element {
+            Object::Str { value } => parts.push(value),
+            other => {
+                return Object::error(&format!(
+                    "elements of `join` must be STRING, got {}",
+                    other.get_type().to_string()
+                ))
+            }
+        }
+    }
+    return Object::Str {
+        value: parts.join(sep),
+    };
+}
+
+/// 引数の型の名前を文字列で返す組み込み関数
+/// Rustでは`type`を関数名に使えないので`type_of`とする。
+fn type_of(_ctx: &mut BuiltinContext, args: &[Object]) -> Object {
+    if args.len() != 1 {
+        return wrong_number_of_arguments(args.len(), 1);
+    }
+    return Object::Str {
+        value: args[0].get_type().to_string(),
+    };
+}
+
+/// 引数を整数に変換する組み込み関数
+/// 文字列は10進数の整数として読み、真偽値はtrueを1、falseを0にする。変換できなければエラーを返す。
+/// 文字列の先頭には`+5`や`-5`のように符号を一つだけ付けられる。符号だけの文字列は変換できない。
+/// 浮動小数点数は0に向かって切り捨てる。無限大やNaN、整数で表せない大きさの数は変換できない。
+fn int(_ctx: &mut BuiltinContext, args: &[Object]) -> Object {
+    if args.len() != 1 {
+        return wrong_number_of_arguments(args.len(), 1);
+    }
+    match &args[0] {
+        Object::Integer { value } => Object::integer(*value),
+        Object::Boolean { value } => Object::integer(*value as i64),
+        // i64::MAXはf64で正確に表せないので、2の63乗未満かどうかで範囲を確かめる
+        Object::Float { value }
+            if value.trunc() >= -(2f64.powi(63)) && value.trunc() < 2f64.powi(63) =>
+        {
+            Object::integer(value.trunc() as i64)
+        }
+        Object::Float { value: _ } => Object::error(&format!(
+            "could not convert {} to INTEGER",
+            args[0].inspect()
+        )),
+        Object::Str { value } => match value.parse::<i64>() {
+            Ok(i) => Object::integer(i),
+            Err(_) => Object::error(&format!(
+                "could not convert {} to INTEGER",
+                args[0].inspect()
+            )),
+        },
+        other => Object::error(&format!(
+            "cannot convert {} to INTEGER",
+            other.get_type().to_string()
+        )),
+    }
+}
+
+/// 引数を文字列に変換する組み込み関数
+/// 文字列はそのまま返し、それ以外は表示と同じ文字列にする。
+fn str(_ctx: &mut BuiltinContext, args: &[Object]) -> Object {
+    if args.len() != 1 {
+        return wrong_number_of_arguments(args.len(), 1);
+    }
+    match &args[0] {
+        Object::Str { value: _ } => args[0].clone(),
+        other => Object::Str {
+            value: other.inspect(),
+        },
+    }
+}
+
+/// 引数を真偽値に変換する組み込み関数
+/// if式の条件と同じくnullとfalseだけが偽になるので、0や空文字列は真になる。
+fn bool(_ctx: &mut BuiltinContext, args: &[Object]) -> Object {
+    if args.len() != 1 {
+        return wrong_number_of_arguments(args.len(), 1);
+    }
+    return Object::boolean(args[0].is_truthy());
+}
+
+/// 整数を`0x`を付けた16進数の文字列にする組み込み関数
+/// 負の数は`-0xff`のように絶対値の前に符号を付ける。
+fn hex(_ctx: &mut BuiltinContext, args: &[Object]) -> Object {
+    return integer_to_string(args, "hex", Object::to_hex);
+}
+
+/// 整数を`0b`を付けた2進数の文字列にする組み込み関数
+/// 負の数は`-0b101`のように絶対値の前に符号を付ける。
+fn bin(_ctx: &mut BuiltinContext, args: &[Object]) -> Object {
+    return integer_to_string(args, "bin", Object::to_binary);
+}
+
+/// ハッシュにキーと値を追加した新しいハッシュを返す組み込み関数
+/// 元のハッシュは変更しない。すでにあるキーなら位置を保ったまま値を置き換える。
+fn set(_ctx: &mut BuiltinContext, args: &[Object]) -> Object {
+    if args.len() != 3 {
+        return wrong_number_of_arguments(args.len(), 3);
+    }
+    let pairs = match hash_and_key(args, "set") {
+        Ok(pairs) => pairs,
+        Err(err) => return err,
+    };
+    let mut pairs = pairs.to_vec();
+    match pairs.iter_mut().find(|(k, _)| *k == args[1]) {
+        Some((_, value)) => *value = args[2].clone(),
+        None => pairs.push((args[1].clone(), args[2].clone())),
+    }
+    return Object::Hash { pairs };
+}
+
+/// ハッシュからキーを取り除いた新しいハッシュを返す組み込み関数
+/// 元のハッシュは変更しない。キーがなければ同じ内容のハッシュを返す。
+fn delete(_ctx: &mut BuiltinContext, args: &[Object]) -> Object {
+    if args.len() != 2 {
+        return wrong_number_of_arguments(args.len(), 2);
+    }
+    let pairs = match hash_and_key(args, "delete") {
+        Ok(pairs) => pairs,
+        Err(err) => return err,
+    };
+    let pairs = pairs
+        .iter()
+        .filter(|(k, _)| *k != args[1])
+        .cloned()
+        .collect();
+    return Object::Hash { pairs };
+}
+
+/// 引数を一つ一行で評価器の出力に書き込む組み込み関数
+fn puts(ctx: &mut BuiltinContext, args: &[Object]) -> Object {
+    return write_lines(ctx.out, "puts", args);
+}
+
+/// 引数を一つ一行で評価器のエラー出力に書き込む組み込み関数
+fn eputs(ctx: &mut BuiltinContext, args: &[Object]) -> Object {
+    return write_lines(ctx.err, "eputs", args);
+}
+
+/// 引数を一つ一行で書き込んでnullを返す関数
+/// 文字列は引用符を付けずにそのまま、それ以外は表示と同じ文字列にする。
+fn write_lines(w: &mut dyn Write, name: &str, args: &[Object]) -> Object {
+    for arg in args {
+        let result = match arg {
+            Object::Str { value } => writeln!(w, "{}", value),
+            other => writeln!(w, "{}", other.inspect()),
+        };
+        if let Err(e) = result {
+            return Object::error(&format!("cannot write output of `{}`: {}", name, e));
+        }
+    }
+    return Object::NULL;
+}
+
+/// 関数が宣言している引数の数を返す組み込み関数
+/// 組み込み関数は引数の数を宣言していないのでエラーにする。
+fn arity(_ctx: &mut BuiltinContext, args: &[Object]) -> Object {
+    if args.len() != 1 {
+        return wrong_number_of_arguments(args.len(), 1);
+    }
+    match &args[0] {
+        Object::Function {
+            parameters,
+            body: _,
+            env: _,
+            name: _,
+        } => Object::integer(parameters.len() as i64),
+        other => Object::error(&format!(
+            "argument to `arity` must be FUNCTION, got {}",
+            other.get_type().to_string()
+        )),
+    }
+}
+
+/// ハッシュとキーを受け取る組み込み関数の引数を確かめてハッシュの中身を返す関数
+fn hash_and_key<'a>(args: &'a [Object], name: &str) -> Result<&'a [(Object, Object)], Object> {
+    let pairs = match &args[0] {
+        Object::Hash { pairs } => pairs,
+        other => {
+            return Err(Object::error(&format!(
+                "first argument to `{}` must be HASH, got {}",
+                name,
+                other.get_type().to_string()
+            )))
+        }
+    };
+    let key_type = args[1].get_type();
+    if !key_type.is_hashable() {
+        return Err(Object::error(&format!(
+            "unusable as hash key: {}",
+            key_type.to_string()
+        )));
+    }
+    return Ok(pairs);
+}
+
+/// 整数を受け取る組み込み関数の引数を確かめて文字列に変換する関数
+fn integer_to_string(
+    args: &[Object],
+    name: &str,
+    convert: fn(&Object) -> Option<String>,
+) -> Object {
+    if args.len() != 1 {
+        return wrong_number_of_arguments(args.len(), 1);
+    }
+    match convert(&args[0]) {
+        Some(value) => Object::Str { value },
+        None => Object::error(&format!(
+            "argument to `{}` must be INTEGER, got {}",
+            name,
+            args[0].get_type().to_string()
+        )),
+    }
+}
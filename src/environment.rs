@@ -0,0 +1,218 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::object::{BuiltinFunction, Object};
+
+/// 識別子とオブジェクトの束縛を管理する環境
+#[derive(PartialEq, Clone)]
+pub struct Environment {
+    // 現在のフレームの束縛
+    store: HashMap<String, Object>,
+    // 外側のスコープの環境
+    outer: Option<Rc<RefCell<Environment>>>,
+    // 埋め込む側が`with_builtins`で登録した組み込み関数の表
+    builtins: HashMap<String, BuiltinFunction>,
+}
+
+impl std::fmt::Debug for Environment {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        // 再帰関数は自身を束縛した環境を持つので束縛名だけを出力する
+        let mut names: Vec<&String> = self.store.keys().collect();
+        names.sort();
+        write!(
+            f,
+            "Environment{{store: {:?}, has_outer: {}}}",
+            names,
+            self.outer.is_some()
+        )
+    }
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        return Environment::new();
+    }
+}
+
+impl Environment {
+    /// 初期化関数
+    pub fn new() -> Self {
+        return Environment {
+            store: HashMap::new(),
+            outer: None,
+            builtins: HashMap::new(),
+        };
+    }
+
+    /// 外側の環境を持つ環境を生成する関数
+    pub fn new_enclosed(outer: Rc<RefCell<Environment>>) -> Self {
+        return Environment {
+            store: HashMap::new(),
+            outer: Some(outer),
+            builtins: HashMap::new(),
+        };
+    }
+
+    /// 組み込み関数を束縛した環境を生成する関数
+    /// 埋め込む側の関数をスクリプトから呼べるようにする。言語の組み込み関数と同じ名前なら登録した関数が優先される。
+    pub fn with_builtins(builtins: HashMap<String, BuiltinFunction>) -> Self {
+        let mut env = Environment::new();
+        for (name, function) in builtins.iter() {
+            let builtin = Object::Builtin {
+                name: name.clone(),
+                function: *function,
+            };
+            env.set(name, builtin);
+        }
+        env.builtins = builtins;
+        return env;
+    }
+
+    /// 埋め込む側が登録した組み込み関数だけを束縛した環境を生成する関数
+    /// 外側の環境に登録された組み込み関数も含め、同じ名前なら内側の環境に登録した関数を優先する。
+    /// 読み込んだファイルを評価する環境の外側に使い、読み込んだ側の束縛を見せずに組み込み関数だけを呼べるようにする。
+    pub fn builtins_only(&self) -> Environment {
+        let mut builtins = match &self.outer {
+            Some(outer) => outer.borrow().builtins_only().builtins,
+            None => HashMap::new(),
+        };
+        builtins.extend(self.builtins.clone());
+        return Environment::with_builtins(builtins);
+    }
+
+    /// 識別子に束縛されたオブジェクトを取得する関数。見つからなければ外側の環境を探す。
+    /// nullを束縛した識別子は`Some(Object::Null)`を返し、どの環境にも束縛がないときだけ`None`を返す。
+    pub fn get(&self, name: &str) -> Option<Object> {
+        match self.store.get(name) {
+            Some(obj) => Some(obj.clone()),
+            None => match &self.outer {
+                Some(outer) => outer.borrow().get(name),
+                None => None,
+            },
+        }
+    }
+
+    /// 現在のフレームに識別子を束縛する関数
+    pub fn set(&mut self, name: &str, value: Object) -> Object {
+        self.store.insert(name.to_string(), value.clone());
+        return value;
+    }
+
+    /// すでに束縛されている識別子の値を置き換える関数
+    /// 現在のフレームになければ外側の環境を探し、最初に見つかった束縛を置き換える。どこにもなければfalseを返す。
+    pub fn assign(&mut self, name: &str, value: Object) -> bool {
+        if let Some(slot) = self.store.get_mut(name) {
+            *slot = value;
+            return true;
+        }
+        match &self.outer {
+            Some(outer) => outer.borrow_mut().assign(name, value),
+            None => false,
+        }
+    }
+
+    /// 別の環境の現在のフレームの束縛をすべて現在のフレームに束縛する関数
+    /// 同じ名前の束縛があれば別の環境の値で上書きする。
+    pub fn import(&mut self, other: &Environment) {
+        for (name, value) in other.store.iter() {
+            self.set(name, value.clone());
+        }
+    }
+
+    /// 現在のフレームを複製したスナップショットを返す関数
+    /// 束縛済みの関数オブジェクトは自身の環境への参照を保持し続ける。
+    pub fn snapshot(&self) -> Environment {
+        return Environment {
+            store: self.store.clone(),
+            outer: self.outer.clone(),
+            builtins: self.builtins.clone(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::environment::Environment;
+    use crate::evaluator::Eval;
+    use crate::lexer::Lexer;
+    use crate::object::Object;
+    use crate::parser::Parser;
+
+    fn test_eval(input: &str, env: &Rc<RefCell<Environment>>) -> Object {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+
+        Eval::new(Rc::clone(env)).eval_program(&program.expect("fail parse program."))
+    }
+
+    #[test]
+    fn test_get_from_outer() {
+        let outer = Rc::new(RefCell::new(Environment::new()));
+        outer.borrow_mut().set("x", Object::Integer { value: 1 });
+        let mut inner = Environment::new_enclosed(Rc::clone(&outer));
+        inner.set("y", Object::Integer { value: 2 });
+
+        assert_eq!(inner.get("x"), Some(Object::Integer { value: 1 }));
+        assert_eq!(inner.get("y"), Some(Object::Integer { value: 2 }));
+        assert_eq!(outer.borrow().get("y"), None);
+    }
+
+    #[test]
+    fn test_get_null_binding() {
+        let outer = Rc::new(RefCell::new(Environment::new()));
+        outer.borrow_mut().set("x", Object::Null);
+        let inner = Environment::new_enclosed(Rc::clone(&outer));
+
+        assert_eq!(inner.get("x"), Some(Object::Null));
+        assert_eq!(inner.get("y"), None);
+    }
+
+    #[test]
+    fn test_assign() {
+        let outer = Rc::new(RefCell::new(Environment::new()));
+        outer.borrow_mut().set("x", Object::Integer { value: 1 });
+        let mut inner = Environment::new_enclosed(Rc::clone(&outer));
+        inner.set("y", Object::Integer { value: 2 });
+
+        // 外側の束縛を置き換え、内側には新しく束縛しない
+        assert!(inner.assign("x", Object::Integer { value: 3 }));
+        assert!(inner.assign("y", Object::Integer { value: 4 }));
+        assert_eq!(outer.borrow().get("x"), Some(Object::Integer { value: 3 }));
+        assert_eq!(inner.get("y"), Some(Object::Integer { value: 4 }));
+        assert!(!inner.assign("z", Object::Integer { value: 5 }));
+        assert_eq!(inner.get("z"), None);
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut env = Environment::new();
+        env.set("x", Object::Integer { value: 1 });
+        let snapshot = env.snapshot();
+        env.set("x", Object::Integer { value: 2 });
+        env.set("y", Object::Integer { value: 3 });
+
+        assert_eq!(snapshot.get("x"), Some(Object::Integer { value: 1 }));
+        assert_eq!(snapshot.get("y"), None);
+    }
+
+    #[test]
+    fn test_reset_keeps_closure() {
+        let env = Rc::new(RefCell::new(Environment::new()));
+        test_eval("let x = 5; let f = fn() { x; };", &env);
+        let closure = env.borrow().get("f").expect("closure is not bound.");
+
+        // リセットして空の環境にする
+        let env = Rc::new(RefCell::new(Environment::new()));
+        assert_eq!(env.borrow().get("x"), None);
+        assert_eq!(env.borrow().get("f"), None);
+
+        // リセット前に作った関数は自身の環境を保持しているので動作する
+        env.borrow_mut().set("g", closure);
+        assert_eq!(test_eval("g();", &env), Object::Integer { value: 5 });
+    }
+}
@@ -1,185 +1,1190 @@
-use crate::ast::{Expression, Program, Statement};
-use crate::object::Object;
+use std::cell::RefCell;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::rc::Rc;
 
-#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
-pub struct Eval {}
+use crate::ast::{Expression, Node, Program, Statement};
+use crate::builtins;
+use crate::environment::Environment;
+use crate::lexer::Lexer;
+use crate::numeric::Numeric;
+use crate::object::{BuiltinContext, Object};
+use crate::parser::{Parser, ParserOptions};
+
+/// 評価器の設定
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct EvalOptions {
+    /// 評価できる文と式の数の上限。Noneなら制限しない。
+    /// 信頼できないスクリプトが終わらないときにホストを止めずに評価を打ち切るために使う。
+    pub max_steps: Option<usize>,
+    /// 真偽値同士の`<`と`>`を、trueを1、falseを0とみなした整数の比較として評価するかどうか。
+    /// C言語に慣れた人向けの設定で、既定ではMonkeyと同じくエラーにする。
+    pub compare_booleans_as_integers: bool,
+    /// 整数どうしの`/`を0に向かって切り捨てた整数にするかどうか。falseなら浮動小数点数で割る。
+    /// Monkeyとの互換性のため既定では整数にする。
+    pub integer_division: bool,
+}
+
+impl EvalOptions {
+    /// 既定の設定を返す関数。`Eval::new`はこの設定を使う。
+    pub fn new() -> Self {
+        return EvalOptions {
+            max_steps: None,
+            compare_booleans_as_integers: false,
+            integer_division: true,
+        };
+    }
+}
+
+impl Default for EvalOptions {
+    fn default() -> Self {
+        return EvalOptions::new();
+    }
+}
+
+/// 評価器
+pub struct Eval {
+    // プログラム直下の文を評価する環境
+    env: Rc<RefCell<Environment>>,
+    // 評価器の設定
+    options: EvalOptions,
+    // この評価器でこれまでに評価した文と式の数
+    steps: usize,
+    // 評価中のループの入れ子の深さ。関数の本体では0から数え直す
+    loop_depth: usize,
+    // 読み込み中のファイルの絶対パス。循環したimportの検出に使う
+    importing: Vec<PathBuf>,
+    // `puts`が出力する書き込み先
+    out: Box<dyn Write>,
+    // `eputs`がエラー出力する書き込み先
+    err: Box<dyn Write>,
+}
+
+impl std::fmt::Debug for Eval {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "Eval{{options: {:?}, steps: {}, loop_depth: {}, importing: {:?}}}",
+            self.options, self.steps, self.loop_depth, self.importing
+        )
+    }
+}
 
 impl Eval {
-    pub fn eval_program(program: &Program) -> Object {
-        Self::eval_statements(&program.statements)
+    /// 初期化関数
+    /// プログラムは渡した環境で評価するので、同じ環境を渡した評価器どうしは束縛を共有する。
+    pub fn new(env: Rc<RefCell<Environment>>) -> Self {
+        return Eval::with_options(env, EvalOptions::new());
+    }
+
+    /// 設定を指定する初期化関数
+    /// 組み込み関数は標準出力と標準エラー出力に書き込む。
+    pub fn with_options(env: Rc<RefCell<Environment>>, options: EvalOptions) -> Self {
+        return Eval::with_writers(
+            env,
+            options,
+            Box::new(std::io::stdout()),
+            Box::new(std::io::stderr()),
+        );
+    }
+
+    /// 設定と組み込み関数の書き込み先を指定する初期化関数
+    /// 出力をバッファに集めればテストや埋め込み先で出力を確かめられる。
+    pub fn with_writers(
+        env: Rc<RefCell<Environment>>,
+        options: EvalOptions,
+        out: Box<dyn Write>,
+        err: Box<dyn Write>,
+    ) -> Self {
+        return Eval {
+            env,
+            options,
+            steps: 0,
+            loop_depth: 0,
+            importing: vec![],
+            out,
+            err,
+        };
+    }
+
+    /// プログラム直下の文を評価する環境を入れ替える関数
+    /// REPLの`:reset`のように、設定や書き込み先はそのままで環境だけを戻すときに使う。
+    pub fn set_env(&mut self, env: Rc<RefCell<Environment>>) {
+        self.env = env;
+    }
+
+    /// プログラムを評価する関数
+    /// プログラム直下のreturn文は評価を止め、その戻り値を一段だけ取り出して返す。
+    pub fn eval_program(&mut self, program: &Program) -> Object {
+        let env = Rc::clone(&self.env);
+        return self.eval_program_in(program, &env);
+    }
+
+    /// 指定した環境でプログラムを評価する関数
+    /// 読み込んだファイルを評価器の環境とは別の環境で評価するために使う。
+    fn eval_program_in(&mut self, program: &Program, env: &Rc<RefCell<Environment>>) -> Object {
+        let result = self.eval_statements(&program.statements, env);
+        Eval::unwrap_return_value(result)
+    }
+
+    /// 読み込んだソースを文ごとにパースしてすぐ評価する関数
+    /// 解釈木全体を保持しないので長いスクリプトでも一度に持つ解釈木は一文分で済む。
+    /// 文を評価するたびにその結果をcallbackに渡し、最後に評価した結果を`eval_program`と同じく戻り値を取り出して返す。
+    /// 途中でパースに失敗したらそこで止めてパースエラーを返す。失敗した文より前の文は評価済みのまま残る。
+    /// 字句解析器は文字列全体を入力に取るので、ソースは最初にすべて読み込む。読み込みながら評価するわけではない。
+    pub fn eval_reader(
+        &mut self,
+        mut reader: impl Read,
+        mut callback: impl FnMut(&Object),
+    ) -> Result<Object, Vec<String>> {
+        let mut source = "".to_string();
+        if let Err(e) = reader.read_to_string(&mut source) {
+            return Err(vec![format!("ソースを読み込めませんでした。{}", e)]);
+        }
+        let mut parser = Parser::new(Lexer::new(&source));
+        let env = Rc::clone(&self.env);
+        let mut result = Object::NULL;
+        while let Some(statement) = parser.parse_next_statement() {
+            result = self.eval_statement(&statement, &env);
+            callback(&result);
+            let result_type = result.get_type();
+            if result_type.is_return_value() || result_type.is_error() {
+                break;
+            }
+        }
+        let errors = parser.get_errors();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(Eval::unwrap_return_value(result))
     }
 
-    fn eval_statements(statements: &Vec<Statement>) -> Object {
+    fn eval_statements(
+        &mut self,
+        statements: &[Statement],
+        env: &Rc<RefCell<Environment>>,
+    ) -> Object {
         let mut result = Object::NULL;
 
         for statement in statements {
-            result = Self::eval_statement(&statement);
-            if result.get_type().is_return_value() {
+            result = self.eval_statement(statement, env);
+            // エラーを後の文の結果で上書きしない
+            let result_type = result.get_type();
+            if result_type.is_return_value() || result_type.is_error() {
                 break;
             }
         }
         result
     }
 
-    fn eval_statement(statement: &Statement) -> Object {
-        let mut result = Object::NULL;
+    fn eval_statement(&mut self, statement: &Statement, env: &Rc<RefCell<Environment>>) -> Object {
+        let evaluated = self.eval_statement_inner(statement, env);
+        return Eval::locate_error(evaluated, statement);
+    }
 
+    fn eval_statement_inner(
+        &mut self,
+        statement: &Statement,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Object {
+        if self.step_limit_exceeded() {
+            return Eval::step_limit_error();
+        }
         match statement {
             stmt @ Statement::ExpressionStatement {
                 token: _,
                 expression: _,
-            } => {
-                result = Self::eval_expression_statement(stmt);
-            }
-            stmt @ Statement::LetStatement {
+            } => self.eval_expression_statement(stmt, env),
+            Statement::LetStatement {
                 token: _,
-                name: _,
-                value: _,
-            } => unimplemented!(),
+                name,
+                value,
+                doc: _,
+            } => self.eval_let_statement(name, value, env),
+            Statement::AssignStatement {
+                token: _,
+                name,
+                value,
+            } => self.eval_assign_statement(name, value, env),
             Statement::ReturnStatement {
                 token: _,
                 return_value,
-            } => {
-                result = Self::eval_return_statement(return_value);
-            },
+            } => self.eval_return_statement(return_value, env),
             stmt @ Statement::BlockStatement {
                 token: _,
                 statements: _,
-            } => {
-                result = Self::eval_block_statement(&stmt);
+            } => self.eval_block_statement(stmt, env),
+            Statement::DoWhileStatement {
+                token: _,
+                body,
+                condition,
+            } => self.eval_do_while_statement(body, condition, env),
+            Statement::ForStatement {
+                token: _,
+                init,
+                condition,
+                update,
+                body,
+            } => self.eval_for_statement(init, condition, update, body, env),
+            Statement::ForInStatement {
+                token: _,
+                variable,
+                iterable,
+                body,
+            } => self.eval_for_in_statement(variable, iterable, body, env),
+            Statement::BreakStatement { token: _ } => {
+                self.eval_loop_control(Object::Break, "break")
+            }
+            Statement::ContinueStatement { token: _ } => {
+                self.eval_loop_control(Object::Continue, "continue")
             }
+            Statement::ImportStatement { token: _, path } => self.eval_import_statement(path, env),
         }
-        result
     }
 
-    fn eval_expression_statement(statement: &Statement) -> Object {
-        let mut result = Object::NULL;
+    /// break文とcontinue文を評価する関数。ループの外ならエラーになる。
+    fn eval_loop_control(&self, control: Object, keyword: &str) -> Object {
+        if self.loop_depth == 0 {
+            return Eval::new_error(format!("`{}` outside of a loop", keyword));
+        }
+        control
+    }
+
+    /// import文を評価する関数
+    /// 読み込んだファイルはスクリプトとしてパースして新しい環境で評価し、その直下で束縛した識別子を現在の環境に束縛する。
+    /// 相対パスはファイルを読み込み中ならそのファイルのディレクトリから、そうでなければ作業ディレクトリから探す。
+    fn eval_import_statement(&mut self, path: &str, env: &Rc<RefCell<Environment>>) -> Object {
+        let mut file = PathBuf::from(path);
+        if file.is_relative() {
+            if let Some(dir) = self.importing.last().and_then(|f| f.parent()) {
+                file = dir.join(file);
+            }
+        }
+        let file = match file.canonicalize() {
+            Ok(file) => file,
+            Err(_) => {
+                return Eval::new_error(format!("cannot import \"{}\": file not found", path))
+            }
+        };
+        if self.importing.contains(&file) {
+            return Eval::new_error(format!("cyclic import of \"{}\"", path));
+        }
+        let source = match std::fs::read_to_string(&file) {
+            Ok(source) => source,
+            Err(e) => return Eval::new_error(format!("cannot import \"{}\": {}", path, e)),
+        };
+        let options = ParserOptions {
+            script: true,
+            ..ParserOptions::new()
+        };
+        let mut parser = Parser::with_options(Lexer::new(&source), options);
+        let program = match parser.parse_program() {
+            Some(program) => program,
+            None => {
+                return Eval::new_error(format!(
+                    "cannot import \"{}\": {} parse errors",
+                    path,
+                    parser.get_errors().len()
+                ))
+            }
+        };
+
+        // 読み込んだファイルでも埋め込む側の組み込み関数を呼べるように、組み込み関数だけの環境を外側に置く。
+        // 読み込んだ側に取り込むのはファイルの直下で束縛したものだけになる。
+        let builtins = Rc::new(RefCell::new(env.borrow().builtins_only()));
+        let module_env = Rc::new(RefCell::new(Environment::new_enclosed(builtins)));
+        self.importing.push(file);
+        // 読み込んだファイルの直下はループの中ではない
+        let loop_depth = self.loop_depth;
+        self.loop_depth = 0;
+        let evaluated = self.eval_program_in(&program, &module_env);
+        self.loop_depth = loop_depth;
+        self.importing.pop();
+        if evaluated.get_type().is_error() {
+            return evaluated;
+        }
+        env.borrow_mut().import(&module_env.borrow());
+        Object::NULL
+    }
+
+    fn eval_expression_statement(
+        &mut self,
+        statement: &Statement,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Object {
         match statement {
             Statement::ExpressionStatement {
                 token: _,
                 expression: exp,
-            } => {
-                result = Self::eval_expression(exp);
-            }
+            } => self.eval_expression(exp, env),
             _ => unreachable!(),
         }
-        result
     }
 
-    fn eval_return_statement(return_value: &Expression) -> Object {
-        let value = Eval::eval_expression(return_value);
-        Object::ReturnValue {value: Box::new(value)}
+    fn eval_let_statement(
+        &mut self,
+        name: &Expression,
+        value: &Expression,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Object {
+        let val = self.eval_expression(value, env);
+        if val.get_type().is_error() {
+            return val;
+        }
+        if let Expression::ArrayPattern {
+            token: _,
+            names,
+            rest,
+        } = name
+        {
+            return Eval::eval_array_pattern(names, rest, val, env);
+        }
+        env.borrow_mut().set(&name.get_value(), val);
+        Object::NULL
+    }
+
+    /// 代入文を評価する関数。束縛されていない識別子には代入できない。
+    fn eval_assign_statement(
+        &mut self,
+        name: &Expression,
+        value: &Expression,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Object {
+        let val = self.eval_expression(value, env);
+        if val.get_type().is_error() {
+            return val;
+        }
+        let name = name.get_value();
+        if !env.borrow_mut().assign(&name, val) {
+            return Eval::new_error(format!("identifier not found: {}", name));
+        }
+        Object::NULL
+    }
+
+    /// 配列を分解して先頭から順に名前を束縛する関数
+    /// 残りの要素の名前がなければ要素数が名前の数と一致しないとエラーにし、あれば残りを配列として束縛する。
+    fn eval_array_pattern(
+        names: &[Box<Expression>],
+        rest: &Option<Box<Expression>>,
+        value: Object,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Object {
+        let elements = match value {
+            Object::Array { elements } => elements,
+            other => {
+                return Eval::new_error(format!(
+                    "cannot destructure {} as ARRAY",
+                    other.get_type().to_string()
+                ))
+            }
+        };
+        let mismatch = match rest {
+            Some(_) => elements.len() < names.len(),
+            None => elements.len() != names.len(),
+        };
+        if mismatch {
+            return Eval::new_error(format!(
+                "destructuring mismatch: expected {}{} elements, got {}",
+                if rest.is_some() { "at least " } else { "" },
+                names.len(),
+                elements.len()
+            ));
+        }
+        let mut env = env.borrow_mut();
+        for (name, element) in names.iter().zip(elements.iter()) {
+            env.set(&name.get_value(), element.clone());
+        }
+        if let Some(rest) = rest {
+            let remaining = Object::Array {
+                elements: elements[names.len()..].to_vec(),
+            };
+            env.set(&rest.get_value(), remaining);
+        }
+        Object::NULL
+    }
+
+    fn eval_return_statement(
+        &mut self,
+        return_value: &Expression,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Object {
+        let value = self.eval_expression(return_value, env);
+        // 戻り値の式の中でreturnやエラーになったときは二重に包まずにそのまま伝える
+        if value.get_type().is_return_value() || value.get_type().is_error() {
+            return value;
+        }
+        Object::ReturnValue {
+            value: Box::new(value),
+        }
     }
 
-    fn eval_block_statement(block: &Statement) -> Object {
+    fn eval_block_statement(
+        &mut self,
+        block: &Statement,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Object {
         let mut result = Object::NULL;
-        if let Statement::BlockStatement { token: _, statements} = block{
+        if let Statement::BlockStatement {
+            token: _,
+            statements,
+        } = block
+        {
             for statement in statements {
-                result = Self::eval_statement(&statement);
+                result = self.eval_statement(statement, env);
+                // 関数やループの外まで伝播させるためにReturnValueやエラー、breakとcontinueは取り出さずにそのまま返す
+                if result.get_type().interrupts() {
+                    break;
+                }
             }
         }
         result
     }
 
-    fn eval_expression(expression: &Expression) -> Object {
-        let mut result = Object::NULL;
-        match expression {
-            Expression::Identifier { token: _, value: _ } => unimplemented!(),
-            Expression::IntegerLiteral { token: _, value } => {
-                result = Object::Integer { value: *value };
+    /// do-while文を評価する関数
+    /// 本体を評価してから条件を判定するので、本体は少なくとも一度は評価される。
+    /// 本体のreturnとエラーはループを抜けてそのまま返し、それ以外のときはnullになる。
+    /// breakはループを抜け、continueは本体の残りを飛ばして条件の判定に進む。
+    fn eval_do_while_statement(
+        &mut self,
+        body: &Statement,
+        condition: &Expression,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Object {
+        self.loop_depth += 1;
+        let result = self.eval_do_while_loop(body, condition, env);
+        self.loop_depth -= 1;
+        result
+    }
+
+    fn eval_do_while_loop(
+        &mut self,
+        body: &Statement,
+        condition: &Expression,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Object {
+        loop {
+            let result = self.eval_statement(body, env);
+            let result_type = result.get_type();
+            if result_type.is_return_value() || result_type.is_error() {
+                return result;
             }
-            Expression::BooleanLiteral { token: _, value } => {
-                if *value {
-                    result = Object::BOOLEAN_TRUE;
-                } else {
-                    result = Object::BOOLEAN_FALSE;
+            if result_type.is_break() {
+                return Object::NULL;
+            }
+            let cond = self.eval_expression(condition, env);
+            if cond.get_type().is_error() {
+                return cond;
+            }
+            if !cond.is_truthy() {
+                return Object::NULL;
+            }
+        }
+    }
+
+    /// for文を評価する関数
+    /// 初期化の文からループ全体を新しい環境で評価するので、初期化や本体のlet文で束縛した名前はループの外から見えない。
+    /// 外側の変数を書き換えるには代入文を使う。どの繰り返しも同じ環境を使い、ループはnullになる。
+    fn eval_for_statement(
+        &mut self,
+        init: &Option<Box<Statement>>,
+        condition: &Option<Box<Expression>>,
+        update: &Option<Box<Statement>>,
+        body: &Statement,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Object {
+        let loop_env = Rc::new(RefCell::new(Environment::new_enclosed(Rc::clone(env))));
+        if let Some(init) = init {
+            let result = self.eval_statement(init, &loop_env);
+            if result.get_type().is_error() {
+                return result;
+            }
+        }
+        self.loop_depth += 1;
+        let result = self.eval_for_loop(condition, update, body, &loop_env);
+        self.loop_depth -= 1;
+        result
+    }
+
+    fn eval_for_loop(
+        &mut self,
+        condition: &Option<Box<Expression>>,
+        update: &Option<Box<Statement>>,
+        body: &Statement,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Object {
+        loop {
+            if let Some(condition) = condition {
+                let cond = self.eval_expression(condition, env);
+                if cond.get_type().is_error() {
+                    return cond;
+                }
+                if !cond.is_truthy() {
+                    return Object::NULL;
+                }
+            }
+            let result = self.eval_statement(body, env);
+            let result_type = result.get_type();
+            if result_type.is_return_value() || result_type.is_error() {
+                return result;
+            }
+            if result_type.is_break() {
+                return Object::NULL;
+            }
+            // continueのときも更新の文は評価する
+            if let Some(update) = update {
+                let updated = self.eval_statement(update, env);
+                if updated.get_type().is_error() {
+                    return updated;
                 }
             }
+        }
+    }
+
+    /// 要素を順に取り出すfor文を評価する関数
+    /// 配列は要素を、文字列は一文字ずつの文字列を、範囲は始まりから終わりの手前までの整数を取り出す。
+    /// 繰り返しごとに新しい環境で要素を束縛する。
+    fn eval_for_in_statement(
+        &mut self,
+        variable: &Expression,
+        iterable: &Expression,
+        body: &Statement,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Object {
+        let collection = self.eval_expression(iterable, env);
+        let name = variable.get_value();
+        match collection {
+            Object::Array { elements } => self.eval_for_in_loop(&name, elements, body, env),
+            Object::Str { value } => {
+                let chars = value.chars().map(|c| Object::Str {
+                    value: c.to_string(),
+                });
+                self.eval_for_in_loop(&name, chars, body, env)
+            }
+            // 範囲は配列を作らずに整数を順に取り出す
+            Object::Range { start, end } => {
+                self.eval_for_in_loop(&name, (start..end).map(Object::integer), body, env)
+            }
+            Object::Error {
+                message: _,
+                position: _,
+            } => collection,
+            other => Eval::new_error(format!(
+                "cannot iterate over {}",
+                other.get_type().to_string()
+            )),
+        }
+    }
+
+    fn eval_for_in_loop(
+        &mut self,
+        name: &str,
+        elements: impl IntoIterator<Item = Object>,
+        body: &Statement,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Object {
+        self.loop_depth += 1;
+        let result = self.eval_for_in_elements(name, elements, body, env);
+        self.loop_depth -= 1;
+        result
+    }
+
+    fn eval_for_in_elements(
+        &mut self,
+        name: &str,
+        elements: impl IntoIterator<Item = Object>,
+        body: &Statement,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Object {
+        for element in elements {
+            let mut iteration_env = Environment::new_enclosed(Rc::clone(env));
+            iteration_env.set(name, element);
+            let result = self.eval_statement(body, &Rc::new(RefCell::new(iteration_env)));
+            let result_type = result.get_type();
+            if result_type.is_return_value() || result_type.is_error() {
+                return result;
+            }
+            if result_type.is_break() {
+                break;
+            }
+        }
+        Object::NULL
+    }
+
+    fn eval_expression(
+        &mut self,
+        expression: &Expression,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Object {
+        let evaluated = self.eval_expression_inner(expression, env);
+        return Eval::locate_error(evaluated, expression);
+    }
+
+    fn eval_expression_inner(
+        &mut self,
+        expression: &Expression,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Object {
+        if self.step_limit_exceeded() {
+            return Eval::step_limit_error();
+        }
+        match expression {
+            Expression::Identifier { token: _, value } => Eval::eval_identifier(value, env),
+            Expression::IntegerLiteral { token: _, value } => Object::integer(*value),
+            Expression::FloatLiteral { token: _, value } => match value.parse::<f64>() {
+                Ok(value) => Object::Float { value },
+                Err(_) => Eval::new_error(format!("invalid float literal: {}", value)),
+            },
+            Expression::BooleanLiteral { token: _, value } => Object::boolean(*value),
+            Expression::StringLiteral { token: _, value } => Object::Str {
+                value: value.to_string(),
+            },
+            Expression::NullLiteral { token: _ } => Object::NULL,
             Expression::FunctionLiteral {
                 token: _,
-                parameters: _,
-                body: _,
-            } => unimplemented!(),
+                parameters,
+                body,
+                name,
+            } => Object::Function {
+                parameters: parameters.clone(),
+                body: Rc::new(body.clone()),
+                env: Rc::clone(env),
+                name: name.clone(),
+            },
             Expression::PrefixExpression {
                 token: _,
                 operator,
                 right_exp,
             } => {
-                let right = Eval::eval_expression(right_exp);
-                result  = Eval::eval_prefix_expression(&operator, &right);
-            },
+                let right = self.eval_expression(right_exp, env);
+                if right.get_type().is_error() {
+                    return right;
+                }
+                Eval::eval_prefix_expression(operator, &right)
+            }
             Expression::InfixExpression {
                 token: _,
                 operator,
                 left_exp,
                 right_exp,
             } => {
-                let left = Eval::eval_expression(left_exp);
-                let right = Eval::eval_expression(right_exp);
-                result = Eval::eval_infix_expression(&operator, &left, &right);
-            },
+                if operator == "&&" || operator == "||" {
+                    return self.eval_logical_expression(operator, left_exp, right_exp, env);
+                }
+                // 被演算子のエラーは演算の型エラーに変えずにそのまま返す
+                let left = self.eval_expression(left_exp, env);
+                if left.get_type().is_error() {
+                    return left;
+                }
+                let right = self.eval_expression(right_exp, env);
+                if right.get_type().is_error() {
+                    return right;
+                }
+                self.eval_infix_expression(operator, &left, &right)
+            }
             Expression::IfExpression {
                 token: _,
                 condition,
                 consequence,
                 alternative,
             } => {
-                let cond = Eval::eval_expression(condition);
+                let cond = self.eval_expression(condition, env);
+                // エラーも真とみなされるので、真偽を判定する前にそのまま返す
+                if cond.get_type().is_error() {
+                    return cond;
+                }
 
                 if cond.is_truthy() {
-                    return Eval::eval_statement(consequence);
+                    self.eval_statement(consequence, env)
+                } else if let Some(alt) = alternative {
+                    self.eval_statement(alt, env)
                 } else {
-                    if let Some(alt) = &**alternative {
-                        return Eval::eval_statement(alt);
-                    } else {
-                        return Object::Null;
+                    // elseがなければ値のない式としてnullになる
+                    Object::NULL
+                }
+            }
+            Expression::ArrayLiteral { token: _, elements } => {
+                let mut evaluated = Vec::new();
+                for element in elements.iter() {
+                    let element = self.eval_expression(element, env);
+                    // 要素のエラーは配列に入れずにそのまま返す
+                    if element.get_type().is_error() {
+                        return element;
                     }
+                    evaluated.push(element);
                 }
-            },
+                Object::Array {
+                    elements: evaluated,
+                }
+            }
+            Expression::SwitchExpression {
+                token: _,
+                subject,
+                arms,
+                default,
+            } => self.eval_switch_expression(subject, arms, default, env),
+            Expression::HashLiteral { token: _, pairs } => self.eval_hash_literal(pairs, env),
+            Expression::ArrayPattern {
+                token: _,
+                names: _,
+                rest: _,
+            } => Eval::new_error("array pattern can only be used in let".to_string()),
+            Expression::SpreadExpression { token: _, value: _ } => {
+                Eval::new_error("spread can only be used in call arguments".to_string())
+            }
             Expression::CallExpression {
                 token: _,
-                function: _,
-                arguments: _,
-            } => unimplemented!(),
+                function,
+                arguments,
+            } => {
+                let func = self.eval_expression(function, env);
+                if func.get_type().is_error() {
+                    return func;
+                }
+                match self.eval_arguments(arguments, env) {
+                    Ok(args) => self.apply_function(&func, &args),
+                    Err(err) => err,
+                }
+            }
+            Expression::IndexExpression {
+                token: _,
+                left,
+                index,
+            } => {
+                let left = self.eval_expression(left, env);
+                if left.get_type().is_error() {
+                    return left;
+                }
+                let index = self.eval_expression(index, env);
+                if index.get_type().is_error() {
+                    return index;
+                }
+                Eval::eval_index_expression(&left, &index)
+            }
+            Expression::MemberExpression {
+                token: _,
+                object,
+                property,
+            } => {
+                let object = self.eval_expression(object, env);
+                if object.get_type().is_error() {
+                    return object;
+                }
+                Eval::eval_member_expression(&object, &property.get_value())
+            }
+            Expression::RangeLiteral {
+                token: _,
+                start,
+                end,
+            } => {
+                let start = self.eval_expression(start, env);
+                if start.get_type().is_error() {
+                    return start;
+                }
+                let end = self.eval_expression(end, env);
+                if end.get_type().is_error() {
+                    return end;
+                }
+                match (&start, &end) {
+                    (Object::Integer { value: s }, Object::Integer { value: e }) => {
+                        Object::Range { start: *s, end: *e }
+                    }
+                    _ => Eval::new_error(format!(
+                        "range bounds must be INTEGER, got {}..{}",
+                        start.get_type().to_string(),
+                        end.get_type().to_string()
+                    )),
+                }
+            }
+        }
+    }
+
+    /// メンバー参照式を評価する関数
+    /// 今はハッシュだけが対象で、メンバー名の文字列をキーとして値を取り出す。キーがなければNULLを返す。
+    fn eval_member_expression(object: &Object, name: &str) -> Object {
+        match object {
+            Object::Hash { pairs: _ } => {
+                let key = Object::Str {
+                    value: name.to_string(),
+                };
+                Eval::eval_index_expression(object, &key)
+            }
+            _ => Eval::new_error(format!(
+                "member access not supported: {}.{}",
+                object.get_type().to_string(),
+                name
+            )),
+        }
+    }
+
+    /// 添字式を評価する関数
+    /// 配列と文字列は整数の添字で要素を取り出し、範囲外ならNULLを返す。文字列の添字はバイトではなく文字で数える。
+    /// ハッシュはキーに対応する値を取り出し、キーがなければNULLを返す。
+    fn eval_index_expression(left: &Object, index: &Object) -> Object {
+        match (left, index) {
+            (Object::Array { elements }, Object::Integer { value }) => {
+                if *value < 0 {
+                    return Object::NULL;
+                }
+                match elements.get(*value as usize) {
+                    Some(element) => element.clone(),
+                    None => Object::NULL,
+                }
+            }
+            (Object::Str { value: s }, Object::Integer { value }) => {
+                if *value < 0 {
+                    return Object::NULL;
+                }
+                match s.chars().nth(*value as usize) {
+                    Some(c) => Object::Str {
+                        value: c.to_string(),
+                    },
+                    None => Object::NULL,
+                }
+            }
+            (Object::Hash { pairs }, key) => {
+                let key_type = key.get_type();
+                if !key_type.is_hashable() {
+                    return Eval::new_error(format!(
+                        "unusable as hash key: {}",
+                        key_type.to_string()
+                    ));
+                }
+                match pairs.iter().find(|(k, _)| k == key) {
+                    Some((_, value)) => value.clone(),
+                    None => Object::NULL,
+                }
+            }
+            _ => Eval::new_error(format!(
+                "index operator not supported: {}[{}]",
+                left.get_type().to_string(),
+                index.get_type().to_string()
+            )),
+        }
+    }
+
+    /// ハッシュリテラルを評価する関数
+    /// 書かれた順を保ち、同じキーが複数あれば最初の位置のまま後の値で上書きする。
+    fn eval_hash_literal(
+        &mut self,
+        pairs: &[(Box<Expression>, Box<Expression>)],
+        env: &Rc<RefCell<Environment>>,
+    ) -> Object {
+        let mut evaluated: Vec<(Object, Object)> = Vec::new();
+        for (key_exp, value_exp) in pairs {
+            let key = self.eval_expression(key_exp, env);
+            let key_type = key.get_type();
+            // キーのエラーはキーに使えない型のエラーに変えずにそのまま返す
+            if key_type.is_error() {
+                return key;
+            }
+            if !key_type.is_hashable() {
+                return Eval::new_error(format!("unusable as hash key: {}", key_type.to_string()));
+            }
+            let value = self.eval_expression(value_exp, env);
+            if value.get_type().is_error() {
+                return value;
+            }
+            match evaluated.iter_mut().find(|(k, _)| *k == key) {
+                Some(pair) => pair.1 = value,
+                None => evaluated.push((key, value)),
+            }
+        }
+        Object::Hash { pairs: evaluated }
+    }
+
+    /// switch式を評価する関数
+    /// 上から順に分岐の値と比べて最初に一致した分岐の式を評価する。どれにも一致せず`_`の分岐もなければエラーになる。
+    fn eval_switch_expression(
+        &mut self,
+        subject: &Expression,
+        arms: &[(Box<Expression>, Box<Expression>)],
+        default: &Option<Box<Expression>>,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Object {
+        let value = self.eval_expression(subject, env);
+        if value.get_type().is_error() {
+            return value;
+        }
+        // 値で比べられる整数、文字列、真偽値だけを分岐できる
+        if !value.get_type().is_hashable() {
+            return Eval::new_error(format!("cannot switch on {}", value.get_type().to_string()));
+        }
+        for (pattern_exp, arm) in arms {
+            let pattern = self.eval_expression(pattern_exp, env);
+            if pattern.get_type().is_error() {
+                return pattern;
+            }
+            if pattern == value {
+                return self.eval_expression(arm, env);
+            }
+        }
+        match default {
+            Some(arm) => self.eval_expression(arm, env),
+            None => Eval::new_error(format!("no switch arm matches {}", value.inspect())),
+        }
+    }
+
+    fn eval_identifier(name: &str, env: &Rc<RefCell<Environment>>) -> Object {
+        // 環境の束縛を言語の組み込み関数より優先する
+        if let Some(obj) = env.borrow().get(name) {
+            return obj;
+        }
+        match builtins::lookup(name) {
+            Some(builtin) => builtin,
+            None => Eval::new_error(format!("identifier not found: {}", name)),
+        }
+    }
+
+    /// 関数に渡す引数を評価する関数
+    /// 引数のエラーは関数に渡さずにErrで返す。
+    fn eval_arguments(
+        &mut self,
+        arguments: &[Box<Expression>],
+        env: &Rc<RefCell<Environment>>,
+    ) -> Result<Vec<Object>, Object> {
+        let mut args: Vec<Object> = Vec::new();
+        for arg in arguments.iter() {
+            // 配列を展開する引数は要素を一つずつ引数として渡す
+            if let Expression::SpreadExpression { token: _, value } = &**arg {
+                match self.eval_expression(value, env) {
+                    Object::Array { elements } => args.extend(elements),
+                    err @ Object::Error { .. } => return Err(err),
+                    other => {
+                        return Err(Eval::new_error(format!(
+                            "cannot spread {}, expected ARRAY",
+                            other.get_type().to_string()
+                        )))
+                    }
+                }
+                continue;
+            }
+            let evaluated = self.eval_expression(arg, env);
+            if evaluated.get_type().is_error() {
+                return Err(evaluated);
+            }
+            args.push(evaluated);
+        }
+        return Ok(args);
+    }
+
+    fn apply_function(&mut self, func: &Object, args: &[Object]) -> Object {
+        if let Object::Function {
+            parameters,
+            body,
+            env,
+            name,
+        } = func
+        {
+            // 足りない引数を未定義のままにしたり余分な引数を捨てたりしない
+            // 残りの引数を受け取る関数は残りの引数の前までの数があればよい
+            let has_rest = matches!(
+                parameters.last().map(|p| &**p),
+                Some(Expression::SpreadExpression { .. })
+            );
+            let required = if has_rest {
+                parameters.len() - 1
+            } else {
+                parameters.len()
+            };
+            if args.len() < required || (!has_rest && args.len() > required) {
+                let target = match name {
+                    Some(name) => format!(" to `{}`", name),
+                    None => "".to_string(),
+                };
+                return Eval::new_error(format!(
+                    "wrong number of arguments{}. got={}, want={}{}",
+                    target,
+                    args.len(),
+                    if has_rest { "at least " } else { "" },
+                    required
+                ));
+            }
+            let extended_env = Eval::extend_function_env(parameters, args, env);
+            // 関数の本体は呼び出し元のループの中ではない
+            let loop_depth = self.loop_depth;
+            self.loop_depth = 0;
+            let evaluated = self.eval_statement(body, &extended_env);
+            self.loop_depth = loop_depth;
+            return Eval::unwrap_return_value(evaluated);
+        }
+        if let Object::Builtin { name: _, function } = func {
+            // 出力する組み込み関数は評価器の書き込み先に書き込む
+            let mut ctx = BuiltinContext {
+                out: &mut *self.out,
+                err: &mut *self.err,
+            };
+            return function(&mut ctx, args);
+        }
+        Eval::new_error(format!("not a function: {}", func.get_type().to_string()))
+    }
+
+    /// 関数の環境を外側に持つ環境を作って引数を束縛する関数
+    fn extend_function_env(
+        parameters: &[Box<Expression>],
+        args: &[Object],
+        env: &Rc<RefCell<Environment>>,
+    ) -> Rc<RefCell<Environment>> {
+        let mut extended = Environment::new_enclosed(Rc::clone(env));
+        for (i, parameter) in parameters.iter().enumerate() {
+            if let Expression::SpreadExpression { token: _, value } = &**parameter {
+                // 残りの引数を配列にまとめて束縛する
+                let rest = Object::Array {
+                    elements: args[i..].to_vec(),
+                };
+                extended.set(&value.get_value(), rest);
+                break;
+            }
+            extended.set(&parameter.get_value(), args[i].clone());
+        }
+        Rc::new(RefCell::new(extended))
+    }
+
+    /// 関数の戻り値を関数の外に伝播させないために取り出す関数
+    fn unwrap_return_value(obj: Object) -> Object {
+        match obj {
+            Object::ReturnValue { value } => *value,
+            _ => obj,
         }
-        result
     }
 
     fn eval_prefix_expression(operator: &str, right: &Object) -> Object {
         match operator {
             "!" => Eval::eval_bang_operation(right),
             "-" => Eval::eval_minus_operation(right),
+            "+" => Eval::eval_plus_operation(right),
             _ => Object::NULL,
         }
     }
 
+    /// 否定演算子を評価する関数
+    /// if式の条件と同じ真偽の判定を反転するので、`!null`は`true`、`!0`や`!5`は`false`になる。
     fn eval_bang_operation(right: &Object) -> Object {
+        Object::boolean(!right.is_truthy())
+    }
+
+    fn eval_minus_operation(right: &Object) -> Object {
         match right {
-            Object::Boolean {value} => {
-                if *value {
-                    Object::BOOLEAN_FALSE
-                } else {
-                    Object::BOOLEAN_TRUE
-                }
+            // 最小の整数の符号を反転すると表せないのでエラーにする
+            Object::Integer { value } => match value.checked_neg() {
+                Some(value) => Object::integer(value),
+                None => Eval::new_error("integer overflow in `-`".to_string()),
             },
-            _ => Object::BOOLEAN_FALSE,
+            Object::Float { value } => Object::Float { value: -value },
+            _ => Object::NULL,
         }
     }
 
-    fn eval_minus_operation(right: &Object) -> Object {
+    /// 単項のプラス演算子を評価する関数。数値はそのままの値を返す。
+    fn eval_plus_operation(right: &Object) -> Object {
         match right {
-            Object::Integer{value} => Object::Integer{value: -(*value)},
-            _ => Object::NULL,
+            Object::Integer { value: _ } | Object::Float { value: _ } => right.clone(),
+            _ => Eval::new_error(format!(
+                "unknown operator: +{}",
+                right.get_type().to_string()
+            )),
+        }
+    }
+
+    /// 論理演算子の式を評価する関数
+    /// 型を予測しやすくするために被演算子の値そのものではなく、真偽値に変換した結果を返す。
+    /// 例えば`true && 5`は`true`、`false || 3`は`true`になる。
+    /// 依頼では`false || 3`を`false`としていたが、`true && 5`が`true`になるのと同じく3は真とみなすので`true`にしている。
+    /// 左辺だけで結果が決まる場合は右辺を評価しない。
+    fn eval_logical_expression(
+        &mut self,
+        operator: &str,
+        left_exp: &Expression,
+        right_exp: &Expression,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Object {
+        let left = self.eval_expression(left_exp, env);
+        if left.get_type().is_error() {
+            return left;
+        }
+        let short_circuit = match operator {
+            "&&" => !left.is_truthy(),
+            "||" => left.is_truthy(),
+            _ => unreachable!(),
+        };
+        if short_circuit {
+            return Object::boolean(left.is_truthy());
         }
+        let right = self.eval_expression(right_exp, env);
+        if right.get_type().is_error() {
+            return right;
+        }
+        Object::boolean(right.is_truthy())
     }
 
-    fn eval_infix_expression(operator: &str, left: &Object, right: &Object) -> Object {
+    /// 中置演算子の式を評価する関数
+    /// `&&`と`||`は短絡評価のために`eval_logical_expression`で扱い、常に真偽値を返す。
+    fn eval_infix_expression(&self, operator: &str, left: &Object, right: &Object) -> Object {
         let left_type = left.get_type();
         let right_type = right.get_type();
         if left_type.is_integer() && right_type.is_integer() {
+            if operator == "/" && !self.options.integer_division {
+                return Eval::eval_float_infix_expression(operator, left, right);
+            }
             Eval::eval_integer_infix_expression(operator, left, right)
+        } else if left_type.is_number() && right_type.is_number() {
+            // 片方が浮動小数点数なら、もう片方の整数も浮動小数点数にして計算する
+            Eval::eval_float_infix_expression(operator, left, right)
+        } else if left_type.is_boolean()
+            && right_type.is_boolean()
+            && (operator == "<" || operator == ">")
+            && self.options.compare_booleans_as_integers
+        {
+            // trueを1、falseを0として整数の大小比較にする
+            let to_integer = |obj: &Object| Object::integer(if obj.is_truthy() { 1 } else { 0 });
+            Eval::eval_integer_infix_expression(operator, &to_integer(left), &to_integer(right))
         } else if left_type.is_boolean() && right_type.is_boolean() {
             Eval::eval_boolean_infix_expression(operator, left, right)
+        } else if left_type.is_string() && right_type.is_string() {
+            Eval::eval_string_infix_expression(operator, left, right)
+        } else if left_type.is_array() && right_type.is_array() {
+            Eval::eval_array_infix_expression(operator, left, right)
+        } else if operator == "+" && (left_type.is_array() || right_type.is_array()) {
+            Eval::new_error(format!(
+                "type mismatch: {} {} {}",
+                left_type.to_string(),
+                operator,
+                right_type.to_string()
+            ))
+        } else if operator == "==" {
+            Object::boolean(left == right)
+        } else if operator == "!=" {
+            Object::boolean(left != right)
+        } else if (operator == "<" || operator == ">") && left_type != right_type {
+            // `1 < 2 < 3`のような連鎖した比較は真偽値と整数の比較になるので書き直し方も伝える
+            let hint = if left_type.is_boolean() && right_type.is_integer() {
+                format!(
+                    "; comparisons do not chain, write `a {} b && b {} c` instead",
+                    operator, operator
+                )
+            } else {
+                "".to_string()
+            };
+            Eval::new_error(format!(
+                "cannot compare {} with {}{}",
+                left_type.to_string(),
+                right_type.to_string(),
+                hint
+            ))
+        } else if operator == "<" || operator == ">" {
+            // 大小比較は整数同士でしか意味を持たない
+            Eval::new_error(format!(
+                "unknown operator: {} {} {}",
+                left_type.to_string(),
+                operator,
+                right_type.to_string()
+            ))
+        } else if left_type != right_type {
+            Eval::new_error(format!(
+                "type mismatch: {} {} {}",
+                left_type.to_string(),
+                operator,
+                right_type.to_string()
+            ))
         } else {
             // TODO others
             Object::NULL
@@ -187,18 +1192,57 @@ impl Eval {
     }
 
     fn eval_integer_infix_expression(operator: &str, left: &Object, right: &Object) -> Object {
-        let left_int = left.inspect().parse::<i64>().unwrap();
-        let right_int = right.inspect().parse::<i64>().unwrap();
-        match operator {
-            "+" => Object::Integer { value: left_int + right_int},
-            "-" => Object::Integer { value: left_int - right_int},
-            "*" => Object::Integer { value: left_int * right_int},
-            "/" => Object::Integer { value: left_int / right_int},
-            "<" => Object::Boolean { value: left_int < right_int},
-            ">" => Object::Boolean { value: left_int > right_int},
-            "==" => Object::Boolean { value: left_int == right_int},
-            "!=" => Object::Boolean { value: left_int != right_int},
-            _ => Object::NULL,
+        match (i64::from_object(left), i64::from_object(right)) {
+            (Some(l), Some(r)) => Eval::eval_numeric_infix_expression(operator, l, r),
+            _ => unreachable!(),
+        }
+    }
+
+    /// 浮動小数点数の中置演算子の式を評価する関数
+    /// 整数は浮動小数点数にして計算する。IEEE 754のとおり0で割ると無限大かNaNになり、溢れても無限大になる。
+    fn eval_float_infix_expression(operator: &str, left: &Object, right: &Object) -> Object {
+        let to_float = |obj: &Object| match obj {
+            Object::Float { value } => *value,
+            Object::Integer { value } => *value as f64,
+            _ => unreachable!(),
+        };
+        let (l, r) = (to_float(left), to_float(right));
+        let value = match operator {
+            "+" => l + r,
+            "-" => l - r,
+            "*" => l * r,
+            "/" => l / r,
+            "<" => return Object::boolean(l < r),
+            ">" => return Object::boolean(l > r),
+            "==" => return Object::boolean(l == r),
+            "!=" => return Object::boolean(l != r),
+            _ => return Object::NULL,
+        };
+        Object::Float { value }
+    }
+
+    /// 数値同士の中置演算子の式を評価する関数
+    /// 0での割り算と結果が数値型で表せない演算はパニックせずにエラーにする。
+    fn eval_numeric_infix_expression<N: Numeric>(operator: &str, left: N, right: N) -> Object {
+        let result = match operator {
+            "+" => left.add(&right),
+            "-" => left.sub(&right),
+            "*" => left.mul(&right),
+            "/" => {
+                if right.is_zero() {
+                    return Eval::new_error("division by zero".to_string());
+                }
+                left.div(&right)
+            }
+            "<" => return Object::boolean(left < right),
+            ">" => return Object::boolean(left > right),
+            "==" => return Object::boolean(left == right),
+            "!=" => return Object::boolean(left != right),
+            _ => return Object::NULL,
+        };
+        match result {
+            Some(value) => value.into_object(),
+            None => Eval::new_error(format!("integer overflow in `{}`", operator)),
         }
     }
 
@@ -206,38 +1250,121 @@ impl Eval {
         let left_bool = left.inspect().parse::<bool>().unwrap();
         let right_bool = right.inspect().parse::<bool>().unwrap();
         match operator {
-            "==" => Object::Boolean { value: left_bool == right_bool},
-            "!=" => Object::Boolean { value: left_bool != right_bool},
+            "==" => Object::boolean(left_bool == right_bool),
+            "!=" => Object::boolean(left_bool != right_bool),
+            "<" | ">" => Eval::new_error(format!("unknown operator: BOOLEAN {} BOOLEAN", operator)),
             _ => Object::NULL,
         }
     }
-}
-
-#[cfg(test)]
-mod test {
-    use crate::evaluator::Eval;
-    use crate::lexer::Lexer;
-    use crate::object::Object;
-    use crate::parser::Parser;
 
-    #[test]
-    fn test_eval_integer_expression() {
+    /// 文字列同士の中置演算子の式を評価する関数
+    /// 大小比較は文字のUnicodeのコードポイントを先頭から比べる辞書順で、UTF-8のバイト列を比べた順と一致する。
+    /// 片方がもう片方の先頭部分なら短い方が小さい。
+    fn eval_string_infix_expression(operator: &str, left: &Object, right: &Object) -> Object {
+        match (left, right) {
+            (Object::Str { value: l }, Object::Str { value: r }) => match operator {
+                "<" => Object::boolean(l < r),
+                ">" => Object::boolean(l > r),
+                "==" => Object::boolean(l == r),
+                "!=" => Object::boolean(l != r),
+                _ => Eval::new_error(format!("unknown operator: STRING {} STRING", operator)),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    /// 配列同士の中置演算子の式を評価する関数
+    /// `+`は左辺の後ろに右辺の要素を並べた新しい配列を返す。
+    fn eval_array_infix_expression(operator: &str, left: &Object, right: &Object) -> Object {
+        match (left, right) {
+            (Object::Array { elements: l }, Object::Array { elements: r }) => match operator {
+                "+" => Object::Array {
+                    elements: l.iter().chain(r.iter()).cloned().collect(),
+                },
+                "==" => Object::boolean(l == r),
+                "!=" => Object::boolean(l != r),
+                _ => Eval::new_error(format!("unknown operator: ARRAY {} ARRAY", operator)),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    /// 評価した文と式の数を数えて上限を超えたかどうかを返す関数
+    fn step_limit_exceeded(&mut self) -> bool {
+        self.steps += 1;
+        match self.options.max_steps {
+            Some(max_steps) => self.steps > max_steps,
+            None => false,
+        }
+    }
+
+    /// 評価の上限を超えたときのエラーを生成する関数
+    fn step_limit_error() -> Object {
+        Eval::new_error("execution step limit exceeded".to_string())
+    }
+
+    /// 位置を持たないエラーに評価していた式や文の位置を付ける関数
+    /// 内側の式で付けた位置は上書きしないので、エラーの位置は原因になった一番内側の式を指す。
+    fn locate_error(obj: Object, node: &impl Node) -> Object {
+        match obj {
+            Object::Error {
+                message,
+                position: None,
+            } => Object::Error {
+                message,
+                position: Some(node.get_token().get_position()),
+            },
+            other => other,
+        }
+    }
+
+    /// エラーオブジェクトを生成する関数
+    fn new_error(message: String) -> Object {
+        Object::Error {
+            message,
+            position: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::io::Write;
+    use std::rc::Rc;
+
+    use crate::environment::Environment;
+    use crate::evaluator::{Eval, EvalOptions};
+    use crate::lexer::Lexer;
+    use crate::numeric::Numeric;
+    use crate::object::{BuiltinContext, BuiltinFunction, Object};
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_eval_integer_expression() {
         let tests = [
             ("5;", Object::Integer { value: 5 }),
             ("10;", Object::Integer { value: 10 }),
             ("-5;", Object::Integer { value: -5 }),
             ("-10;", Object::Integer { value: -10 }),
-            ("5 + 5 + 5 + 5 - 10;", Object::Integer {value: 10}),
-            ("2 * 2 * 2 * 2 * 2;", Object::Integer {value: 32}),
-            ("-50 + 100 + -50;", Object::Integer {value: 0}),
-            ("5 * 2 + 10;", Object::Integer {value: 20}),
-            ("5 + 2 * 10;", Object::Integer {value: 25}),
-            ("20 + 2 * -10;", Object::Integer {value: 0}),
-            ("50 / 2 * 2 + 10;", Object::Integer {value: 60}),
-            ("2 * (5 + 10);", Object::Integer {value: 30}),
-            ("3 * 3 * 3 + 10;", Object::Integer {value: 37}),
-            ("3 * (3 * 3 + 10);", Object::Integer {value: 57}),
-            ("(5 + 10 * 2 + 15 / 3) * 2 + -10;", Object::Integer {value: 50}),
+            ("5 + 5 + 5 + 5 - 10;", Object::Integer { value: 10 }),
+            ("2 * 2 * 2 * 2 * 2;", Object::Integer { value: 32 }),
+            ("-50 + 100 + -50;", Object::Integer { value: 0 }),
+            ("5 * 2 + 10;", Object::Integer { value: 20 }),
+            ("5 + 2 * 10;", Object::Integer { value: 25 }),
+            ("20 + 2 * -10;", Object::Integer { value: 0 }),
+            ("50 / 2 * 2 + 10;", Object::Integer { value: 60 }),
+            ("2 * (5 + 10);", Object::Integer { value: 30 }),
+            ("3 * 3 * 3 + 10;", Object::Integer { value: 37 }),
+            ("3 * (3 * 3 + 10);", Object::Integer { value: 57 }),
+            (
+                "(5 + 10 * 2 + 15 / 3) * 2 + -10;",
+                Object::Integer { value: 50 },
+            ),
+            // 整数どうしの割り算は0に向かって切り捨てる
+            ("5 / 2;", Object::Integer { value: 2 }),
+            ("-5 / 2;", Object::Integer { value: -2 }),
             ("1 < 2;", Object::Boolean { value: true }),
             ("1 > 2;", Object::Boolean { value: false }),
             ("1 < 1;", Object::Boolean { value: false }),
@@ -251,6 +1378,229 @@ mod test {
         do_test(&tests);
     }
 
+    #[test]
+    fn test_integer_object() {
+        assert_eq!(test_eval("5;"), Object::integer(5));
+        assert_eq!(test_eval("2 * 3 - 1;"), Object::integer(5));
+        assert_eq!(test_eval("-300;"), Object::integer(-300));
+        assert_eq!(
+            Object::integer(i64::MAX),
+            Object::Integer { value: i64::MAX }
+        );
+    }
+
+    #[test]
+    fn test_numeric_i64() {
+        let tests = [
+            ("+", 7, 2, Object::Integer { value: 9 }),
+            ("-", 2, 7, Object::Integer { value: -5 }),
+            ("*", -3, 4, Object::Integer { value: -12 }),
+            // 整数の割り算は0に向かって切り捨てる
+            ("/", -7, 2, Object::Integer { value: -3 }),
+            ("<", 1, 2, Object::BOOLEAN_TRUE),
+            (">", 1, 2, Object::BOOLEAN_FALSE),
+            ("==", 3, 3, Object::BOOLEAN_TRUE),
+            ("!=", 3, 3, Object::BOOLEAN_FALSE),
+        ];
+        for (operator, left, right, expect) in tests.iter() {
+            let evaluated = Eval::eval_numeric_infix_expression::<i64>(operator, *left, *right);
+            assert_eq!(evaluated, *expect, "{} {} {}", left, operator, right);
+            // 整数オブジェクト同士の評価と結果が変わらない
+            let (l, r) = (Object::integer(*left), Object::integer(*right));
+            let eval = Eval::new(Rc::new(RefCell::new(Environment::new())));
+            assert_eq!(eval.eval_infix_expression(operator, &l, &r), *expect);
+        }
+        assert_eq!(i64::from_object(&Object::integer(3)), Some(3));
+        assert_eq!(i64::from_object(&Object::BOOLEAN_TRUE), None);
+    }
+
+    #[test]
+    fn test_integer_arithmetic_errors() {
+        // 信頼できないスクリプトでもホストをパニックさせずにエラーを返す
+        let tests = [
+            ("5 / 0;", Object::error("division by zero")),
+            ("let zero = 0; 1 / zero;", Object::error("division by zero")),
+            ("0 / 0;", Object::error("division by zero")),
+            (
+                "9223372036854775807 + 1;",
+                Object::error("integer overflow in `+`"),
+            ),
+            (
+                "-9223372036854775807 - 2;",
+                Object::error("integer overflow in `-`"),
+            ),
+            (
+                "4611686018427387904 * 2;",
+                Object::error("integer overflow in `*`"),
+            ),
+            (
+                "(-9223372036854775807 - 1) / -1;",
+                Object::error("integer overflow in `/`"),
+            ),
+            (
+                "-(-9223372036854775807 - 1);",
+                Object::error("integer overflow in `-`"),
+            ),
+            // 溢れない境界の値は計算できる
+            (
+                "9223372036854775806 + 1;",
+                Object::Integer { value: i64::MAX },
+            ),
+            (
+                "-9223372036854775807 - 1;",
+                Object::Integer { value: i64::MIN },
+            ),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_float_object() {
+        let tests = [
+            ("2.5;", Object::Float { value: 2.5 }),
+            ("1e3;", Object::Float { value: 1000.0 }),
+            ("-1.5;", Object::Float { value: -1.5 }),
+            ("+1.5;", Object::Float { value: 1.5 }),
+            ("1.5 + 2.25;", Object::Float { value: 3.75 }),
+            ("1.5 * 2.0 - 0.5;", Object::Float { value: 2.5 }),
+            // 整数と混ぜると整数を浮動小数点数にして計算する
+            ("1 + 0.5;", Object::Float { value: 1.5 }),
+            ("3.0 / 2;", Object::Float { value: 1.5 }),
+            ("1 < 1.5;", Object::BOOLEAN_TRUE),
+            ("2 == 2.0;", Object::BOOLEAN_TRUE),
+            // 0での割り算と溢れはIEEE 754のとおり無限大かNaNになる
+            (
+                "5.0 / 0.0;",
+                Object::Float {
+                    value: f64::INFINITY,
+                },
+            ),
+            (
+                "-5.0 / 0;",
+                Object::Float {
+                    value: f64::NEG_INFINITY,
+                },
+            ),
+            (
+                "1e308 * 10.0;",
+                Object::Float {
+                    value: f64::INFINITY,
+                },
+            ),
+            ("0.0 / 0.0 == 0.0 / 0.0;", Object::BOOLEAN_FALSE),
+            ("0.0 / 0.0 != 0.0 / 0.0;", Object::BOOLEAN_TRUE),
+            (
+                "type(1.0);",
+                Object::Str {
+                    value: "FLOAT".to_string(),
+                },
+            ),
+            ("{1.0: 1};", Object::error("unusable as hash key: FLOAT")),
+        ];
+        do_test(&tests);
+        assert_eq!(Object::Float { value: 2.0 }.inspect(), "2.0");
+    }
+
+    #[test]
+    fn test_integer_division_option() {
+        // 既定ではMonkeyと同じく0に向かって切り捨てた整数にする
+        do_test(&[
+            ("5 / 2;", Object::Integer { value: 2 }),
+            ("-5 / 2;", Object::Integer { value: -2 }),
+        ]);
+
+        // 設定すれば整数どうしの割り算も浮動小数点数にする
+        let options = EvalOptions {
+            integer_division: false,
+            ..EvalOptions::new()
+        };
+        let tests = [
+            ("5 / 2;", Object::Float { value: 2.5 }),
+            ("-5 / 2;", Object::Float { value: -2.5 }),
+            ("4 / 2;", Object::Float { value: 2.0 }),
+            (
+                "5 / 0;",
+                Object::Float {
+                    value: f64::INFINITY,
+                },
+            ),
+            // 割り算以外の整数の演算は変わらない
+            ("5 * 2;", Object::Integer { value: 10 }),
+        ];
+        for (input, expected) in tests.iter() {
+            let program = Parser::new(Lexer::new(input))
+                .parse_program()
+                .expect("fail parse program.");
+            let env = Rc::new(RefCell::new(Environment::new()));
+            let evaluated =
+                Eval::with_options(Rc::clone(&env), options.clone()).eval_program(&program);
+            assert_eq!(&evaluated, expected, "{}", input);
+        }
+    }
+
+    /// 数値の演算が`Numeric`トレイトを通ることを確かめるための数値型
+    #[derive(Debug, PartialEq, PartialOrd)]
+    struct StubNumber(i64);
+
+    impl Numeric for StubNumber {
+        fn from_object(obj: &Object) -> Option<Self> {
+            i64::from_object(obj).map(StubNumber)
+        }
+
+        fn into_object(self) -> Object {
+            Object::Str {
+                value: format!("stub({})", self.0),
+            }
+        }
+
+        fn is_zero(&self) -> bool {
+            self.0 == 0
+        }
+
+        fn add(&self, rhs: &Self) -> Option<Self> {
+            Some(StubNumber(self.0 + rhs.0))
+        }
+
+        fn sub(&self, rhs: &Self) -> Option<Self> {
+            Some(StubNumber(self.0 - rhs.0))
+        }
+
+        fn mul(&self, rhs: &Self) -> Option<Self> {
+            Some(StubNumber(self.0 * rhs.0))
+        }
+
+        fn div(&self, rhs: &Self) -> Option<Self> {
+            Some(StubNumber(self.0 / rhs.0))
+        }
+    }
+
+    #[test]
+    fn test_numeric_stub() {
+        let stub = |s: &str| Object::Str {
+            value: s.to_string(),
+        };
+        let tests = [
+            ("+", 7, 2, stub("stub(9)")),
+            ("-", 7, 2, stub("stub(5)")),
+            ("*", 7, 2, stub("stub(14)")),
+            ("/", 7, 2, stub("stub(3)")),
+            ("<", 7, 2, Object::BOOLEAN_FALSE),
+            ("!=", 7, 2, Object::BOOLEAN_TRUE),
+        ];
+        for (operator, left, right, expect) in tests.iter() {
+            let evaluated = Eval::eval_numeric_infix_expression(
+                operator,
+                StubNumber(*left),
+                StubNumber(*right),
+            );
+            assert_eq!(evaluated, *expect, "{} {} {}", left, operator, right);
+        }
+        assert_eq!(
+            StubNumber::from_object(&Object::integer(4)),
+            Some(StubNumber(4))
+        );
+    }
+
     #[test]
     fn test_eval_boolean_expression() {
         let tests = [
@@ -269,6 +1619,273 @@ mod test {
         do_test(&tests);
     }
 
+    #[test]
+    fn test_ordering_incomparable_types() {
+        let tests = [
+            (
+                "true < false;",
+                Object::error("unknown operator: BOOLEAN < BOOLEAN"),
+            ),
+            (
+                "true > false;",
+                Object::error("unknown operator: BOOLEAN > BOOLEAN"),
+            ),
+            (
+                "null < null;",
+                Object::error("unknown operator: NULL < NULL"),
+            ),
+            ("true == true;", Object::BOOLEAN_TRUE),
+            (
+                r#"1 < "a";"#,
+                Object::error("cannot compare INTEGER with STRING"),
+            ),
+            (
+                "1 < true;",
+                Object::error("cannot compare INTEGER with BOOLEAN"),
+            ),
+        ];
+
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_chained_comparison_message() {
+        let tests = [
+            ("1 < 2 < 3;", Object::error("cannot compare BOOLEAN with INTEGER; comparisons do not chain, write `a < b && b < c` instead")),
+            ("3 > 2 > 1;", Object::error("cannot compare BOOLEAN with INTEGER; comparisons do not chain, write `a > b && b > c` instead")),
+            ("let x = 5; 1 < x < 10;", Object::error("cannot compare BOOLEAN with INTEGER; comparisons do not chain, write `a < b && b < c` instead")),
+            // 書き直した比較は期待通りに評価できる
+            ("let x = 5; 1 < x && x < 10;", Object::BOOLEAN_TRUE),
+        ];
+
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_logical_operators() {
+        let tests = [
+            ("true && true;", Object::BOOLEAN_TRUE),
+            ("true && false;", Object::BOOLEAN_FALSE),
+            ("false || true;", Object::BOOLEAN_TRUE),
+            ("false || false;", Object::BOOLEAN_FALSE),
+            ("true && 5;", Object::BOOLEAN_TRUE),
+            ("false && 3;", Object::BOOLEAN_FALSE),
+            // 依頼の例では`false`だったが、3は真とみなすので`true && 5`と揃えて`true`になる
+            ("false || 3;", Object::BOOLEAN_TRUE),
+            ("false || null;", Object::BOOLEAN_FALSE),
+            ("5 || false;", Object::BOOLEAN_TRUE),
+            ("1 < 2 && 2 < 3;", Object::BOOLEAN_TRUE),
+        ];
+
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_string_literal() {
+        let tests = [
+            (
+                r#""Hello World!";"#,
+                Object::Str {
+                    value: "Hello World!".to_string(),
+                },
+            ),
+            (
+                r#""a\nb";"#,
+                Object::Str {
+                    value: "a\nb".to_string(),
+                },
+            ),
+            (r#""a" == "a";"#, Object::BOOLEAN_TRUE),
+            (r#""a" != "b";"#, Object::BOOLEAN_TRUE),
+        ];
+
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_string_comparison() {
+        let tests = [
+            (r#""a" < "b";"#, Object::BOOLEAN_TRUE),
+            (r#""a" > "b";"#, Object::BOOLEAN_FALSE),
+            (r#""b" > "abc";"#, Object::BOOLEAN_TRUE),
+            // 先頭部分が同じなら短い方が小さい
+            (r#""ab" < "abc";"#, Object::BOOLEAN_TRUE),
+            (r#""abc" > "ab";"#, Object::BOOLEAN_TRUE),
+            (r#""" < "a";"#, Object::BOOLEAN_TRUE),
+            (r#""abc" < "abc";"#, Object::BOOLEAN_FALSE),
+            (r#""abc" == "abc";"#, Object::BOOLEAN_TRUE),
+            (r#""ab" == "abc";"#, Object::BOOLEAN_FALSE),
+            // 大文字は小文字より前のコードポイント
+            (r#""Z" < "a";"#, Object::BOOLEAN_TRUE),
+            (r#""é" > "z";"#, Object::BOOLEAN_TRUE),
+            (
+                r#""a" * "b";"#,
+                Object::error("unknown operator: STRING * STRING"),
+            ),
+            (
+                r#""a" - "b";"#,
+                Object::error("unknown operator: STRING - STRING"),
+            ),
+            (
+                r#""a" < 1;"#,
+                Object::error("cannot compare STRING with INTEGER"),
+            ),
+        ];
+
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_array_literal() {
+        let tests = [
+            ("[];", Object::Array { elements: vec![] }),
+            (
+                "[1, 2 * 2, 3 + 3];",
+                Object::Array {
+                    elements: vec![
+                        Object::Integer { value: 1 },
+                        Object::Integer { value: 4 },
+                        Object::Integer { value: 6 },
+                    ],
+                },
+            ),
+            ("[1, foo];", Object::error("identifier not found: foo")),
+            (
+                "[1, 2] + [foo];",
+                Object::error("identifier not found: foo"),
+            ),
+        ];
+
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_array_concatenation() {
+        let tests = [
+            (
+                "[1, 2] + [3, 4];",
+                Object::Array {
+                    elements: vec![
+                        Object::Integer { value: 1 },
+                        Object::Integer { value: 2 },
+                        Object::Integer { value: 3 },
+                        Object::Integer { value: 4 },
+                    ],
+                },
+            ),
+            ("[] + [];", Object::Array { elements: vec![] }),
+            (
+                "[] + [1];",
+                Object::Array {
+                    elements: vec![Object::Integer { value: 1 }],
+                },
+            ),
+            (
+                "[1] + [];",
+                Object::Array {
+                    elements: vec![Object::Integer { value: 1 }],
+                },
+            ),
+            ("let a = [1]; a + a == [1, 1];", Object::BOOLEAN_TRUE),
+            (
+                "[1, 2] + 3;",
+                Object::error("type mismatch: ARRAY + INTEGER"),
+            ),
+            (
+                "true + [1];",
+                Object::error("type mismatch: BOOLEAN + ARRAY"),
+            ),
+            (
+                "[1] - [1];",
+                Object::error("unknown operator: ARRAY - ARRAY"),
+            ),
+        ];
+
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_nested_array_equality() {
+        let tests = [
+            ("[[1], [2]] == [[1], [2]];", Object::BOOLEAN_TRUE),
+            ("[[1], [2]] != [[1], [2]];", Object::BOOLEAN_FALSE),
+            ("[[1], [2]] == [[1], [3]];", Object::BOOLEAN_FALSE),
+            ("[[1, [2, [3]]]] == [[1, [2, [3]]]];", Object::BOOLEAN_TRUE),
+            ("[[1, [2, [3]]]] == [[1, [2, [4]]]];", Object::BOOLEAN_FALSE),
+            // 入れ子の長さが違えば等しくない
+            ("[[1], [2]] == [[1], [2, 3]];", Object::BOOLEAN_FALSE),
+            ("[[]] == [[], []];", Object::BOOLEAN_FALSE),
+            ("[[1]] == [1];", Object::BOOLEAN_FALSE),
+            (
+                r#"[["a", true], {"k": [1]}] == [["a", true], {"k": [1]}];"#,
+                Object::BOOLEAN_TRUE,
+            ),
+            (
+                "let a = [[1], [2]]; let b = [[1]] + [[2]]; a == b;",
+                Object::BOOLEAN_TRUE,
+            ),
+        ];
+
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_array_as_hash_value() {
+        let nested = Object::Array {
+            elements: vec![
+                Object::Integer { value: 1 },
+                Object::Array {
+                    elements: vec![Object::Integer { value: 2 }],
+                },
+            ],
+        };
+        let tests = [
+            (r#"{"xs": [1, [2]]}["xs"];"#, nested.clone()),
+            (
+                r#"let h = {"xs": [1, [2]]}; h["xs"][1][0];"#,
+                Object::Integer { value: 2 },
+            ),
+            (
+                r#"let h = {"xs": [1, [2]]}; h["xs"] == [1, [2]];"#,
+                Object::BOOLEAN_TRUE,
+            ),
+            (r#"let h = set({}, "xs", [1, [2]]); h["xs"];"#, nested),
+            (r#"{"a": [1]} == {"a": [1]};"#, Object::BOOLEAN_TRUE),
+            (r#"{"a": [1]} == {"a": [2]};"#, Object::BOOLEAN_FALSE),
+            // 配列は値にはできてもキーにはできない
+            (
+                r#"{[1]: "xs"};"#,
+                Object::error("unusable as hash key: ARRAY"),
+            ),
+        ];
+
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_operand_error_propagation() {
+        let tests = [
+            (
+                "(5 + true) + 5;",
+                Object::error("type mismatch: INTEGER + BOOLEAN"),
+            ),
+            (
+                "5 + (5 + true);",
+                Object::error("type mismatch: INTEGER + BOOLEAN"),
+            ),
+            (
+                "-(true + 1);",
+                Object::error("type mismatch: BOOLEAN + INTEGER"),
+            ),
+            ("!x;", Object::error("identifier not found: x")),
+            ("x && true;", Object::error("identifier not found: x")),
+            ("true || x;", Object::BOOLEAN_TRUE),
+            ("false || x;", Object::error("identifier not found: x")),
+        ];
+
+        do_test(&tests);
+    }
+
     #[test]
     fn test_bang_operator() {
         let tests = [
@@ -278,6 +1895,37 @@ mod test {
             ("!!true;", Object::BOOLEAN_TRUE),
             ("!!false;", Object::BOOLEAN_FALSE),
             ("!!5;", Object::BOOLEAN_TRUE),
+            ("!null;", Object::BOOLEAN_TRUE),
+            ("!!null;", Object::BOOLEAN_FALSE),
+            ("!0;", Object::BOOLEAN_FALSE),
+            (r#"!"";"#, Object::BOOLEAN_FALSE),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_plus_operator() {
+        let tests = [
+            ("+5;", Object::Integer { value: 5 }),
+            ("+-5;", Object::Integer { value: -5 }),
+            ("-+5;", Object::Integer { value: -5 }),
+            ("let x = 3; 1 + +x;", Object::Integer { value: 4 }),
+            ("+true;", Object::error("unknown operator: +BOOLEAN")),
+            (r#"+"a";"#, Object::error("unknown operator: +STRING")),
+            ("+null;", Object::error("unknown operator: +NULL")),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_null_literal() {
+        let tests = [
+            ("null;", Object::NULL),
+            ("null == null;", Object::BOOLEAN_TRUE),
+            ("null != null;", Object::BOOLEAN_FALSE),
+            ("null == false;", Object::BOOLEAN_FALSE),
+            ("let x = null; x;", Object::NULL),
+            ("if (null) {10;} else {20;};", Object::Integer { value: 20 }),
         ];
         do_test(&tests);
     }
@@ -285,13 +1933,40 @@ mod test {
     #[test]
     fn test_if_expressions() {
         let tests = [
-            ("if (true) {10;};", Object::Integer {value: 10}),
+            ("if (true) {10;};", Object::Integer { value: 10 }),
             ("if (false) {10;};", Object::NULL),
-            ("if (1) {10;};", Object::Integer {value: 10}),
-            ("if (1 < 2) {10;};", Object::Integer {value: 10}),
+            ("if (1) {10;};", Object::Integer { value: 10 }),
+            ("if (1 < 2) {10;};", Object::Integer { value: 10 }),
             ("if (1 > 2) {10;};", Object::NULL),
-            ("if (1 < 2) {10;} else {20;};", Object::Integer {value: 10}),
-            ("if (1 > 2) {10;} else {20;};", Object::Integer {value: 20}),
+            (
+                "if (1 < 2) {10;} else {20;};",
+                Object::Integer { value: 10 },
+            ),
+            (
+                "if (1 > 2) {10;} else {20;};",
+                Object::Integer { value: 20 },
+            ),
+            // 条件のエラーは分岐を選ばずにそのまま返す
+            (
+                "if (foo) {1;} else {2;};",
+                Object::error("identifier not found: foo"),
+            ),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_bind_if_without_else() {
+        let env = Rc::new(RefCell::new(Environment::new()));
+        let program = Parser::new(Lexer::new("let x = if (false) { 1; };"))
+            .parse_program()
+            .expect("fail parse program.");
+        Eval::new(Rc::clone(&env)).eval_program(&program);
+        assert_eq!(env.borrow().get("x"), Some(Object::NULL));
+
+        let tests = [
+            ("let x = if (false) { 1; }; x;", Object::NULL),
+            ("let x = if (true) { 1; }; x;", Object::Integer { value: 1 }),
         ];
         do_test(&tests);
     }
@@ -299,49 +1974,1443 @@ mod test {
     #[test]
     fn test_eval_return_statements() {
         let tests = [
-            ("return 5;", Object::ReturnValue {value: Box::new(Object::Integer { value: 5 })}),
-            ("return 10;", Object::ReturnValue {value: Box::new(Object::Integer { value: 10 })}),
-            ("5; return 5;", Object::ReturnValue {value: Box::new(Object::Integer { value: 5 })}),
-            ("return 5; 5;", Object::ReturnValue {value: Box::new(Object::Integer { value: 5 })}),
-            ("5; return 5; 5;", Object::ReturnValue {value: Box::new(Object::Integer { value: 5 })}),
-            ("return -5;", Object::ReturnValue {value: Box::new(Object::Integer { value: -5 })}),
-            ("return -10;", Object::ReturnValue {value: Box::new(Object::Integer { value: -10 })}),
-            ("return 5 + 5 + 5 + 5 - 10;", Object::ReturnValue {value: Box::new(Object::Integer {value: 10})}),
-            ("return 2 * 2 * 2 * 2 * 2;", Object::ReturnValue {value: Box::new(Object::Integer {value: 32})}),
-            ("return -50 + 100 + -50;", Object::ReturnValue {value: Box::new(Object::Integer {value: 0})}),
-            ("return 5 * 2 + 10;", Object::ReturnValue {value: Box::new(Object::Integer {value: 20})}),
-            ("return 5 + 2 * 10;", Object::ReturnValue {value: Box::new(Object::Integer {value: 25})}),
-            ("return 20 + 2 * -10;", Object::ReturnValue {value: Box::new(Object::Integer {value: 0})}),
-            ("return 50 / 2 * 2 + 10;", Object::ReturnValue {value: Box::new(Object::Integer {value: 60})}),
-            ("return 2 * (5 + 10);", Object::ReturnValue {value: Box::new(Object::Integer {value: 30})}),
-            ("return 3 * 3 * 3 + 10;", Object::ReturnValue {value: Box::new(Object::Integer {value: 37})}),
-            ("return 3 * (3 * 3 + 10);", Object::ReturnValue {value: Box::new(Object::Integer {value: 57})}),
-            ("return (5 + 10 * 2 + 15 / 3) * 2 + -10;", Object::ReturnValue {value: Box::new(Object::Integer {value: 50})}),
-            ("return 1 < 2;", Object::ReturnValue {value: Box::new(Object::Boolean { value: true })}),
-            ("return 1 > 2;", Object::ReturnValue {value: Box::new(Object::Boolean { value: false })}),
-            ("return 1 < 1;", Object::ReturnValue {value: Box::new(Object::Boolean { value: false })}),
-            ("return 1 > 1;", Object::ReturnValue {value: Box::new(Object::Boolean { value: false })}),
-            ("return 1 == 1;", Object::ReturnValue {value: Box::new(Object::Boolean { value: true })}),
-            ("return 1 != 1;", Object::ReturnValue {value: Box::new(Object::Boolean { value: false })}),
-            ("return 1 == 2;", Object::ReturnValue {value: Box::new(Object::Boolean { value: false })}),
-            ("return 1 != 2;", Object::ReturnValue {value: Box::new(Object::Boolean { value: true })}),
+            ("return 5;", Object::Integer { value: 5 }),
+            ("return 10;", Object::Integer { value: 10 }),
+            ("5; return 5;", Object::Integer { value: 5 }),
+            ("return 5; 5;", Object::Integer { value: 5 }),
+            ("5; return 5; 5;", Object::Integer { value: 5 }),
+            ("return -5;", Object::Integer { value: -5 }),
+            ("return -10;", Object::Integer { value: -10 }),
+            ("return 5 + 5 + 5 + 5 - 10;", Object::Integer { value: 10 }),
+            ("return 2 * 2 * 2 * 2 * 2;", Object::Integer { value: 32 }),
+            ("return -50 + 100 + -50;", Object::Integer { value: 0 }),
+            ("return 5 * 2 + 10;", Object::Integer { value: 20 }),
+            ("return 5 + 2 * 10;", Object::Integer { value: 25 }),
+            ("return 20 + 2 * -10;", Object::Integer { value: 0 }),
+            ("return 50 / 2 * 2 + 10;", Object::Integer { value: 60 }),
+            ("return 2 * (5 + 10);", Object::Integer { value: 30 }),
+            ("return 3 * 3 * 3 + 10;", Object::Integer { value: 37 }),
+            ("return 3 * (3 * 3 + 10);", Object::Integer { value: 57 }),
+            (
+                "return (5 + 10 * 2 + 15 / 3) * 2 + -10;",
+                Object::Integer { value: 50 },
+            ),
+            ("return 1 < 2;", Object::Boolean { value: true }),
+            ("return 1 > 2;", Object::Boolean { value: false }),
+            ("return 1 < 1;", Object::Boolean { value: false }),
+            ("return 1 > 1;", Object::Boolean { value: false }),
+            ("return 1 == 1;", Object::Boolean { value: true }),
+            ("return 1 != 1;", Object::Boolean { value: false }),
+            ("return 1 == 2;", Object::Boolean { value: false }),
+            ("return 1 != 2;", Object::Boolean { value: true }),
+        ];
+
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_let_statements() {
+        let tests = [
+            ("let a = 5; a;", Object::Integer { value: 5 }),
+            ("let a = 5 * 5; a;", Object::Integer { value: 25 }),
+            ("let a = 5; let b = a; b;", Object::Integer { value: 5 }),
+            (
+                "let a = 5; let b = a; let c = a + b + 5; c;",
+                Object::Integer { value: 15 },
+            ),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_do_while_statement() {
+        let tests = [
+            ("let i = 0; do { let i = i + 1; } while (i < 3); i;", Object::Integer { value: 3 }),
+            // 条件が最初から偽でも本体は一度だけ評価される
+            ("let i = 0; do { let i = i + 1; } while (false); i;", Object::Integer { value: 1 }),
+            ("let i = 10; do { let i = i + 1; } while (i < 3); i;", Object::Integer { value: 11 }),
+            ("do { 1; } while (false);", Object::NULL),
+            // 本体のreturnはループの外に伝わる
+            (
+                "let f = fn() { let i = 0; do { if (i == 2) { return i * 10; }; let i = i + 1; } while (true); }; f();",
+                Object::Integer { value: 20 },
+            ),
+            ("do { x; } while (true);", Object::error("identifier not found: x")),
+            ("do { 1; } while (y);", Object::error("identifier not found: y")),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_for_statement() {
+        let tests = [
+            ("let sum = 0; for (let i = 1; i < 11; i = i + 1) { sum = sum + i; } sum;", Object::Integer { value: 55 }),
+            ("for (let i = 0; i < 3; i = i + 1) { 1; }", Object::NULL),
+            // 条件が最初から偽なら本体は評価されない
+            ("let n = 0; for (let i = 0; false; i = i + 1) { n = 1; } n;", Object::Integer { value: 0 }),
+            // 初期化で束縛した名前はループの外に漏れない
+            ("for (let i = 0; i < 3; i = i + 1) {} i;", Object::error("identifier not found: i")),
+            ("let i = 10; for (let i = 0; i < 3; i = i + 1) {} i;", Object::Integer { value: 10 }),
+            // 本体のlet文もループの環境に束縛する
+            ("let x = 1; for (let i = 0; i < 3; i = i + 1) { let x = i; } x;", Object::Integer { value: 1 }),
+            // 外側の変数を初期化に使う
+            ("let i = 0; for (i = 5; i < 8; i = i + 1) {} i;", Object::Integer { value: 8 }),
+            // continueでも更新の文は評価する
+            (
+                "let sum = 0; for (let i = 0; i < 5; i = i + 1) { if (i == 2) { continue; }; sum = sum + i; } sum;",
+                Object::Integer { value: 8 },
+            ),
+            ("let n = 0; for (;;) { n = n + 1; if (n == 4) { break; }; } n;", Object::Integer { value: 4 }),
+            (
+                "let f = fn() { for (let i = 0; i < 10; i = i + 1) { if (i == 3) { return i * 2; }; } -1; }; f();",
+                Object::Integer { value: 6 },
+            ),
+            ("for (let i = 0; i < x; i = i + 1) {}", Object::error("identifier not found: x")),
+            ("for (let i = 0; i < 1; j = i) {}", Object::error("identifier not found: j")),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_for_in_statement() {
+        let tests = [
+            ("let sum = 0; for (x in [1, 2, 3]) { sum = sum + x; } sum;", Object::Integer { value: 6 }),
+            // 文字列は一文字ずつ取り出す
+            (
+                r#"let s = ""; for (c in "日本語") { s = join([c, s], ""); } s;"#,
+                Object::Str { value: "語本日".to_string() },
+            ),
+            ("let n = 0; for (x in []) { n = 1; } n;", Object::Integer { value: 0 }),
+            ("for (x in [1]) { 1; }", Object::NULL),
+            // 要素を束縛した名前はループの外に漏れない
+            ("let x = 10; for (x in [1, 2]) {} x;", Object::Integer { value: 10 }),
+            ("for (x in [1, 2]) {} x;", Object::error("identifier not found: x")),
+            (
+                "let sum = 0; for (x in [1, 2, 3, 4]) { if (x == 2) { continue; }; if (x == 4) { break; }; sum = sum + x; } sum;",
+                Object::Integer { value: 4 },
+            ),
+            ("let f = fn(xs) { for (x in xs) { if (x > 1) { return x; }; } 0; }; f([1, 5, 7]);", Object::Integer { value: 5 }),
+            // 繰り返しごとに新しい環境で束縛するので、関数はそれぞれの要素を覚える
+            (
+                "let fs = []; for (x in [1, 2]) { fs = fs + [fn() { x; }]; } fs[0]() + fs[1]() * 10;",
+                Object::Integer { value: 21 },
+            ),
+            ("for (x in 5) {}", Object::error("cannot iterate over INTEGER")),
+            (r#"for (x in {"a": 1}) {}"#, Object::error("cannot iterate over HASH")),
+            ("for (x in y) {}", Object::error("identifier not found: y")),
         ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_range() {
+        let tests = [
+            ("1..10;", Object::Range { start: 1, end: 10 }),
+            ("let n = 3; 0..n * 2;", Object::Range { start: 0, end: 6 }),
+            // 終わりは含まない
+            (
+                "let sum = 0; for (i in 1..5) { sum = sum + i; } sum;",
+                Object::integer(10),
+            ),
+            // 終わりが始まり以下なら一度も繰り返さない
+            (
+                "let n = 0; for (i in 5..1) { n = n + 1; } n;",
+                Object::integer(0),
+            ),
+            (
+                "let n = 0; for (i in 3..3) { n = n + 1; } n;",
+                Object::integer(0),
+            ),
+            (
+                "let last = 0; for (i in -2..0) { last = i; } last;",
+                Object::integer(-1),
+            ),
+            ("let r = 0..2; r == 0..2;", Object::BOOLEAN_TRUE),
+            (
+                "type(1..2);",
+                Object::Str {
+                    value: "RANGE".to_string(),
+                },
+            ),
+            (
+                r#""a".."b";"#,
+                Object::error("range bounds must be INTEGER, got STRING..STRING"),
+            ),
+            (
+                "1..true;",
+                Object::error("range bounds must be INTEGER, got INTEGER..BOOLEAN"),
+            ),
+            ("1..x;", Object::error("identifier not found: x")),
+        ];
+        do_test(&tests);
+        assert_eq!(test_eval("-1..2;").inspect(), "-1..2");
+    }
+
+    #[test]
+    fn test_assign_statement() {
+        let tests = [
+            ("let x = 1; x = x + 1; x;", Object::Integer { value: 2 }),
+            ("let x = 1; x = 5;", Object::NULL),
+            // 関数の中から外側の束縛を書き換える
+            (
+                "let n = 0; let inc = fn() { n = n + 1; }; inc(); inc(); n;",
+                Object::Integer { value: 2 },
+            ),
+            ("x = 1;", Object::error("identifier not found: x")),
+            (
+                "let x = 1; x = y;",
+                Object::error("identifier not found: y"),
+            ),
+        ];
+        do_test(&tests);
+    }
 
+    #[test]
+    fn test_break_and_continue() {
+        let tests = [
+            // 条件が真のままでもbreakでループを抜ける
+            ("let i = 0; do { let i = i + 1; if (i == 3) { break; }; } while (true); i;", Object::Integer { value: 3 }),
+            ("do { break; } while (true);", Object::NULL),
+            // continueは本体の残りを飛ばして条件を判定する
+            (
+                "let i = 0; let sum = 0; do { let i = i + 1; if (i == 2) { continue; }; let sum = sum + i; } while (i < 4); sum;",
+                Object::Integer { value: 8 },
+            ),
+            ("let i = 0; do { let i = i + 1; continue; let i = 100; } while (i < 3); i;", Object::Integer { value: 3 }),
+            // 入れ子のループでは一番内側のループだけを抜ける
+            (
+                "let n = 0; let i = 0; do { let i = i + 1; do { let n = n + 1; break; } while (true); } while (i < 3); n;",
+                Object::Integer { value: 3 },
+            ),
+            ("break;", Object::error("`break` outside of a loop")),
+            ("if (true) { continue; };", Object::error("`continue` outside of a loop")),
+            // 関数の本体は呼び出し元のループの中ではない
+            ("let f = fn() { break; }; do { f(); } while (false);", Object::error("`break` outside of a loop")),
+            ("do { let f = fn() { continue; }; f(); } while (false);", Object::error("`continue` outside of a loop")),
+            // 関数の中のループのbreakは関数の中で完結する
+            ("let f = fn() { do { break; } while (true); 1; }; do { f(); } while (false); f();", Object::Integer { value: 1 }),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_null_binding() {
+        let tests = [
+            // nullを束縛した識別子は未定義ではない
+            ("let x = null; x;", Object::NULL),
+            ("let x = null; y;", Object::error("identifier not found: y")),
+            ("let f = fn() { null; }; let x = f(); x;", Object::NULL),
+            ("let x = null; let f = fn() { x; }; f();", Object::NULL),
+            // 組み込み関数と同じ名前にnullを束縛すると組み込み関数を隠す
+            ("let len = null; len;", Object::NULL),
+        ];
         do_test(&tests);
     }
 
+    #[test]
+    fn test_eval_reader() {
+        let input = "let x = 2; let double = fn(n) { n * 2; }; double(x); x + 1;";
+        let env = Rc::new(RefCell::new(Environment::new()));
+        let mut results = vec![];
+        let last = Eval::new(Rc::clone(&env))
+            .eval_reader(input.as_bytes(), |obj| results.push(obj.clone()));
+
+        assert_eq!(last, Ok(Object::Integer { value: 3 }));
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0], Object::NULL);
+        assert_eq!(results[2], Object::Integer { value: 4 });
+        assert_eq!(results[3], Object::Integer { value: 3 });
+        // 同じ環境を共有して評価する
+        assert_eq!(env.borrow().get("x"), Some(Object::Integer { value: 2 }));
+
+        // パースエラーがあればそこで止まる
+        let env = Rc::new(RefCell::new(Environment::new()));
+        let mut results = vec![];
+        let last = Eval::new(Rc::clone(&env)).eval_reader(
+            "let y = 1;\ny + 1;\nlet = 2;\nlet z = 3;".as_bytes(),
+            |obj| results.push(obj.clone()),
+        );
+        assert!(last.is_err());
+        assert_eq!(results, vec![Object::NULL, Object::Integer { value: 2 }]);
+        assert_eq!(env.borrow().get("y"), Some(Object::Integer { value: 1 }));
+        // エラーより後の文は評価しない
+        assert_eq!(env.borrow().get("z"), None);
+    }
+
+    #[test]
+    fn test_import() {
+        let dir = std::env::temp_dir().join(format!("monkey_rs_import_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let files = [
+            (
+                "math.mk",
+                "let square = fn(x) { x * x; }; let base = 10; let add_base = fn(x) { x + base; };",
+            ),
+            // 相対パスは読み込み中のファイルのディレクトリから探す
+            // スクリプトとしてパースするので文末のセミコロンは省略できる
+            ("main.mk", "import \"math.mk\"\nlet y = square(3)"),
+            ("a.mk", "import \"b.mk\"; let a = 1;"),
+            ("b.mk", "import \"a.mk\"; let b = 2;"),
+            ("secret.mk", "let s = secret;"),
+            ("host.mk", "let d = double(21);"),
+        ];
+        for (name, source) in files.iter() {
+            std::fs::write(dir.join(name), source).unwrap();
+        }
+        let path = |name: &str| dir.join(name).display().to_string();
+
+        let tests = [
+            (
+                format!("import \"{}\"; add_base(y);", path("main.mk")),
+                Object::integer(19),
+            ),
+            // 読み込んだファイルの関数はそのファイルの束縛を参照する
+            (
+                format!("let base = 0; import \"{}\"; base;", path("math.mk")),
+                Object::integer(10),
+            ),
+            (format!("import \"{}\";", path("math.mk")), Object::NULL),
+            (
+                format!("import \"{}\";", path("a.mk")),
+                Object::error("cyclic import of \"a.mk\""),
+            ),
+            (
+                format!("import \"{}\";", path("none.mk")),
+                Object::error(&format!(
+                    "cannot import \"{}\": file not found",
+                    path("none.mk")
+                )),
+            ),
+            // 読み込んだファイルからは読み込んだ側の束縛は見えない
+            (
+                format!("let secret = 1; import \"{}\";", path("secret.mk")),
+                Object::error("identifier not found: secret"),
+            ),
+        ];
+        for (input, expected) in tests.iter() {
+            assert_eq!(&test_eval(input), expected, "{}", input);
+        }
+
+        // 読み込んだファイルからも埋め込む側が登録した組み込み関数を呼べる
+        let mut builtins: HashMap<String, BuiltinFunction> = HashMap::new();
+        builtins.insert("double".to_string(), double);
+        let env = Rc::new(RefCell::new(Environment::with_builtins(builtins)));
+        let input = format!(
+            "let double = 0; import \"{}\"; [d, double];",
+            path("host.mk")
+        );
+        let program = Parser::new(Lexer::new(&input)).parse_program().unwrap();
+        assert_eq!(
+            Eval::new(env).eval_program(&program),
+            Object::Array {
+                // 組み込み関数は読み込んだ側に取り込まないので、読み込んだ側の束縛はそのまま残る
+                elements: vec![Object::integer(42), Object::integer(0)]
+            }
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_eval_keeps_environment() {
+        let env = Rc::new(RefCell::new(Environment::new()));
+        let mut eval = Eval::new(Rc::clone(&env));
+        // 同じ評価器で評価したプログラムは束縛を共有する
+        for (input, expected) in [
+            ("let x = 2;", Object::NULL),
+            ("x * 3;", Object::Integer { value: 6 }),
+        ]
+        .iter()
+        {
+            let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+            assert_eq!(&eval.eval_program(&program), expected);
+        }
+        // 評価器に渡した環境から束縛を読める
+        assert_eq!(env.borrow().get("x"), Some(Object::Integer { value: 2 }));
+    }
+
+    #[test]
+    fn test_step_limit() {
+        let options = EvalOptions {
+            max_steps: Some(1000),
+            ..EvalOptions::new()
+        };
+        // 終わらない再帰呼び出しを打ち切る
+        let input = "let f = fn(n) { f(n + 1); }; f(0);";
+        let program = Parser::new(Lexer::new(input))
+            .parse_program()
+            .expect("fail parse program.");
+        let env = Rc::new(RefCell::new(Environment::new()));
+        let evaluated = Eval::with_options(Rc::clone(&env), options.clone()).eval_program(&program);
+        assert_eq!(evaluated, Object::error("execution step limit exceeded"));
+
+        // 終わらないループも打ち切る
+        for input in [
+            "for (;;) {}",
+            "for (;;) { let x = 1; }",
+            "do {} while (true)",
+        ]
+        .iter()
+        {
+            let program = Parser::new(Lexer::new(&format!("{};", input)))
+                .parse_program()
+                .expect("fail parse program.");
+            let env = Rc::new(RefCell::new(Environment::new()));
+            let evaluated = Eval::with_options(env, options.clone()).eval_program(&program);
+            assert_eq!(
+                evaluated,
+                Object::error("execution step limit exceeded"),
+                "{}",
+                input
+            );
+        }
+
+        // 上限に達しない評価は影響を受けない
+        let program = Parser::new(Lexer::new("let x = 1 + 2; x * 3;"))
+            .parse_program()
+            .expect("fail parse program.");
+        let env = Rc::new(RefCell::new(Environment::new()));
+        let evaluated = Eval::with_options(Rc::clone(&env), options).eval_program(&program);
+        assert_eq!(evaluated, Object::Integer { value: 9 });
+
+        // 上限は文と式を合わせた数で数える
+        let env = Rc::new(RefCell::new(Environment::new()));
+        let options = EvalOptions {
+            max_steps: Some(2),
+            ..EvalOptions::new()
+        };
+        let evaluated = Eval::with_options(Rc::clone(&env), options).eval_program(&program);
+        assert_eq!(evaluated, Object::error("execution step limit exceeded"));
+    }
+
+    #[test]
+    fn test_compare_booleans_as_integers() {
+        let program = Parser::new(Lexer::new("true > false;"))
+            .parse_program()
+            .expect("fail parse program.");
+
+        // 既定ではMonkeyと同じくエラーにする
+        let env = Rc::new(RefCell::new(Environment::new()));
+        let evaluated = Eval::new(Rc::clone(&env)).eval_program(&program);
+        assert_eq!(
+            evaluated,
+            Object::error("unknown operator: BOOLEAN > BOOLEAN")
+        );
+
+        // 設定すればtrueを1、falseを0として比べる
+        let options = EvalOptions {
+            compare_booleans_as_integers: true,
+            ..EvalOptions::new()
+        };
+        let tests = [
+            ("true > false;", Object::BOOLEAN_TRUE),
+            ("true < false;", Object::BOOLEAN_FALSE),
+            ("false < true;", Object::BOOLEAN_TRUE),
+            ("true > true;", Object::BOOLEAN_FALSE),
+            // 等価比較と真偽値と整数の比較は変わらない
+            ("true == true;", Object::BOOLEAN_TRUE),
+            (
+                "true > 0;",
+                Object::error("cannot compare BOOLEAN with INTEGER; comparisons do not chain, write `a > b && b > c` instead"),
+            ),
+        ];
+        for (input, expected) in tests.iter() {
+            let program = Parser::new(Lexer::new(input))
+                .parse_program()
+                .expect("fail parse program.");
+            let env = Rc::new(RefCell::new(Environment::new()));
+            let evaluated =
+                Eval::with_options(Rc::clone(&env), options.clone()).eval_program(&program);
+            assert_eq!(&evaluated, expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_let_array_pattern() {
+        let tests = [
+            (
+                "let [a, b] = [1, 2]; a * 10 + b;",
+                Object::Integer { value: 12 },
+            ),
+            (
+                "let [head, ...tail] = [1, 2, 3]; tail;",
+                Object::Array {
+                    elements: vec![Object::Integer { value: 2 }, Object::Integer { value: 3 }],
+                },
+            ),
+            (
+                "let [head, ...tail] = [1]; tail;",
+                Object::Array { elements: vec![] },
+            ),
+            (
+                "let [a, b] = [1, 2, 3];",
+                Object::error("destructuring mismatch: expected 2 elements, got 3"),
+            ),
+            (
+                "let [a, b] = [1]; a;",
+                Object::error("destructuring mismatch: expected 2 elements, got 1"),
+            ),
+            (
+                "let [a, b, ...c] = [1];",
+                Object::error("destructuring mismatch: expected at least 2 elements, got 1"),
+            ),
+            (
+                "let [a] = 1;",
+                Object::error("cannot destructure INTEGER as ARRAY"),
+            ),
+            ("let x = y; 1;", Object::error("identifier not found: y")),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_function_object() {
+        let evaluated = test_eval("fn(x) { x + 2; };");
+        if let Object::Function {
+            parameters,
+            body,
+            env: _,
+            name,
+        } = evaluated
+        {
+            assert_eq!(name, None);
+            assert_eq!(parameters.len(), 1);
+            assert_eq!(parameters[0].to_string(), "x");
+            assert_eq!(body.to_string(), "{(x + 2);}");
+        } else {
+            panic!("関数オブジェクトではありません。{:?}", evaluated);
+        }
+    }
+
+    #[test]
+    fn test_empty_block() {
+        // 空のブロックはnullに評価する
+        let tests = [
+            ("if (true) {};", Object::NULL),
+            ("if (false) { 1; } else {};", Object::NULL),
+            ("fn() {}();", Object::NULL),
+            ("let f = fn(x) {}; f(1);", Object::NULL),
+            ("let f = fn() {}; f() == null;", Object::BOOLEAN_TRUE),
+        ];
+        do_test(&tests);
+
+        // 空の本体も空のブロックとして表示する
+        let evaluated = test_eval("fn() {};");
+        assert_eq!(evaluated.inspect(), "fn() {}");
+        if let Object::Function {
+            parameters: _,
+            body,
+            env: _,
+            name: _,
+        } = evaluated
+        {
+            assert_eq!(body.to_string(), "{}");
+        } else {
+            panic!("関数オブジェクトではありません。{:?}", evaluated);
+        }
+    }
+
+    #[test]
+    fn test_named_function_object() {
+        let evaluated = test_eval("let add = fn(x, y) { x + y; }; add;");
+        if let Object::Function {
+            parameters: _,
+            body: _,
+            env: _,
+            name,
+        } = evaluated
+        {
+            assert_eq!(name, Some("add".to_string()));
+        } else {
+            panic!("関数オブジェクトではありません。{:?}", evaluated);
+        }
+
+        // 別の名前に束縛し直しても定義したときの名前を保つ
+        let evaluated = test_eval("let add = fn(x, y) { x + y; }; let plus = add; plus(1);");
+        assert_eq!(
+            evaluated,
+            Object::error("wrong number of arguments to `add`. got=1, want=2")
+        );
+    }
+
+    #[test]
+    fn test_function_identity() {
+        // 関数は同じ関数リテラルの評価で作られたものどうしだけが等しい
+        let tests = [
+            ("let f = fn() {}; f == f;", Object::boolean(true)),
+            ("let f = fn() {}; let g = f; f == g;", Object::boolean(true)),
+            ("let f = fn() {}; f != f;", Object::boolean(false)),
+            // 本体が同じでも別に評価した関数は等しくない
+            ("let t = fn() {} == fn() {}; t;", Object::boolean(false)),
+            (
+                "let f = fn() {}; let g = fn() {}; f == g;",
+                Object::boolean(false),
+            ),
+            (
+                "let make = fn() { fn(x) { x; }; }; make() == make();",
+                Object::boolean(false),
+            ),
+            (
+                "let f = fn(n) { if (n == 0) { 0; } else { f(n - 1); }; }; [f] == [f];",
+                Object::boolean(true),
+            ),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_function_application() {
+        let tests = [
+            (
+                "let identity = fn(x) { x; }; identity(5);",
+                Object::Integer { value: 5 },
+            ),
+            (
+                "let identity = fn(x) { return x; }; identity(5);",
+                Object::Integer { value: 5 },
+            ),
+            (
+                "let double = fn(x) { x * 2; }; double(5);",
+                Object::Integer { value: 10 },
+            ),
+            (
+                "let add = fn(x, y) { x + y; }; add(5, 5);",
+                Object::Integer { value: 10 },
+            ),
+            (
+                "let add = fn(x, y) { x + y; }; add(5 + 5, add(5, 5));",
+                Object::Integer { value: 20 },
+            ),
+            ("fn(x) { x; }(5);", Object::Integer { value: 5 }),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_immediately_invoked_function() {
+        let tests = [
+            ("fn(x) { x * 2; }(21);", Object::Integer { value: 42 }),
+            ("fn(a, b) { a + b; }(3, 4);", Object::Integer { value: 7 }),
+            (
+                "fn(x) { fn(y) { x + y; }; }(1)(2);",
+                Object::Integer { value: 3 },
+            ),
+            ("fn() { 5; }() * 2;", Object::Integer { value: 10 }),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_closures() {
+        let input = "
+            let newAdder = fn(x) {
+                fn(y) { x + y; };
+            };
+            let addTwo = newAdder(2);
+            addTwo(2);
+        ";
+        assert_eq!(test_eval(input), Object::Integer { value: 4 });
+    }
+
+    #[test]
+    fn test_return_in_nested_block() {
+        let tests = [
+            (
+                "fn() { if (true) { return 1; }; return 2; }();",
+                Object::Integer { value: 1 },
+            ),
+            (
+                "fn() { if (false) { return 1; }; return 2; }();",
+                Object::Integer { value: 2 },
+            ),
+            (
+                "fn() { if (true) { if (true) { return 10; }; return 1; }; }();",
+                Object::Integer { value: 10 },
+            ),
+            (
+                "if (true) { if (true) { return 10; }; return 1; };",
+                Object::Integer { value: 10 },
+            ),
+            ("fn() { 1; 2; }();", Object::Integer { value: 2 }),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_nested_return() {
+        let tests = [
+            // 呼び出しごとに戻り値を一段だけ取り出す
+            ("let inner = fn() { return 1; 2; }; let outer = fn() { return inner() + 10; 3; }; outer();", Object::Integer { value: 11 }),
+            ("let f = fn() { return fn() { return 5; 6; }; 7; }; f()();", Object::Integer { value: 5 }),
+            ("let f = fn() { let g = fn() { return 1; }; g(); return 2; }; f();", Object::Integer { value: 2 }),
+            // 戻り値の式の中のreturnは二重に包まない
+            ("let g = fn(x) { return if (x) { return 1; } else { 2; }; 3; }; let h = fn() { let v = g(true); return v + 1; }; h();", Object::Integer { value: 2 }),
+            ("let g = fn(x) { return if (x) { return 1; } else { 2; }; 3; }; g(false);", Object::Integer { value: 2 }),
+            ("return fn() { return 1; }();", Object::Integer { value: 1 }),
+            ("let f = fn() { return x; }; f();", Object::error("identifier not found: x")),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_error_stops_evaluation() {
+        let tests = [
+            ("foobar; 5;", Object::error("identifier not found: foobar")),
+            (
+                "5; true < false; 10;",
+                Object::error("unknown operator: BOOLEAN < BOOLEAN"),
+            ),
+            (
+                "if (true) { true > false; 1; };",
+                Object::error("unknown operator: BOOLEAN > BOOLEAN"),
+            ),
+            (
+                "let f = fn() { x; 1; }; f(); 2;",
+                Object::error("identifier not found: x"),
+            ),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_hash_literal() {
+        let input = r#"let two = "two"; {"one": 10 - 9, two: 1 + 1, 3: 3, true: 4, "one": 5};"#;
+        // 何度評価しても書かれた順に表示される
+        for _ in 0..2 {
+            assert_eq!(
+                test_eval(input).inspect(),
+                r#"{"one": 5, "two": 2, 3: 3, true: 4}"#
+            );
+        }
+        let tests = [
+            ("{};", Object::Hash { pairs: vec![] }),
+            ("{[1]: 2};", Object::error("unusable as hash key: ARRAY")),
+            ("{foo: 1};", Object::error("identifier not found: foo")),
+            (r#"{"a": foo};"#, Object::error("identifier not found: foo")),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_switch_expression() {
+        let name = |s: &str| Object::Str {
+            value: s.to_string(),
+        };
+        let names = r#"let name = fn(x) { switch (x) { 1 => "one", 2 => "two", _ => "other" }; };"#;
+        let tests = [
+            (format!("{} name(1);", names), name("one")),
+            (format!("{} name(2);", names), name("two")),
+            // どの値にも一致しなければ`_`の分岐を評価する
+            (format!("{} name(3);", names), name("other")),
+            (format!("{} name(\"1\");", names), name("other")),
+            (
+                r#"switch ("b") { "a" => 1, "b" => 2 };"#.to_string(),
+                Object::Integer { value: 2 },
+            ),
+            (
+                "switch (1 < 2) { false => 0, true => 1 };".to_string(),
+                Object::Integer { value: 1 },
+            ),
+            // 最初に一致した分岐だけを評価する
+            (
+                r#"switch (1) { 1 => 1, 1 => error("unreachable") };"#.to_string(),
+                Object::Integer { value: 1 },
+            ),
+            (
+                "let x = 2; switch (x * 2) { x + 2 => true, _ => false };".to_string(),
+                Object::BOOLEAN_TRUE,
+            ),
+            (
+                "switch (3) { 1 => 1, 2 => 2 };".to_string(),
+                Object::error("no switch arm matches 3"),
+            ),
+            (
+                "switch (3) {};".to_string(),
+                Object::error("no switch arm matches 3"),
+            ),
+            (
+                "switch ([1]) { _ => 1 };".to_string(),
+                Object::error("cannot switch on ARRAY"),
+            ),
+            (
+                "switch (x) { _ => 1 };".to_string(),
+                Object::error("identifier not found: x"),
+            ),
+            (
+                "switch (1) { y => 1 };".to_string(),
+                Object::error("identifier not found: y"),
+            ),
+        ];
+        for (input, expect) in tests.iter() {
+            assert_eq!(test_eval(input), *expect, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_call_non_function() {
+        let tests = [
+            ("5(3);", Object::error("not a function: INTEGER")),
+            ("let x = 1; x(2);", Object::error("not a function: INTEGER")),
+            (r#""f"();"#, Object::error("not a function: STRING")),
+            ("[1](0);", Object::error("not a function: ARRAY")),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_function_arity() {
+        let tests = [
+            (
+                "let f = fn(x, y) { x; }; f(1);",
+                Object::error("wrong number of arguments to `f`. got=1, want=2"),
+            ),
+            (
+                "let f = fn(x, y) { x; }; f(1, 2, 3);",
+                Object::error("wrong number of arguments to `f`. got=3, want=2"),
+            ),
+            (
+                "fn() { 1; }(2);",
+                Object::error("wrong number of arguments. got=1, want=0"),
+            ),
+            (
+                "let f = fn(x, y) { x; }; f(1, 2);",
+                Object::Integer { value: 1 },
+            ),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_index_expression() {
+        let tests = [
+            ("[1, 2, 3][0];", Object::Integer { value: 1 }),
+            ("[1, 2, 3][1 + 1];", Object::Integer { value: 3 }),
+            ("let i = 0; [1][i];", Object::Integer { value: 1 }),
+            (
+                "let a = [1, 2, 3]; a[0] + a[1] + a[2];",
+                Object::Integer { value: 6 },
+            ),
+            ("[1, 2, 3][3];", Object::NULL),
+            ("[1, 2, 3][-1];", Object::NULL),
+            (
+                r#""hello"[1];"#,
+                Object::Str {
+                    value: "e".to_string(),
+                },
+            ),
+            (r#""hello"[5];"#, Object::NULL),
+            (r#""hello"[-1];"#, Object::NULL),
+            // 文字列の添字はバイトではなく文字で数える
+            (
+                r#""héllo"[1];"#,
+                Object::Str {
+                    value: "é".to_string(),
+                },
+            ),
+            (
+                r#""héllo"[2];"#,
+                Object::Str {
+                    value: "l".to_string(),
+                },
+            ),
+            (
+                r#""日本語"[2];"#,
+                Object::Str {
+                    value: "語".to_string(),
+                },
+            ),
+            (r#"{"a": 1, 2: true}["a"];"#, Object::Integer { value: 1 }),
+            (r#"{"a": 1, 2: true}[2];"#, Object::BOOLEAN_TRUE),
+            (r#"{"a": 1}["b"];"#, Object::NULL),
+            (
+                r#"{"a": 1}[[1]];"#,
+                Object::error("unusable as hash key: ARRAY"),
+            ),
+            (
+                "1[0];",
+                Object::error("index operator not supported: INTEGER[INTEGER]"),
+            ),
+            ("[1][x];", Object::error("identifier not found: x")),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_member_expression() {
+        let tests = [
+            (
+                r#"let point = {"x": 1, "y": 2}; point.x + point.y;"#,
+                Object::integer(3),
+            ),
+            (
+                r#"{"a": {"b": "c"}}.a.b;"#,
+                Object::Str {
+                    value: "c".to_string(),
+                },
+            ),
+            (
+                r#"let m = {"double": fn(x) { x * 2; }}; m.double(4);"#,
+                Object::integer(8),
+            ),
+            (r#"{"a": 1}.b;"#, Object::NULL),
+            // 文字列以外のキーは参照できない
+            (r#"{1: 1}.a;"#, Object::NULL),
+            (
+                "let x = 1; x.a;",
+                Object::error("member access not supported: INTEGER.a"),
+            ),
+            (
+                "[1].len;",
+                Object::error("member access not supported: ARRAY.len"),
+            ),
+            ("y.a;", Object::error("identifier not found: y")),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_spread_and_rest() {
+        let ints = |values: &[i64]| Object::Array {
+            elements: values
+                .iter()
+                .map(|v| Object::Integer { value: *v })
+                .collect(),
+        };
+        let tests = [
+            (
+                "let add = fn(a, b, c) { a + b + c; }; add(...[1, 2, 3]);",
+                Object::Integer { value: 6 },
+            ),
+            (
+                "let add = fn(a, b, c) { a + b + c; }; add(1, ...[2], ...[3]);",
+                Object::Integer { value: 6 },
+            ),
+            (
+                "let add = fn(a, b) { a + b; }; add(...[1, 2, 3]);",
+                Object::error("wrong number of arguments to `add`. got=3, want=2"),
+            ),
+            (
+                "let f = fn(x) { x; }; f(...1);",
+                Object::error("cannot spread INTEGER, expected ARRAY"),
+            ),
+            (
+                "let f = fn(x) { x; }; f(...y);",
+                Object::error("identifier not found: y"),
+            ),
+            ("len(...[[1, 2]]);", Object::Integer { value: 2 }),
+            (
+                "let f = fn(head, ...tail) { tail; }; f(1, 2, 3);",
+                ints(&[2, 3]),
+            ),
+            (
+                "let f = fn(head, ...tail) { head; }; f(1, 2, 3);",
+                Object::Integer { value: 1 },
+            ),
+            ("let f = fn(head, ...tail) { tail; }; f(1);", ints(&[])),
+            ("let f = fn(...all) { all; }; f();", ints(&[])),
+            (
+                "let f = fn(...all) { all; }; f(...[1, 2], 3);",
+                ints(&[1, 2, 3]),
+            ),
+            (
+                "let f = fn(a, b, ...rest) { rest; }; f(1);",
+                Object::error("wrong number of arguments to `f`. got=1, want=at least 2"),
+            ),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_error_position() {
+        let tests = [
+            // (input, (行, 列))
+            ("let x = 1;\nlet y = 2;\nx + true;", (3, 3)),
+            ("5;\n  len(1, 2);", (2, 6)),
+            ("let f = fn(x) {\n  x + [1];\n};\nf(1);", (2, 5)),
+            ("\nlen(1);", (2, 4)),
+            ("let [a] = [1, 2];", (1, 1)),
+            ("1;\n\nfoo;", (3, 1)),
+        ];
+        for (input, position) in tests.iter() {
+            let evaluated = test_eval(input);
+            assert!(evaluated.get_type().is_error(), "{:?}", evaluated);
+            assert_eq!(evaluated.error_position(), Some(*position), "{}", input);
+        }
+        // エラー以外は位置を持たない
+        assert_eq!(test_eval("1;").error_position(), None);
+    }
+
+    #[test]
+    fn test_builtin_len() {
+        let tests = [
+            (r#"len("");"#, Object::Integer { value: 0 }),
+            (r#"len("héllo");"#, Object::Integer { value: 5 }),
+            (r#"len("日本語");"#, Object::Integer { value: 3 }),
+            ("len([1, 2, 3]);", Object::Integer { value: 3 }),
+            (
+                "len(1);",
+                Object::error("argument to `len` not supported, got INTEGER"),
+            ),
+            (
+                r#"len("a", "b");"#,
+                Object::error("wrong number of arguments. got=2, want=1"),
+            ),
+            (
+                "let len = fn(x) { 0; }; len([1]);",
+                Object::Integer { value: 0 },
+            ),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_builtin_assert() {
+        let tests = [
+            (r#"error("boom");"#, Object::error("boom")),
+            (
+                r#"let f = fn() { error("inner"); 1; }; f() + 1;"#,
+                Object::error("inner"),
+            ),
+            (
+                "error(1);",
+                Object::error("argument to `error` must be STRING, got INTEGER"),
+            ),
+            ("assert(1 < 2);", Object::NULL),
+            ("assert(1 > 2);", Object::error("assertion failed")),
+            ("assert_eq(1 + 1, 2);", Object::NULL),
+            (r#"assert_eq([1, "a"], [1, "a"]);"#, Object::NULL),
+            (
+                r#"assert_eq(1, "1");"#,
+                Object::error(r#"assertion failed: 1 != "1""#),
+            ),
+            (
+                "assert_eq(1);",
+                Object::error("wrong number of arguments. got=1, want=2"),
+            ),
+            // 失敗した表明の後の文は評価しない
+            ("assert(false); 10;", Object::error("assertion failed")),
+            ("assert(assert(false));", Object::error("assertion failed")),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_builtin_split_join() {
+        let strs = |values: &[&str]| Object::Array {
+            elements: values
+                .iter()
+                .map(|v| Object::Str {
+                    value: v.to_string(),
+                })
+                .collect(),
+        };
+        let tests = [
+            (r#"split("a,b,c", ",");"#, strs(&["a", "b", "c"])),
+            (r#"split("a, b", ", ");"#, strs(&["a", "b"])),
+            (r#"split("a,,b,", ",");"#, strs(&["a", "", "b", ""])),
+            (r#"split("abc", ";");"#, strs(&["abc"])),
+            // 区切り文字列が空なら一文字ずつに分ける
+            (r#"split("héllo", "");"#, strs(&["h", "é", "l", "l", "o"])),
+            (r#"split("", ",");"#, strs(&[""])),
+            (r#"split("", "");"#, strs(&[])),
+            (
+                r#"split(1, ",");"#,
+                Object::error("argument to `split` must be STRING, got INTEGER"),
+            ),
+            (
+                r#"split("a", [","]);"#,
+                Object::error("argument to `split` must be STRING, got ARRAY"),
+            ),
+            (
+                r#"split("a");"#,
+                Object::error("wrong number of arguments. got=1, want=2"),
+            ),
+            (
+                r#"join(["a", "b"], "-");"#,
+                Object::Str {
+                    value: "a-b".to_string(),
+                },
+            ),
+            (
+                r#"join(["a"], "-");"#,
+                Object::Str {
+                    value: "a".to_string(),
+                },
+            ),
+            // 空の配列は空文字列になる
+            (
+                r#"join([], "-");"#,
+                Object::Str {
+                    value: "".to_string(),
+                },
+            ),
+            (
+                r#"join(split("a,b,c", ","), "");"#,
+                Object::Str {
+                    value: "abc".to_string(),
+                },
+            ),
+            (
+                r#"join(["a", 1], "-");"#,
+                Object::error("elements of `join` must be STRING, got INTEGER"),
+            ),
+            (
+                r#"join("ab", "-");"#,
+                Object::error("first argument to `join` must be ARRAY, got STRING"),
+            ),
+            (
+                r#"join(["a"], 1);"#,
+                Object::error("second argument to `join` must be STRING, got INTEGER"),
+            ),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_builtin_type() {
+        let type_name = |name: &str| Object::Str {
+            value: name.to_string(),
+        };
+        let tests = [
+            ("type(1);", type_name("INTEGER")),
+            ("type(true);", type_name("BOOLEAN")),
+            ("type(null);", type_name("NULL")),
+            (r#"type("a");"#, type_name("STRING")),
+            ("type([1]);", type_name("ARRAY")),
+            ("type({1: 2});", type_name("HASH")),
+            ("type(fn(x) { x; });", type_name("FUNCTION")),
+            ("type(len);", type_name("BUILTIN")),
+            ("type(if (false) { 1; });", type_name("NULL")),
+            ("type(type(1));", type_name("STRING")),
+            (
+                "type();",
+                Object::error("wrong number of arguments. got=0, want=1"),
+            ),
+            (
+                "type(1, 2);",
+                Object::error("wrong number of arguments. got=2, want=1"),
+            ),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_builtin_conversions() {
+        let string = |value: &str| Object::Str {
+            value: value.to_string(),
+        };
+        let tests = [
+            (r#"int("42");"#, Object::Integer { value: 42 }),
+            (r#"int("-7");"#, Object::Integer { value: -7 }),
+            (r#"int("+5");"#, Object::Integer { value: 5 }),
+            (
+                r#"int("+");"#,
+                Object::error(r#"could not convert "+" to INTEGER"#),
+            ),
+            (
+                r#"int("+-5");"#,
+                Object::error(r#"could not convert "+-5" to INTEGER"#),
+            ),
+            (
+                r#"int("++5");"#,
+                Object::error(r#"could not convert "++5" to INTEGER"#),
+            ),
+            ("int(5);", Object::Integer { value: 5 }),
+            ("int(true);", Object::Integer { value: 1 }),
+            ("int(false);", Object::Integer { value: 0 }),
+            // 浮動小数点数は0に向かって切り捨てる
+            ("int(2.7);", Object::Integer { value: 2 }),
+            ("int(-2.7);", Object::Integer { value: -2 }),
+            (
+                "int(1.0 / 0.0);",
+                Object::error("could not convert inf to INTEGER"),
+            ),
+            (
+                "int(1e19);",
+                Object::error("could not convert 1e19 to INTEGER"),
+            ),
+            (
+                r#"int("abc");"#,
+                Object::error(r#"could not convert "abc" to INTEGER"#),
+            ),
+            (
+                r#"int("");"#,
+                Object::error(r#"could not convert "" to INTEGER"#),
+            ),
+            (
+                "int([1]);",
+                Object::error("cannot convert ARRAY to INTEGER"),
+            ),
+            (
+                "int(null);",
+                Object::error("cannot convert NULL to INTEGER"),
+            ),
+            ("str(42);", string("42")),
+            ("str(-1);", string("-1")),
+            ("str(true);", string("true")),
+            ("str(null);", string("null")),
+            (r#"str("a");"#, string("a")),
+            (r#"str([1, "a"]);"#, string(r#"[1, "a"]"#)),
+            (r#"int(str(12)) + 1;"#, Object::Integer { value: 13 }),
+            // 真偽値への変換はif式の条件の真偽に従う
+            ("bool(0);", Object::BOOLEAN_TRUE),
+            (r#"bool("");"#, Object::BOOLEAN_TRUE),
+            ("bool(null);", Object::BOOLEAN_FALSE),
+            ("bool(false);", Object::BOOLEAN_FALSE),
+            ("bool([]);", Object::BOOLEAN_TRUE),
+            (
+                "bool();",
+                Object::error("wrong number of arguments. got=0, want=1"),
+            ),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_builtin_hex_and_bin() {
+        let string = |value: &str| Object::Str {
+            value: value.to_string(),
+        };
+        let tests = [
+            ("hex(255);", string("0xff")),
+            ("hex(16 * 16);", string("0x100")),
+            ("hex(0);", string("0x0")),
+            // 負の数は絶対値の前に符号を付ける
+            ("hex(-255);", string("-0xff")),
+            ("bin(5);", string("0b101")),
+            ("bin(0);", string("0b0")),
+            ("bin(-2);", string("-0b10")),
+            (
+                r#"hex("ff");"#,
+                Object::error("argument to `hex` must be INTEGER, got STRING"),
+            ),
+            (
+                "bin(true);",
+                Object::error("argument to `bin` must be INTEGER, got BOOLEAN"),
+            ),
+            (
+                "hex(1, 2);",
+                Object::error("wrong number of arguments. got=2, want=1"),
+            ),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_builtin_set_and_delete() {
+        let string = |value: &str| Object::Str {
+            value: value.to_string(),
+        };
+        let hash = |pairs: &[(&str, i64)]| Object::Hash {
+            pairs: pairs
+                .iter()
+                .map(|(k, v)| (string(k), Object::integer(*v)))
+                .collect(),
+        };
+        let tests = [
+            (r#"set({"a": 1}, "b", 2);"#, hash(&[("a", 1), ("b", 2)])),
+            // 上書きしたキーは元の位置のまま
+            (
+                r#"set({"a": 1, "b": 2}, "a", 3);"#,
+                hash(&[("a", 3), ("b", 2)]),
+            ),
+            (r#"delete({"a": 1, "b": 2}, "a");"#, hash(&[("b", 2)])),
+            // ないキーを消しても変わらない
+            (r#"delete({"a": 1}, "b");"#, hash(&[("a", 1)])),
+            (r#"delete({}, 1);"#, Object::Hash { pairs: vec![] }),
+            // 元のハッシュは変更しない
+            (
+                r#"let h = {"a": 1}; let g = set(h, "a", 2); delete(g, "a"); h;"#,
+                hash(&[("a", 1)]),
+            ),
+            (
+                r#"let h = {"a": 1}; set(h, "b", 2)["b"];"#,
+                Object::integer(2),
+            ),
+            (
+                r#"set({}, [1], 2);"#,
+                Object::error("unusable as hash key: ARRAY"),
+            ),
+            (
+                r#"delete({}, fn() {});"#,
+                Object::error("unusable as hash key: FUNCTION"),
+            ),
+            (
+                "set([1], 0, 2);",
+                Object::error("first argument to `set` must be HASH, got ARRAY"),
+            ),
+            (
+                "delete(1, 1);",
+                Object::error("first argument to `delete` must be HASH, got INTEGER"),
+            ),
+            (
+                "set({}, 1);",
+                Object::error("wrong number of arguments. got=2, want=3"),
+            ),
+            (
+                "delete({});",
+                Object::error("wrong number of arguments. got=1, want=2"),
+            ),
+        ];
+        do_test(&tests);
+    }
+
+    #[test]
+    fn test_builtin_arity() {
+        let tests = [
+            ("arity(fn(x, y) {});", Object::integer(2)),
+            ("arity(fn() {});", Object::integer(0)),
+            (
+                "let add = fn(a, b, c) { a + b + c; }; arity(add);",
+                Object::integer(3),
+            ),
+            (
+                "arity(5);",
+                Object::error("argument to `arity` must be FUNCTION, got INTEGER"),
+            ),
+            (
+                "arity(len);",
+                Object::error("argument to `arity` must be FUNCTION, got BUILTIN"),
+            ),
+            (
+                "arity();",
+                Object::error("wrong number of arguments. got=0, want=1"),
+            ),
+        ];
+        do_test(&tests);
+    }
+
+    /// 書き込んだ内容を後から読めるように共有するバッファ
+    #[derive(Clone)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl SharedBuffer {
+        fn new() -> Self {
+            SharedBuffer(Rc::new(RefCell::new(Vec::new())))
+        }
+
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.borrow().clone()).unwrap()
+        }
+    }
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_builtin_puts() {
+        let (out, err) = (SharedBuffer::new(), SharedBuffer::new());
+        let new_eval = |env: Environment| {
+            Eval::with_writers(
+                Rc::new(RefCell::new(env)),
+                EvalOptions::new(),
+                Box::new(out.clone()),
+                Box::new(err.clone()),
+            )
+        };
+        let input = r#"puts("hello", 1 + 2, [1, "a"]); eputs("oops"); puts();"#;
+        let program = Parser::new(Lexer::new(input))
+            .parse_program()
+            .expect("fail parse program.");
+        let mut eval = new_eval(Environment::new());
+        assert_eq!(eval.eval_program(&program), Object::NULL);
+        // 文字列は引用符を付けずに出力する
+        assert_eq!(out.contents(), "hello\n3\n[1, \"a\"]\n");
+        assert_eq!(err.contents(), "oops\n");
+
+        // 同じ名前で登録した組み込み関数は書き込み先に出力しない
+        let mut builtins: HashMap<String, BuiltinFunction> = HashMap::new();
+        builtins.insert("puts".to_string(), zero);
+        let mut eval = new_eval(Environment::with_builtins(builtins));
+        let program = Parser::new(Lexer::new("puts(1);")).parse_program().unwrap();
+        assert_eq!(eval.eval_program(&program), Object::Integer { value: 0 });
+        assert_eq!(out.contents(), "hello\n3\n[1, \"a\"]\n");
+
+        // letで束縛した同じ名前の関数も書き込み先に出力しない
+        let mut eval = new_eval(Environment::new());
+        let input = "let puts = fn(x) { x * 2; }; puts(2);";
+        let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+        assert_eq!(eval.eval_program(&program), Object::Integer { value: 4 });
+        // 引数の評価に失敗したときは何も出力しない
+        let program = Parser::new(Lexer::new("eputs(1, x);"))
+            .parse_program()
+            .unwrap();
+        assert_eq!(
+            eval.eval_program(&program),
+            Object::error("identifier not found: x")
+        );
+        assert_eq!(out.contents(), "hello\n3\n[1, \"a\"]\n");
+        assert_eq!(err.contents(), "oops\n");
+
+        // 出力する組み込み関数も値として束縛して呼び出せる
+        let mut eval = new_eval(Environment::new());
+        let input = r#"let p = puts; p("again"); type(puts);"#;
+        let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+        assert_eq!(
+            eval.eval_program(&program),
+            Object::Str {
+                value: "BUILTIN".to_string()
+            }
+        );
+        assert_eq!(out.contents(), "hello\n3\n[1, \"a\"]\nagain\n");
+    }
+
+    fn double(_ctx: &mut BuiltinContext, args: &[Object]) -> Object {
+        match args {
+            [Object::Integer { value }] => Object::Integer { value: value * 2 },
+            _ => Object::error("double expects an integer"),
+        }
+    }
+
+    fn zero(_ctx: &mut BuiltinContext, _args: &[Object]) -> Object {
+        Object::Integer { value: 0 }
+    }
+
+    #[test]
+    fn test_custom_builtins() {
+        let mut builtins: HashMap<String, BuiltinFunction> = HashMap::new();
+        builtins.insert("double".to_string(), double);
+        // 言語の組み込み関数と同じ名前なら登録した関数が優先される
+        builtins.insert("len".to_string(), zero);
+        let env = Rc::new(RefCell::new(Environment::with_builtins(builtins)));
+
+        let tests = [
+            ("double(21);", Object::Integer { value: 42 }),
+            (
+                "let x = 5; double(double(x));",
+                Object::Integer { value: 20 },
+            ),
+            ("double(true);", Object::error("double expects an integer")),
+            ("len([1, 2]);", Object::Integer { value: 0 }),
+        ];
+        for (input, expected) in tests.iter() {
+            let program = Parser::new(Lexer::new(input)).parse_program();
+            let evaluated =
+                Eval::new(Rc::clone(&env)).eval_program(&program.expect("fail parse program."));
+            assert_eq!(&evaluated, expected);
+        }
+    }
+
     fn test_eval(input: &str) -> Object {
-        let mut lexer = Lexer::new(input);
+        let lexer = Lexer::new(input);
         let mut parser = Parser::new(lexer);
         let program = parser.parse_program();
+        let env = Rc::new(RefCell::new(Environment::new()));
 
-        Eval::eval_program(&program.expect("fail parse program."))
+        Eval::new(Rc::clone(&env)).eval_program(&program.expect("fail parse program."))
     }
 
     fn do_test(tests: &[(&str, Object)]) {
-        for (input, expected) in tests.to_vec() {
+        for (input, expected) in tests.iter() {
             let evaluated = test_eval(input);
-            assert_eq!(evaluated, expected);
+            assert_eq!(&evaluated, expected);
         }
     }
 }
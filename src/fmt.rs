@@ -0,0 +1,410 @@
+use crate::ast::{Expression, Program, Statement};
+use crate::lexer::escape_string;
+use crate::parser::{Opt, Parser};
+
+/// 一段分の字下げ
+const INDENT: &str = "  ";
+
+/// プログラムを読みやすいMonkeyのソースコードに整形する関数
+/// デバッグ用の`to_string`と違い、必要な括弧だけを付けてブロックは改行と2つの空白で字下げする。
+/// 出力を再びパースすると元と同じ解釈木になる。
+pub fn format(program: &Program) -> String {
+    let mut s = "".to_string();
+    for stmt in program.statements.iter() {
+        s.push_str(&format_statement(stmt, 0));
+        s.push('\n');
+    }
+    return s;
+}
+
+/// 文を字下げの深さに合わせて整形する関数。ブロック以外は末尾にセミコロンを付ける。
+fn format_statement(statement: &Statement, depth: usize) -> String {
+    let indent = INDENT.repeat(depth);
+    match statement {
+        Statement::LetStatement {
+            token: _,
+            name,
+            value,
+            doc,
+        } => {
+            let mut s = "".to_string();
+            if let Some(doc) = doc {
+                for line in doc.lines() {
+                    s.push_str(&format!("{}// {}\n", indent, line));
+                }
+            }
+            s.push_str(&format!(
+                "{}let {} = {};",
+                indent,
+                name,
+                format_expression(value, depth)
+            ));
+            s
+        }
+        Statement::AssignStatement {
+            token: _,
+            name,
+            value,
+        } => format!("{}{} = {};", indent, name, format_expression(value, depth)),
+        Statement::ReturnStatement {
+            token: _,
+            return_value,
+        } => format!(
+            "{}return {};",
+            indent,
+            format_expression(return_value, depth)
+        ),
+        Statement::ExpressionStatement {
+            token: _,
+            expression,
+        } => format!("{}{};", indent, format_expression(expression, depth)),
+        Statement::BlockStatement {
+            token: _,
+            statements: _,
+        } => format!("{}{}", indent, format_block(statement, depth)),
+        Statement::DoWhileStatement {
+            token: _,
+            body,
+            condition,
+        } => format!(
+            "{}do {} while ({});",
+            indent,
+            format_block(body, depth),
+            format_expression(condition, depth)
+        ),
+        Statement::ForStatement {
+            token: _,
+            init,
+            condition,
+            update,
+            body,
+        } => {
+            let init = match init {
+                Some(init) => format_statement(init, 0),
+                None => ";".to_string(),
+            };
+            let condition = match condition {
+                Some(condition) => format!(" {}", format_expression(condition, depth)),
+                None => "".to_string(),
+            };
+            // 更新の文は閉じ括弧の前なのでセミコロンを付けない
+            let update = match update {
+                Some(update) => format!(" {}", format_statement(update, 0).trim_end_matches(';')),
+                None => "".to_string(),
+            };
+            format!(
+                "{}for ({}{};{}) {}",
+                indent,
+                init,
+                condition,
+                update,
+                format_block(body, depth)
+            )
+        }
+        Statement::ForInStatement {
+            token: _,
+            variable,
+            iterable,
+            body,
+        } => format!(
+            "{}for ({} in {}) {}",
+            indent,
+            variable,
+            format_expression(iterable, depth),
+            format_block(body, depth)
+        ),
+        Statement::BreakStatement { token: _ } => format!("{}break;", indent),
+        Statement::ContinueStatement { token: _ } => format!("{}continue;", indent),
+        Statement::ImportStatement { token: _, path } => {
+            format!("{}import {};", indent, escape_string(path))
+        }
+    }
+}
+
+/// ブロックを整形する関数。閉じ括弧はブロックを書き始めた行の字下げに揃える。
+fn format_block(block: &Statement, depth: usize) -> String {
+    match block {
+        Statement::BlockStatement {
+            token: _,
+            statements,
+        } => {
+            if statements.is_empty() {
+                return "{}".to_string();
+            }
+            let mut s = "{\n".to_string();
+            for stmt in statements.iter() {
+                s.push_str(&format_statement(stmt, depth + 1));
+                s.push('\n');
+            }
+            s.push_str(&INDENT.repeat(depth));
+            s.push('}');
+            return s;
+        }
+        // ブロック以外はそのまま一つの文として扱う
+        _ => format_statement(block, depth),
+    }
+}
+
+/// 式を整形する関数
+fn format_expression(expression: &Expression, depth: usize) -> String {
+    match expression {
+        Expression::Identifier { token: _, value } => value.to_string(),
+        Expression::IntegerLiteral { token: _, value } => value.to_string(),
+        Expression::FloatLiteral { token: _, value } => value.to_string(),
+        Expression::BooleanLiteral { token: _, value } => value.to_string(),
+        Expression::StringLiteral { token: _, value } => escape_string(value),
+        Expression::NullLiteral { token: _ } => "null".to_string(),
+        Expression::FunctionLiteral {
+            token: _,
+            parameters,
+            body,
+            name: _,
+        } => {
+            let params: Vec<String> = parameters.iter().map(|p| p.to_string()).collect();
+            format!("fn({}) {}", params.join(", "), format_block(body, depth))
+        }
+        Expression::PrefixExpression {
+            token: _,
+            operator,
+            right_exp,
+        } => {
+            let right = format_expression(right_exp, depth);
+            if is_operator_expression(right_exp) {
+                format!("{}({})", operator, right)
+            } else {
+                format!("{}{}", operator, right)
+            }
+        }
+        Expression::InfixExpression {
+            token,
+            operator,
+            left_exp,
+            right_exp,
+        } => {
+            let precedence = Parser::infix_precedence(&token.get_token_type());
+            // 左結合なので右辺は同じ優先順位でも括弧が必要
+            let left = format_operand(left_exp, depth, |p| p < precedence);
+            let right = format_operand(right_exp, depth, |p| p <= precedence);
+            format!("{} {} {}", left, operator, right)
+        }
+        Expression::IfExpression {
+            token: _,
+            condition,
+            consequence,
+            alternative,
+        } => {
+            let mut s = format!(
+                "if ({}) {}",
+                format_expression(condition, depth),
+                format_block(consequence, depth)
+            );
+            if let Some(alt) = alternative {
+                s.push_str(&format!(" else {}", format_block(alt, depth)));
+            }
+            s
+        }
+        Expression::SwitchExpression {
+            token: _,
+            subject,
+            arms,
+            default,
+        } => {
+            let mut s = format!("switch ({}) {{", format_expression(subject, depth));
+            if arms.is_empty() && default.is_none() {
+                s.push('}');
+                return s;
+            }
+            let indent = INDENT.repeat(depth + 1);
+            let mut elems: Vec<String> = arms
+                .iter()
+                .map(|(pattern, value)| {
+                    format!(
+                        "{}{} => {}",
+                        indent,
+                        format_expression(pattern, depth + 1),
+                        format_expression(value, depth + 1)
+                    )
+                })
+                .collect();
+            if let Some(default) = default {
+                elems.push(format!(
+                    "{}_ => {}",
+                    indent,
+                    format_expression(default, depth + 1)
+                ));
+            }
+            s.push('\n');
+            s.push_str(&elems.join(",\n"));
+            s.push('\n');
+            s.push_str(&INDENT.repeat(depth));
+            s.push('}');
+            s
+        }
+        Expression::ArrayLiteral { token: _, elements } => {
+            let elems: Vec<String> = elements
+                .iter()
+                .map(|e| format_expression(e, depth))
+                .collect();
+            format!("[{}]", elems.join(", "))
+        }
+        Expression::HashLiteral { token: _, pairs } => {
+            let ps: Vec<String> = pairs
+                .iter()
+                .map(|(k, v)| {
+                    format!(
+                        "{}: {}",
+                        format_expression(k, depth),
+                        format_expression(v, depth)
+                    )
+                })
+                .collect();
+            format!("{{{}}}", ps.join(", "))
+        }
+        Expression::ArrayPattern {
+            token: _,
+            names: _,
+            rest: _,
+        } => expression.to_string(),
+        Expression::CallExpression {
+            token: _,
+            function,
+            arguments,
+        } => {
+            let mut callee = format_expression(function, depth);
+            if is_operator_expression(function) {
+                callee = format!("({})", callee);
+            }
+            let args: Vec<String> = arguments
+                .iter()
+                .map(|a| format_expression(a, depth))
+                .collect();
+            format!("{}({})", callee, args.join(", "))
+        }
+        Expression::IndexExpression {
+            token: _,
+            left,
+            index,
+        } => {
+            let mut target = format_expression(left, depth);
+            if is_operator_expression(left) {
+                target = format!("({})", target);
+            }
+            format!("{}[{}]", target, format_expression(index, depth))
+        }
+        Expression::MemberExpression {
+            token: _,
+            object,
+            property,
+        } => {
+            let mut target = format_expression(object, depth);
+            if is_operator_expression(object) {
+                target = format!("({})", target);
+            }
+            format!("{}.{}", target, property)
+        }
+        Expression::RangeLiteral { token, start, end } => {
+            let precedence = Parser::infix_precedence(&token.get_token_type());
+            let start = format_operand(start, depth, |p| p < precedence);
+            let end = format_operand(end, depth, |p| p <= precedence);
+            format!("{}..{}", start, end)
+        }
+        Expression::SpreadExpression { token: _, value } => {
+            format!("...{}", format_expression(value, depth))
+        }
+    }
+}
+
+/// 中置演算子の被演算子を整形する関数。被演算子の優先順位で括弧が必要なときだけ括弧で囲む。
+/// 範囲リテラルも`..`を中置演算子とみなして比べる。
+fn format_operand(operand: &Expression, depth: usize, needs_paren: impl Fn(Opt) -> bool) -> String {
+    let s = format_expression(operand, depth);
+    if let Expression::InfixExpression {
+        token,
+        operator: _,
+        left_exp: _,
+        right_exp: _,
+    }
+    | Expression::RangeLiteral {
+        token,
+        start: _,
+        end: _,
+    } = operand
+    {
+        if needs_paren(Parser::infix_precedence(&token.get_token_type())) {
+            return format!("({})", s);
+        }
+    }
+    return s;
+}
+
+/// 前置・中置演算子の式かどうかを判定する関数。範囲リテラルも中置演算子の式とみなす。
+fn is_operator_expression(expression: &Expression) -> bool {
+    return matches!(
+        expression,
+        Expression::PrefixExpression { .. }
+            | Expression::InfixExpression { .. }
+            | Expression::RangeLiteral { .. }
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fmt::format;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_format() {
+        let input =
+            "let add=fn(a,b){return a+b;};if(add(1,2)>2){\"ok\\n\";}else{[1,2*3,{true:null}];};";
+        let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+        let expect = "let add = fn(a, b) {
+  return a + b;
+};
+if (add(1, 2) > 2) {
+  \"ok\\n\";
+} else {
+  [1, 2 * 3, {true: null}];
+};
+";
+        assert_eq!(format(&program), expect);
+    }
+
+    #[test]
+    fn test_format_round_trip() {
+        let inputs = [
+            "let x = 5; x;",
+            "a + b * c - (d - e) / f;",
+            "a * (b + c); -(a + b); !-a; - -a;",
+            "a || b && c; (a || b) && c; a == (b == c);",
+            "fn(x) { fn(y) { if (x < y) { return x; } else { return y; }; }; }(1)(2);",
+            "fn() {}; let f = fn(x) { x; }; f(1, [2, 3], {\"a\": 1, 2: true});",
+            "let s = \"tab\\t\\\"quote\\\"\"; null;",
+            "-f(x) * g(-1); +a * +(b + c) - +-1;",
+            "let [a, ...b] = [1, 2]; let [] = [];",
+            "(a + b)[0]; -a[1]; [1, 2][i * 2]; f(x)[0][1];",
+            "a.b.c; (a + b).c; -a.b; f(x).y[0].z(1); {\"k\": 1}.k;",
+            "0..n + 1; (0..n) + 1; a..(b..c); a..b..c; -(1..2); (a..b)[0]; 1..2 < 3 == x;",
+            "for (i in 0..len(xs)) { f(xs[i]); }",
+            "let f = fn(a, ...rest) { rest; }; f(...[1, 2], ...a + b);",
+            "do { let i = i + 1; } while (i < 3); do {} while (false);",
+            "do { if (x) { break; } else { continue; }; } while (true);",
+            "for (let i = 0; i < 10; i = i + 1) { sum = sum + i; } for (;;) {} for (x = 1; ; f(x)) {}",
+            "for (x in [1, 2] + a) { f(x); } for (c in \"abc\") {}",
+            "import \"lib/a.mk\"; fn() { import \"b\\tc\"; };",
+            "switch (x + 1) { 1 => \"one\", 2 => fn() { switch (y) { _ => 0 }; }, _ => null }; switch (x) {};",
+        ];
+        for input in inputs.iter() {
+            let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+            let formatted = format(&program);
+            let mut parser = Parser::new(Lexer::new(&formatted));
+            let reparsed = parser
+                .parse_program()
+                .unwrap_or_else(|| panic!("{}\n{:?}", formatted, parser.get_errors()));
+
+            assert_eq!(reparsed.statements, program.statements, "{}", formatted);
+            // 整形済みのソースを整形しても変わらない
+            assert_eq!(format(&reparsed), formatted);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use crate::lexer::Lexer;
+use crate::parser::{Parser, ParserOptions};
+
+/// 任意のバイト列を入力として字句解析と構文解析をする関数
+/// UTF-8として不正なバイトは置換文字にして読む。どんな入力でもパニックせずにパースエラーとして報告する。
+pub fn fuzz_parse(data: &[u8]) {
+    parse_bytes(data);
+}
+
+/// バイト列をREPLとスクリプトの両方の設定でパースしてパースエラーの数を返す関数
+fn parse_bytes(data: &[u8]) -> (usize, usize) {
+    let source = String::from_utf8_lossy(data);
+    let mut counts = (0, 0);
+    for (script, count) in [(false, &mut counts.0), (true, &mut counts.1)] {
+        let options = ParserOptions {
+            script,
+            ..ParserOptions::new()
+        };
+        let mut parser = Parser::with_options(Lexer::new(&source), options);
+        let (_, errors) = parser.parse_program_lossy();
+        *count = errors.len();
+    }
+    return counts;
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fuzz::{fuzz_parse, parse_bytes};
+
+    /// 再現できるように種を固定した疑似乱数(xorshift)
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_fuzz_parse_random_bytes() {
+        // Monkeyの記号を多めに混ぜて構文解析の奥まで届く入力を作る
+        let alphabet = b"letfnifelsereturn xy01 =+-!*/<>(){}[],;:.\"\n\t&|_";
+        let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+        for _ in 0..5000 {
+            let len = (rng.next() % 48) as usize;
+            let data: Vec<u8> = (0..len)
+                .map(|_| {
+                    let r = rng.next();
+                    if r & 0b11 == 0 {
+                        (r >> 8) as u8
+                    } else {
+                        alphabet[(r >> 8) as usize % alphabet.len()]
+                    }
+                })
+                .collect();
+            fuzz_parse(&data);
+        }
+    }
+
+    #[test]
+    fn test_fuzz_parse_reports_errors() {
+        assert_eq!(parse_bytes(b""), (0, 0));
+        assert_eq!(parse_bytes(b"let x = 1; x;"), (0, 0));
+        // 不正なUTF-8も置換文字として読んでエラーにする
+        let (errors, script_errors) = parse_bytes(&[0xff, 0xfe, b';']);
+        assert!(errors > 0 && script_errors > 0);
+        let (errors, _) = parse_bytes(b"let = fn(;");
+        assert!(errors > 0);
+    }
+}
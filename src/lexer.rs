@@ -2,13 +2,22 @@ use crate::token::{Token, TokenType};
 
 /// 字句解析器
 pub struct Lexer {
-    input: String,
     // 対象の文字列
+    input: String,
+    // 入力に対する現在の位置(現在の文字の先頭のバイト位置)
     position: usize,
-    // 入力に対する現在の位置(現在の文字の位置)
+    // これから読み込む位置(現在の文字の次の文字の先頭のバイト位置)
     read_position: usize,
-    // これから読み込む位置(現在の文字の次の位置)
-    ch: Option<char>, // 現在検査中の文字
+    // 現在検査中の文字
+    ch: Option<char>,
+    // 現在検査中の文字の行(1始まり)
+    line: usize,
+    // 現在検査中の文字の列(1始まり)
+    column: usize,
+    // 字句解析で見つかったエラー文の集まり
+    errors: Vec<String>,
+    // コメントを読み飛ばさずにトークンとして返すかどうか
+    doc_mode: bool,
 }
 
 impl Lexer {
@@ -21,98 +30,286 @@ impl Lexer {
             // read_positionは現在読んでいる位置
             read_position: 0,
             ch: None,
+            line: 1,
+            column: 0,
+            errors: Vec::new(),
+            doc_mode: false,
         };
 
         l.read_char();
         return l;
     }
 
+    /// 既存の字句解析器を新しい入力で使いまわすための関数
+    /// 入力用のバッファを再利用するので入力ごとに字句解析器を生成するより割り当てが少ない。
+    pub fn reset(&mut self, input: impl AsRef<str>) {
+        self.input.clear();
+        self.input.push_str(input.as_ref());
+        self.position = 0;
+        self.read_position = 0;
+        self.ch = None;
+        self.line = 1;
+        self.column = 0;
+        self.errors.clear();
+
+        self.read_char();
+    }
+
     /// 位置プロパティのゲッター
+    /// 現在の文字の先頭のバイト位置を返す。入力の終わりに達した後は何度トークンを読んでも入力のバイト数のまま変わらない。
     pub fn get_position(&self) -> usize {
         return self.position;
     }
 
+    /// まだトークンとして読んでいない残りの入力を返す関数
+    /// 位置はバイト単位だが常に文字の境界にあるので、マルチバイト文字の途中で切れることはない。
+    /// 文をパースした後に入力が残っているかを調べるために使う。入力の終わりに達した後は空文字列を返す。
+    pub fn remaining(&self) -> &str {
+        return self.input.get(self.position..).unwrap_or("");
+    }
+
+    /// ドキュメントモードを切り替える関数
+    /// ドキュメントモードでは`//`と`/* */`のコメントを読み飛ばさずにCOMMENTトークンとして返す。
+    /// 入力を`reset`で入れ替えてもモードは変わらない。
+    pub fn set_doc_mode(&mut self, doc_mode: bool) {
+        self.doc_mode = doc_mode;
+    }
+
+    /// 字句解析で見つかったエラーを返す関数
+    /// 字句解析器はエラーがあってもILLEGALトークンを返して解析を続けるので、構文解析のエラーと区別したいときに使う。
+    pub fn errors(&self) -> &[String] {
+        return &self.errors;
+    }
+
     /// 文字として認識しない空白扱いできる記号を飛ばす関数
     fn skip_whitespace(&mut self) {
+        while self
+            .ch
+            .is_some_and(|c| c == ' ' || c == '\t' || c == '\n' || c == '\r')
+        {
+            self.read_char();
+        }
+    }
+
+    /// 空白とコメントを本物のトークンが始まるまで繰り返し読み飛ばす関数
+    /// ドキュメントモードではコメントをトークンとして返すので空白だけを読み飛ばす。
+    fn skip_trivia(&mut self) {
         loop {
-            if let Some(c) = self.ch {
-                if c == ' ' || c == '\t' || c == '\n' || c == '\r' {
-                    self.read_char();
-                } else {
-                    break;
-                }
+            self.skip_whitespace();
+            if self.doc_mode {
+                break;
+            }
+            if self.is_comment_start() {
+                self.read_comment();
+            } else if self.is_block_comment_start() {
+                self.read_block_comment();
             } else {
                 break;
             }
         }
     }
 
+    /// `/*`から始まるコメントかどうかを判定する関数
+    fn is_block_comment_start(&self) -> bool {
+        return self.ch == Some('/') && self.peek_char() == Some('*');
+    }
+
+    /// `/*`から`*/`までのコメントを読んで中身の文字列を返す関数。入れ子にはできない。
+    /// 閉じる`*/`がなければエラーを記録して入力の終わりまで読む。
+    fn read_block_comment(&mut self) -> String {
+        let start = (self.line, self.column);
+        // `/*`を読み飛ばす
+        self.read_char();
+        self.read_char();
+        let position = self.position;
+        loop {
+            match self.ch {
+                Some('*') if self.peek_char() == Some('/') => {
+                    let end = self.position;
+                    // `*/`を読み飛ばす
+                    self.read_char();
+                    self.read_char();
+                    return self.input[position..end].trim().to_string();
+                }
+                Some(_) => self.read_char(),
+                None => {
+                    self.make_error(start, "コメントが閉じられていません。");
+                    return self.input[position..self.position].trim().to_string();
+                }
+            }
+        }
+    }
+
+    /// `//`から始まるコメントかどうかを判定する関数
+    fn is_comment_start(&self) -> bool {
+        return self.ch == Some('/') && self.peek_char() == Some('/');
+    }
+
+    /// `//`から行末までのコメントを読んで`//`の後ろの文字列を返す関数。改行は読まない。
+    fn read_comment(&mut self) -> String {
+        // `//`を読み飛ばす
+        self.read_char();
+        self.read_char();
+        let position = self.position;
+        loop {
+            match self.ch {
+                Some('\n') | None => break,
+                Some(_) => self.read_char(),
+            }
+        }
+        return self.input[position..self.position].trim().to_string();
+    }
+
     /// 一文字分先を読むソッド
     fn peek_char(&self) -> Option<char> {
-        return if self.read_position >= self.input.len() {
-            None
-        } else {
-            self.input.chars().nth(self.read_position)
-        };
+        return self.peek_char_at(1);
+    }
+
+    /// 現在の文字からoffset文字分先を読むメソッド。offsetが0なら現在の文字を返す。
+    /// `...`のような複数文字のトークンを読み進める前に判定するために使う。
+    fn peek_char_at(&self, offset: usize) -> Option<char> {
+        return self.input.get(self.position..)?.chars().nth(offset);
     }
 
     /// 一文字分を呼んで状態を更新するメソッド
     fn read_char(&mut self) {
+        // 改行は'\n'だけで数えるので"\r\n"も一つの改行になる
+        if self.ch == Some('\n') {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
         if self.read_position >= self.input.len() {
             self.ch = None;
         } else {
-            self.ch = self.input.chars().nth(self.read_position);
+            self.ch = self.input[self.read_position..].chars().next();
         }
         self.position = self.read_position;
-        self.read_position += 1;
+        // 位置はバイト単位なので文字の境界を保つためにUTF-8での長さ分だけ進める
+        self.read_position += self.ch.map_or(1, |c| c.len_utf8());
     }
 
     /// 識別子を読んで返す関数
     fn read_identifier(&mut self) -> String {
         // 文字の位置の始点
         let position = self.position;
-        loop {
-            if let Some(c) = self.ch {
-                if is_letter(&c) {
-                    self.read_char();
-                } else {
-                    break;
-                }
-            } else {
-                break;
-            }
+        while self.ch.is_some_and(|c| is_letter(&c)) {
+            self.read_char();
         }
         return self.input.as_str()[position..self.position].to_string();
     }
 
-    /// 数字を読んで返す関数
-    fn read_number(&mut self) -> String {
+    /// 数値リテラルを読んでトークン型と一緒に返す関数
+    /// 小数部か`1e10`、`1.5e-3`、`2E+5`のような指数部があればFLOATとして読む。
+    /// `1e`、`1e+`のように指数部に数字がないときや`1.2e3.4`のように続けて小数点があるときはILLEGALを返す。
+    fn read_number(&mut self) -> (TokenType, String) {
+        let start = (self.line, self.column);
         // 文字の位置の始点
         let position = self.position;
+        let mut token_type = TokenType::INT;
+        let mut valid = true;
+        self.read_digits();
+        // 数字が続かない'.'は数値の一部にしない
+        if self.ch == Some('.') && self.peek_char().is_some_and(|c| is_digit(&c)) {
+            token_type = TokenType::FLOAT;
+            self.read_char();
+            self.read_digits();
+        }
+        if self.ch == Some('e') || self.ch == Some('E') {
+            token_type = TokenType::FLOAT;
+            self.read_char();
+            if self.ch == Some('+') || self.ch == Some('-') {
+                self.read_char();
+            }
+            valid = self.ch.is_some_and(|c| is_digit(&c));
+            self.read_digits();
+        }
+        // 2つ目の小数点や指数部の後の小数点は不正な数値の一部として読み捨てる
+        while self.ch == Some('.') && self.peek_char().is_some_and(|c| is_digit(&c)) {
+            valid = false;
+            self.read_char();
+            self.read_digits();
+        }
+        let literal = self.input.as_str()[position..self.position].to_string();
+        if !valid {
+            self.make_error(start, &format!("不正な数値リテラル{}があります。", literal));
+            return (TokenType::ILLEGAL, literal);
+        }
+        return (token_type, literal);
+    }
+
+    /// 数字が続く限り読み進める関数
+    fn read_digits(&mut self) {
+        while let Some(c) = self.ch {
+            if !is_digit(&c) {
+                break;
+            }
+            self.read_char();
+        }
+    }
+
+    /// 文字列を読んで返す関数。エスケープシーケンスは対応する文字に変換する。
+    /// 閉じる'"'がないか不正なエスケープシーケンスを含むときはNoneを返す。
+    fn read_string(&mut self) -> Option<String> {
+        let start = (self.line, self.column);
+        // 開始の'"'を読み飛ばす
+        self.read_char();
+        let mut s = "".to_string();
+        let mut valid = true;
         loop {
-            if let Some(c) = self.ch {
-                if is_digit(&c) {
+            match self.ch {
+                Some('"') => {
                     self.read_char();
-                } else {
                     break;
                 }
-            } else {
-                break;
+                Some('\\') => {
+                    self.read_char();
+                    match self.ch.and_then(unescape) {
+                        Some(c) => s.push(c),
+                        None => match self.ch {
+                            Some(c) => {
+                                let msg = format!("不正なエスケープシーケンス\\{}があります。", c);
+                                self.make_error(start, &msg);
+                                valid = false;
+                            }
+                            // エスケープ対象の文字の前に入力が終わった
+                            None => {
+                                self.make_error(start, "文字列リテラルが閉じられていません。");
+                                return None;
+                            }
+                        },
+                    }
+                }
+                Some(c) => s.push(c),
+                None => {
+                    self.make_error(start, "文字列リテラルが閉じられていません。");
+                    return None;
+                }
             }
+            self.read_char();
         }
-        return self.input.as_str()[position..self.position].to_string();
+        if !valid {
+            return None;
+        }
+        return Some(s);
     }
 
     /// 入力の次の部分を呼んでToken構造体を生成するメソッド
     pub fn next_token(&mut self) -> Token {
-        self.skip_whitespace();
-        let mut tok: Option<Token> = None;
-        match self.ch.clone() {
+        self.skip_trivia();
+        let start = self.position;
+        let position = (self.line, self.column);
+        let tok: Option<Token>;
+        match self.ch {
             // 演算子
             Some('=') => {
-                if Some('=') == self.peek_char() {
+                if Some('=') == self.peek_char_at(1) {
                     tok = Some(Token::new(TokenType::EQ, "=="));
                     self.read_char();
+                } else if Some('>') == self.peek_char_at(1) {
+                    tok = Some(Token::new(TokenType::ARROW, "=>"));
+                    self.read_char();
                 } else {
                     tok = Some(Token::new(TokenType::ASSIGN, "="));
                 }
@@ -127,15 +324,21 @@ impl Lexer {
                 self.read_char();
             }
             Some('/') => {
-                tok = Some(Token::new(TokenType::SLASH, "/"));
-                self.read_char();
+                if self.is_comment_start() {
+                    tok = Some(Token::new(TokenType::COMMENT, &self.read_comment()));
+                } else if self.is_block_comment_start() {
+                    tok = Some(Token::new(TokenType::COMMENT, &self.read_block_comment()));
+                } else {
+                    tok = Some(Token::new(TokenType::SLASH, "/"));
+                    self.read_char();
+                }
             }
             Some('*') => {
                 tok = Some(Token::new(TokenType::ASTERISK, "*"));
                 self.read_char();
             }
             Some('!') => {
-                if Some('=') == self.peek_char() {
+                if Some('=') == self.peek_char_at(1) {
                     tok = Some(Token::new(TokenType::NEQ, "!="));
                     self.read_char();
                 } else {
@@ -153,6 +356,26 @@ impl Lexer {
                 tok = Some(Token::new(TokenType::GT, ">"));
                 self.read_char();
             }
+            Some('&') => {
+                if Some('&') == self.peek_char_at(1) {
+                    tok = Some(Token::new(TokenType::AND, "&&"));
+                    self.read_char();
+                } else {
+                    self.make_error(position, "不正な文字&があります。");
+                    tok = Some(Token::new(TokenType::ILLEGAL, "&"));
+                }
+                self.read_char();
+            }
+            Some('|') => {
+                if Some('|') == self.peek_char_at(1) {
+                    tok = Some(Token::new(TokenType::OR, "||"));
+                    self.read_char();
+                } else {
+                    self.make_error(position, "不正な文字|があります。");
+                    tok = Some(Token::new(TokenType::ILLEGAL, "|"));
+                }
+                self.read_char();
+            }
 
             // デリミタ
             Some(';') => {
@@ -163,6 +386,23 @@ impl Lexer {
                 tok = Some(Token::new(TokenType::COMMA, ","));
                 self.read_char();
             }
+            Some(':') => {
+                tok = Some(Token::new(TokenType::COLON, ":"));
+                self.read_char();
+            }
+            Some('.') => {
+                if self.peek_char_at(1) == Some('.') && self.peek_char_at(2) == Some('.') {
+                    tok = Some(Token::new(TokenType::ELLIPSIS, "..."));
+                    self.read_char();
+                    self.read_char();
+                } else if self.peek_char_at(1) == Some('.') {
+                    tok = Some(Token::new(TokenType::RANGE, ".."));
+                    self.read_char();
+                } else {
+                    tok = Some(Token::new(TokenType::DOT, "."));
+                }
+                self.read_char();
+            }
 
             // 括弧
             Some('(') => {
@@ -181,17 +421,32 @@ impl Lexer {
                 tok = Some(Token::new(TokenType::RBRACE, "}"));
                 self.read_char();
             }
+            Some('[') => {
+                tok = Some(Token::new(TokenType::LBRACKET, "["));
+                self.read_char();
+            }
+            Some(']') => {
+                tok = Some(Token::new(TokenType::RBRACKET, "]"));
+                self.read_char();
+            }
 
             // 識別子とリテラル
+            Some('"') => match self.read_string() {
+                Some(s) => tok = Some(Token::new(TokenType::STRING, &s)),
+                None => tok = Some(Token::new(TokenType::ILLEGAL, "\"")),
+            },
             Some(c) => {
                 if is_letter(&c) {
                     let ident = self.read_identifier();
                     let token_type = TokenType::lookup_ident(&ident);
                     tok = Some(Token::new(token_type, &ident));
                 } else if is_digit(&c) {
-                    tok = Some(Token::new(TokenType::INT, &self.read_number()));
+                    let (token_type, literal) = self.read_number();
+                    tok = Some(Token::new(token_type, &literal));
                 } else {
+                    self.make_error(position, &format!("不正な文字{}があります。", c));
                     tok = Some(Token::new(TokenType::ILLEGAL, &c.to_string()));
+                    self.read_char();
                 }
             }
 
@@ -205,19 +460,64 @@ impl Lexer {
             }
         };
 
-        if tok.is_none() {
-            tok = Some(Token::new(TokenType::ILLEGAL, ""));
+        let tok = match tok {
+            Some(tok) => tok,
+            None => Token::new(TokenType::ILLEGAL, ""),
+        };
+        // 入力の終わり以外で位置が進まないと呼び出し側が無限ループするので必ず進める
+        if !tok.token_type_is(TokenType::EOF) && self.position <= start {
+            debug_assert!(
+                self.ch.is_none(),
+                "字句解析器の位置が進んでいません。{:?}",
+                tok
+            );
+            self.read_char();
         }
-        return tok.unwrap();
+        return Token::with_position(tok.get_token_type(), tok.literal(), position);
+    }
+
+    /// 字句解析のエラーを位置情報付きで追加する関数
+    fn make_error(&mut self, position: (usize, usize), msg: &str) {
+        let msg = format!("{}行{}列目: {}", position.0, position.1, msg);
+        self.errors.push(msg);
     }
 }
 
-/// 識別子用の文字判定関数
+/// 識別子用の文字判定関数。'_'とUnicodeの英字(πや名前など)を識別子に使える。
 fn is_letter(ch: &char) -> bool {
-    return ('a' <= *ch && *ch <= 'z') || ('A' <= *ch && *ch <= 'Z') || *ch == '_';
+    return ch.is_alphabetic() || *ch == '_';
 }
 
 /// 数字用の判定関数
 fn is_digit(ch: &char) -> bool {
     return '0' <= *ch && *ch <= '9';
 }
+
+/// エスケープシーケンスの'\'に続く文字を対応する文字に変換する関数
+fn unescape(ch: char) -> Option<char> {
+    match ch {
+        'n' => Some('\n'),
+        't' => Some('\t'),
+        'r' => Some('\r'),
+        '"' => Some('"'),
+        '\\' => Some('\\'),
+        _ => None,
+    }
+}
+
+/// 文字列を字句解析器が同じ文字列として読み込める文字列リテラルの形に変換する関数
+pub fn escape_string(value: &str) -> String {
+    let mut s = "\"".to_string();
+    for c in value.chars() {
+        match c {
+            '\n' => s.push_str("\\n"),
+            '\t' => s.push_str("\\t"),
+            '\r' => s.push_str("\\r"),
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            _ => s.push(c),
+        }
+    }
+    s.push('"');
+    return s;
+}
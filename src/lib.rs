@@ -1,6 +1,9 @@
 //! Monkey言語の処理系に関するモジュールです。
 //! Monkey言語に関してはREADMEを参照してください。
 
+// 明示的なreturnはこのクレートの書き方なので、クレート全体で許容する
+#![allow(clippy::needless_return)]
+
 /// 字句解析が返しうるトークン列に関するモジュール
 pub mod token;
 
@@ -13,11 +16,31 @@ pub mod ast;
 /// パーサー(構文解析器)用モジュール
 pub mod parser;
 
+/// 解釈木をソースコードに整形するためのモジュール
+pub mod fmt;
+
+/// 解釈木を評価せずに調べるためのモジュール
+pub mod analysis;
+
 /// REPLを扱うためのモジュール
 pub mod repl;
 
 /// オブジェクトシステム用のモジュール
 pub mod object;
 
+/// 識別子の束縛を管理する環境用のモジュール
+pub mod environment;
+
+/// 組み込み関数用のモジュール
+pub mod builtins;
+
+/// cargo-fuzzで字句解析と構文解析を試すための入口のモジュール
+/// `fuzz`フィーチャーを有効にしたときだけ公開する。
+#[cfg(any(test, feature = "fuzz"))]
+pub mod fuzz;
+
+/// 評価器が使う数値型の演算を抽象化するモジュール
+mod numeric;
+
 /// 式を評価するためのモジュール
 pub mod evaluator;
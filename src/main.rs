@@ -1,8 +1,41 @@
 use std::io::{stdin, stdout, Write};
+use std::path::Path;
 
-use monkey_rs::repl::start;
+use monkey_rs::repl::{dump_file, start, DumpMode};
+
+/// 使い方の説明
+const USAGE: &str = "usage: monkey-rs [--tokens <path> | --ast <path>]";
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        // ファイルのトークン列か解釈木を出力して評価せずに終了する
+        let mode = match args[0].as_str() {
+            "--tokens" => DumpMode::Tokens,
+            "--ast" => DumpMode::Ast,
+            _ => {
+                eprintln!("{}", USAGE);
+                std::process::exit(2);
+            }
+        };
+        let path = match args.get(1) {
+            Some(path) => path,
+            None => {
+                eprintln!("{}", USAGE);
+                std::process::exit(2);
+            }
+        };
+        match dump_file(Path::new(path), mode, &mut stdout()) {
+            Ok(true) => return,
+            // 不正なトークンかパースエラーがあった
+            Ok(false) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("{}を読み込めませんでした。{}", path, e);
+                std::process::exit(1);
+            }
+        }
+    }
+
     let r = stdin();
     let mut w = stdout();
 
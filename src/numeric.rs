@@ -0,0 +1,52 @@
+use crate::object::Object;
+
+/// 評価器が数値の演算に使う数値型の操作
+/// 整数は今は`i64`だけだが、多倍長整数などを追加するときはこのトレイトを実装すれば評価器を書き換えずに済む。
+/// 比較演算子は`PartialOrd`で評価する。
+/// 四則演算は結果がこの型で表せないときにNoneを返し、信頼できないスクリプトでもホストをパニックさせない。
+pub(crate) trait Numeric: Sized + PartialOrd {
+    /// オブジェクトから値を取り出す関数。この型で表せないオブジェクトならNone
+    fn from_object(obj: &Object) -> Option<Self>;
+    /// 値をオブジェクトにする関数
+    fn into_object(self) -> Object;
+    /// 0かどうかの判定。0での割り算を溢れと区別するために使う
+    fn is_zero(&self) -> bool;
+    fn add(&self, rhs: &Self) -> Option<Self>;
+    fn sub(&self, rhs: &Self) -> Option<Self>;
+    fn mul(&self, rhs: &Self) -> Option<Self>;
+    /// 0で割ったときもNoneを返す
+    fn div(&self, rhs: &Self) -> Option<Self>;
+}
+
+impl Numeric for i64 {
+    fn from_object(obj: &Object) -> Option<Self> {
+        match obj {
+            Object::Integer { value } => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn into_object(self) -> Object {
+        Object::integer(self)
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == 0
+    }
+
+    fn add(&self, rhs: &Self) -> Option<Self> {
+        self.checked_add(*rhs)
+    }
+
+    fn sub(&self, rhs: &Self) -> Option<Self> {
+        self.checked_sub(*rhs)
+    }
+
+    fn mul(&self, rhs: &Self) -> Option<Self> {
+        self.checked_mul(*rhs)
+    }
+
+    fn div(&self, rhs: &Self) -> Option<Self> {
+        self.checked_div(*rhs)
+    }
+}
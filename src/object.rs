@@ -1,102 +1,454 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::io::Write;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+
+use crate::ast::{Expression, Statement};
+use crate::environment::Environment;
+use crate::lexer::escape_string;
+
 // 定数
 const NULL_OBJECT: &str = "NULL";
 const INTEGER_OBJECT: &str = "INTEGER";
+const FLOAT_OBJECT: &str = "FLOAT";
 const BOOLEAN_OBJECT: &str = "BOOLEAN";
 const RETURN_VALUE_OBJECT: &str = "RETURN_VALUE";
+const FUNCTION_OBJECT: &str = "FUNCTION";
+const ERROR_OBJECT: &str = "ERROR";
+const ARRAY_OBJECT: &str = "ARRAY";
+const HASH_OBJECT: &str = "HASH";
+const STRING_OBJECT: &str = "STRING";
+const BUILTIN_OBJECT: &str = "BUILTIN";
+const BREAK_OBJECT: &str = "BREAK";
+const CONTINUE_OBJECT: &str = "CONTINUE";
+const RANGE_OBJECT: &str = "RANGE";
+
+/// 共有の表に持つ小さい整数の範囲
+pub const SMALL_INTEGERS: RangeInclusive<i64> = -128..=256;
+
+thread_local! {
+    // `SMALL_INTEGERS`の整数のオブジェクトを小さい順に並べた表
+    // オブジェクトは`Rc`を含みスレッド間で共有できないので、スレッドごとに持つ
+    static SMALL_INTEGER_CACHE: Vec<Object> = SMALL_INTEGERS.map(|value| Object::Integer { value }).collect();
+}
+
+/// 組み込み関数を呼び出した評価器の書き込み先
+/// `puts`のように出力する組み込み関数は、標準出力ではなくここに書き込む。
+pub struct BuiltinContext<'a> {
+    pub out: &'a mut dyn Write,
+    pub err: &'a mut dyn Write,
+}
+
+/// 組み込み関数の実体。呼び出した評価器の書き込み先と評価済みの引数を受け取って結果のオブジェクトを返す。
+pub type BuiltinFunction = fn(&mut BuiltinContext, &[Object]) -> Object;
 
 /// オブジェクトシステム上で管理するための型情報
-#[derive(Debug, Eq, PartialEq, Clone, Hash)]
-pub struct ObjectType {
-    object_type: String,
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub enum ObjectType {
+    Null,
+    Integer,
+    Float,
+    Boolean,
+    String,
+    ReturnValue,
+    Function,
+    Error,
+    Array,
+    Hash,
+    Builtin,
+    Break,
+    Continue,
+    Range,
 }
 
 impl ObjectType {
-    pub fn integer_object_type() -> Self {
-        ObjectType {
-            object_type: INTEGER_OBJECT.to_string(),
-        }
-    }
+    /// 全てのオブジェクトの型の一覧。型を追加したらここにも追加する。
+    const ALL: &'static [ObjectType] = &[
+        ObjectType::Null,
+        ObjectType::Integer,
+        ObjectType::Float,
+        ObjectType::Boolean,
+        ObjectType::String,
+        ObjectType::ReturnValue,
+        ObjectType::Function,
+        ObjectType::Error,
+        ObjectType::Array,
+        ObjectType::Hash,
+        ObjectType::Builtin,
+        ObjectType::Break,
+        ObjectType::Continue,
+        ObjectType::Range,
+    ];
 
-    pub fn boolean_object_type() -> Self {
-        ObjectType {
-            object_type: BOOLEAN_OBJECT.to_string(),
-        }
+    /// 全てのオブジェクトの型を返す関数
+    pub fn all() -> &'static [ObjectType] {
+        return ObjectType::ALL;
     }
 
-    pub fn null_object_type() -> Self {
-        ObjectType {
-            object_type: NULL_OBJECT.to_string(),
-        }
+    pub fn is_integer(&self) -> bool {
+        *self == ObjectType::Integer
     }
-
-    pub fn return_value_object_type() -> Self {
-        ObjectType {
-            object_type: RETURN_VALUE_OBJECT.to_string(),
-        }
+    pub fn is_float(&self) -> bool {
+        *self == ObjectType::Float
     }
-
-    pub fn is_integer(&self) -> bool {
-        &self.object_type == INTEGER_OBJECT
+    /// 算術演算と大小比較ができる数値の型かどうか
+    pub fn is_number(&self) -> bool {
+        self.is_integer() || self.is_float()
     }
     pub fn is_boolean(&self) -> bool {
-        &self.object_type == BOOLEAN_OBJECT
+        *self == ObjectType::Boolean
     }
     pub fn is_null(&self) -> bool {
-        &self.object_type == NULL_OBJECT
+        *self == ObjectType::Null
     }
     pub fn is_return_value(&self) -> bool {
-        &self.object_type == RETURN_VALUE_OBJECT
+        *self == ObjectType::ReturnValue
+    }
+    pub fn is_function(&self) -> bool {
+        *self == ObjectType::Function
+    }
+    pub fn is_error(&self) -> bool {
+        *self == ObjectType::Error
+    }
+    pub fn is_array(&self) -> bool {
+        *self == ObjectType::Array
+    }
+    pub fn is_hash(&self) -> bool {
+        *self == ObjectType::Hash
+    }
+    pub fn is_string(&self) -> bool {
+        *self == ObjectType::String
+    }
+    pub fn is_builtin(&self) -> bool {
+        *self == ObjectType::Builtin
+    }
+    pub fn is_break(&self) -> bool {
+        *self == ObjectType::Break
+    }
+    pub fn is_continue(&self) -> bool {
+        *self == ObjectType::Continue
+    }
+    pub fn is_range(&self) -> bool {
+        *self == ObjectType::Range
+    }
+    /// 文の並びの評価をそこで打ち切って外側に伝える型かどうか
+    pub fn interrupts(&self) -> bool {
+        self.is_return_value() || self.is_error() || self.is_break() || self.is_continue()
+    }
+    /// ハッシュのキーに使える型かどうか
+    pub fn is_hashable(&self) -> bool {
+        self.is_integer() || self.is_boolean() || self.is_string()
     }
 }
 
+/// エラーメッセージで使う型の名前
+#[allow(clippy::to_string_trait_impl)]
 impl ToString for ObjectType {
     fn to_string(&self) -> String {
-        self.object_type.to_string()
+        let name = match self {
+            ObjectType::Null => NULL_OBJECT,
+            ObjectType::Integer => INTEGER_OBJECT,
+            ObjectType::Float => FLOAT_OBJECT,
+            ObjectType::Boolean => BOOLEAN_OBJECT,
+            ObjectType::String => STRING_OBJECT,
+            ObjectType::ReturnValue => RETURN_VALUE_OBJECT,
+            ObjectType::Function => FUNCTION_OBJECT,
+            ObjectType::Error => ERROR_OBJECT,
+            ObjectType::Array => ARRAY_OBJECT,
+            ObjectType::Hash => HASH_OBJECT,
+            ObjectType::Builtin => BUILTIN_OBJECT,
+            ObjectType::Break => BREAK_OBJECT,
+            ObjectType::Continue => CONTINUE_OBJECT,
+            ObjectType::Range => RANGE_OBJECT,
+        };
+        name.to_string()
     }
 }
 
 /// オブジェクトシステム上で扱うオブジェクト情報
-#[derive(Debug, PartialEq, Clone, Hash)]
+#[derive(Debug, Clone)]
 pub enum Object {
     Null,
-    Integer { value: i64 },
-    Boolean { value: bool },
-    ReturnValue { value: Box<Object>},
+    Integer {
+        value: i64,
+    },
+    // 浮動小数点数はNaNが自分自身と等しくないので、ハッシュのキーには使えない
+    Float {
+        value: f64,
+    },
+    Boolean {
+        value: bool,
+    },
+    Str {
+        value: String,
+    },
+    ReturnValue {
+        value: Box<Object>,
+    },
+    Function {
+        // Expression::Identifierの集まり
+        parameters: Vec<Box<Expression>>,
+        // 関数本体。Statement::BlockStatementのこと
+        // 関数リテラルを評価するたびに作られ、同じ関数の複製どうしで共有する
+        body: Rc<Statement>,
+        // 関数が定義された環境
+        env: Rc<RefCell<Environment>>,
+        // let文で束縛したときの名前。無名関数ならNone
+        name: Option<String>,
+    },
+    Error {
+        message: String,
+        // エラーの原因になった式や文のソース上の(行, 列)
+        position: Option<(usize, usize)>,
+    },
+    Array {
+        elements: Vec<Object>,
+    },
+    // 挿入順を保持するためにキーと値の組を並べて持つ
+    Hash {
+        pairs: Vec<(Object, Object)>,
+    },
+    Builtin {
+        // 呼び出すときに使う名前
+        name: String,
+        function: BuiltinFunction,
+    },
+    // ループを抜けることを外側のループに伝えるオブジェクト
+    Break,
+    // 次の繰り返しに進むことを外側のループに伝えるオブジェクト
+    Continue,
+    // 始まりを含み終わりを含まない整数の範囲。終わりが始まり以下なら空の範囲
+    Range {
+        start: i64,
+        end: i64,
+    },
 }
 
-impl ToString for Object {
-    fn to_string(&self) -> String {
+/// エラーは`Token`と同じく位置を比べずにメッセージだけで比べる
+/// 関数は構造ではなく同一性で比べる
+// 組み込み関数の比較には関数ポインタも使うが、名前も比べるので異なる組み込み関数を取り違えることはない
+#[allow(unpredictable_function_pointer_comparisons)]
+impl PartialEq for Object {
+    fn eq(&self, other: &Object) -> bool {
         use Object::*;
-        match self {
-            Null => "null".to_string(),
-            Integer { value: v } => format!("{}", v),
-            Boolean { value: v } => format!("{}", v),
-            ReturnValue { value: obj }  => format!("{}", obj.to_string()),
+        match (self, other) {
+            (Null, Null) => true,
+            (Break, Break) => true,
+            (Continue, Continue) => true,
+            (Range { start: ls, end: le }, Range { start: rs, end: re }) => ls == rs && le == re,
+            (Integer { value: l }, Integer { value: r }) => l == r,
+            (Float { value: l }, Float { value: r }) => l == r,
+            (Boolean { value: l }, Boolean { value: r }) => l == r,
+            (Str { value: l }, Str { value: r }) => l == r,
+            (ReturnValue { value: l }, ReturnValue { value: r }) => l == r,
+            // 関数は同じ関数リテラルの評価で作られたものどうしだけが等しい
+            // 本体の構文木や環境の中身は比べない
+            (
+                Function {
+                    parameters: _,
+                    body: lb,
+                    env: _,
+                    name: _,
+                },
+                Function {
+                    parameters: _,
+                    body: rb,
+                    env: _,
+                    name: _,
+                },
+            ) => Rc::ptr_eq(lb, rb),
+            (
+                Error {
+                    message: l,
+                    position: _,
+                },
+                Error {
+                    message: r,
+                    position: _,
+                },
+            ) => l == r,
+            (Array { elements: l }, Array { elements: r }) => l == r,
+            (Hash { pairs: l }, Hash { pairs: r }) => l == r,
+            (
+                Builtin {
+                    name: ln,
+                    function: lf,
+                },
+                Builtin {
+                    name: rn,
+                    function: rf,
+                },
+            ) => ln == rn && lf == rf,
+            _ => false,
         }
     }
 }
 
+/// Rustの書式化では`inspect`と同じ表示にする
+impl fmt::Display for Object {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.inspect())
+    }
+}
+
 impl Object {
     pub const BOOLEAN_TRUE: Object = Object::Boolean { value: true };
     pub const BOOLEAN_FALSE: Object = Object::Boolean { value: false };
     pub const NULL: Object = Object::Null;
 
+    /// 真偽値オブジェクトを生成する関数。共有の定数`BOOLEAN_TRUE`か`BOOLEAN_FALSE`を返す。
+    pub const fn boolean(value: bool) -> Object {
+        if value {
+            Object::BOOLEAN_TRUE
+        } else {
+            Object::BOOLEAN_FALSE
+        }
+    }
+
+    /// 整数オブジェクトを生成する関数
+    /// 真偽値の定数と同じように、よく使う小さい整数は共有の表`SMALL_INTEGERS`から返す。
+    pub fn integer(value: i64) -> Object {
+        match Object::small_integer(value) {
+            Some(object) => object,
+            None => Object::Integer { value },
+        }
+    }
+
+    /// 共有の表にある小さい整数のオブジェクトを返す関数。表の範囲外ならNone
+    pub fn small_integer(value: i64) -> Option<Object> {
+        if !SMALL_INTEGERS.contains(&value) {
+            return None;
+        }
+        let index = (value - SMALL_INTEGERS.start()) as usize;
+        return Some(SMALL_INTEGER_CACHE.with(|cache| cache[index].clone()));
+    }
+
+    /// 整数を`0x`を付けた16進数の文字列にする関数。整数以外ならNone
+    /// 負の数は2の補数ではなく、`-0xff`のように絶対値の前に符号を付けて表す。
+    pub fn to_hex(&self) -> Option<String> {
+        self.format_integer("0x", |v| format!("{:x}", v))
+    }
+
+    /// 整数を`0b`を付けた2進数の文字列にする関数。整数以外ならNone
+    /// 負の数は`to_hex`と同じく`-0b101`のように絶対値の前に符号を付けて表す。
+    pub fn to_binary(&self) -> Option<String> {
+        self.format_integer("0b", |v| format!("{:b}", v))
+    }
+
+    /// 整数の符号と接頭辞の後に絶対値を指定した進数で並べる関数
+    fn format_integer(&self, prefix: &str, digits: fn(u64) -> String) -> Option<String> {
+        match self {
+            Object::Integer { value } => {
+                let sign = if *value < 0 { "-" } else { "" };
+                Some(format!(
+                    "{}{}{}",
+                    sign,
+                    prefix,
+                    digits(value.unsigned_abs())
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// 位置を持たないエラーオブジェクトを生成する関数
+    /// 評価器が評価していた式や文の位置を後から付ける。
+    pub fn error(message: &str) -> Object {
+        Object::Error {
+            message: message.to_string(),
+            position: None,
+        }
+    }
+
+    /// エラーの原因になったソース上の(行, 列)を返す関数。エラー以外か位置が分からなければNone
+    pub fn error_position(&self) -> Option<(usize, usize)> {
+        match self {
+            Object::Error {
+                message: _,
+                position,
+            } => *position,
+            _ => None,
+        }
+    }
+
     pub fn get_type(&self) -> ObjectType {
         match self {
-            Object::Null => ObjectType::null_object_type(),
-            Object::Integer { value: _ } => ObjectType::integer_object_type(),
-            Object::Boolean { value: _ } => ObjectType::boolean_object_type(),
-            Object::ReturnValue { value: _ } => ObjectType::return_value_object_type(),
+            Object::Null => ObjectType::Null,
+            Object::Integer { value: _ } => ObjectType::Integer,
+            Object::Float { value: _ } => ObjectType::Float,
+            Object::Boolean { value: _ } => ObjectType::Boolean,
+            Object::Str { value: _ } => ObjectType::String,
+            Object::ReturnValue { value: _ } => ObjectType::ReturnValue,
+            Object::Function {
+                parameters: _,
+                body: _,
+                env: _,
+                name: _,
+            } => ObjectType::Function,
+            Object::Error {
+                message: _,
+                position: _,
+            } => ObjectType::Error,
+            Object::Array { elements: _ } => ObjectType::Array,
+            Object::Hash { pairs: _ } => ObjectType::Hash,
+            Object::Builtin {
+                name: _,
+                function: _,
+            } => ObjectType::Builtin,
+            Object::Break => ObjectType::Break,
+            Object::Continue => ObjectType::Continue,
+            Object::Range { start: _, end: _ } => ObjectType::Range,
         }
     }
+    /// ユーザーに見せるオブジェクトの表示を返す関数
+    /// オブジェクトの表示はこの関数にまとめ、`Display`(と`to_string`)はこの関数に任せる。
     pub fn inspect(&self) -> String {
-        self.to_string()
+        use Object::*;
+        match self {
+            Null => "null".to_string(),
+            Integer { value: v } => format!("{}", v),
+            // 整数と見分けられるように、小数部が0でも`2.0`のように小数点を付ける
+            Float { value: v } => format!("{:?}", v),
+            Boolean { value: v } => format!("{}", v),
+            // 表示した形がそのままMonkeyの文字列リテラルとして読み込めるようにエスケープする
+            Str { value: v } => escape_string(v),
+            ReturnValue { value: obj } => obj.inspect(),
+            Function {
+                parameters,
+                body,
+                env: _,
+                name: _,
+            } => {
+                let params: Vec<String> = parameters.iter().map(|p| p.to_string()).collect();
+                format!("fn({}) {}", params.join(", "), body)
+            }
+            Error {
+                message,
+                position: _,
+            } => format!("ERROR: {}", message),
+            Array { elements } => {
+                let elems: Vec<String> = elements.iter().map(|e| e.inspect()).collect();
+                format!("[{}]", elems.join(", "))
+            }
+            Hash { pairs } => {
+                let ps: Vec<String> = pairs
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", k.inspect(), v.inspect()))
+                    .collect();
+                format!("{{{}}}", ps.join(", "))
+            }
+            Builtin { name, function: _ } => format!("builtin function {}", name),
+            Break => "break".to_string(),
+            Continue => "continue".to_string(),
+            Range { start, end } => format!("{}..{}", start, end),
+        }
     }
 
-    pub fn is_truthy(&self) -> bool{
+    pub fn is_truthy(&self) -> bool {
         let object_type = self.get_type();
-        if object_type.is_null(){
-            return true;
+        if object_type.is_null() {
+            return false;
         }
         if let Object::Boolean { value } = self {
             return *value;
@@ -105,3 +457,192 @@ impl Object {
         true
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::environment::Environment;
+    use crate::evaluator::Eval;
+    use crate::lexer::Lexer;
+    use crate::object::{Object, ObjectType};
+    use crate::parser::Parser;
+    use crate::token::TokenType;
+
+    #[test]
+    fn test_smart_constructors() {
+        assert_eq!(Object::boolean(true), Object::BOOLEAN_TRUE);
+        assert_eq!(Object::boolean(false), Object::BOOLEAN_FALSE);
+        assert_eq!(Object::integer(-3), Object::Integer { value: -3 });
+    }
+
+    #[test]
+    fn test_small_integer_cache() {
+        // 範囲内の整数は共有の表から返す
+        for value in [-128, 0, 5, 256].iter() {
+            assert_eq!(Object::small_integer(*value), Some(Object::integer(*value)));
+        }
+        assert_eq!(Object::small_integer(-129), None);
+        assert_eq!(Object::small_integer(257), None);
+        assert_eq!(Object::integer(257), Object::Integer { value: 257 });
+
+        // 評価した整数リテラルも表のオブジェクトと等しい
+        let program = Parser::new(Lexer::new("5;")).parse_program().unwrap();
+        let env = Rc::new(RefCell::new(Environment::new()));
+        let evaluated = Eval::new(env).eval_program(&program);
+        assert_eq!(Some(evaluated), Object::small_integer(5));
+    }
+
+    #[test]
+    fn test_integer_in_bases() {
+        assert_eq!(Object::integer(255).to_hex(), Some("0xff".to_string()));
+        assert_eq!(Object::integer(-255).to_hex(), Some("-0xff".to_string()));
+        assert_eq!(Object::integer(5).to_binary(), Some("0b101".to_string()));
+        assert_eq!(Object::integer(0).to_binary(), Some("0b0".to_string()));
+        assert_eq!(
+            Object::integer(i64::MIN).to_hex(),
+            Some("-0x8000000000000000".to_string())
+        );
+        assert_eq!(Object::BOOLEAN_TRUE.to_hex(), None);
+    }
+
+    type Predicate = fn(&ObjectType) -> bool;
+
+    #[test]
+    fn test_object_type_predicates() {
+        let predicates: [(&str, Predicate); 14] = [
+            ("NULL", ObjectType::is_null),
+            ("INTEGER", ObjectType::is_integer),
+            ("FLOAT", ObjectType::is_float),
+            ("BOOLEAN", ObjectType::is_boolean),
+            ("STRING", ObjectType::is_string),
+            ("RETURN_VALUE", ObjectType::is_return_value),
+            ("FUNCTION", ObjectType::is_function),
+            ("ERROR", ObjectType::is_error),
+            ("ARRAY", ObjectType::is_array),
+            ("HASH", ObjectType::is_hash),
+            ("BUILTIN", ObjectType::is_builtin),
+            ("BREAK", ObjectType::is_break),
+            ("CONTINUE", ObjectType::is_continue),
+            ("RANGE", ObjectType::is_range),
+        ];
+        let all = ObjectType::all();
+        assert_eq!(all.len(), predicates.len());
+        for (name, predicate) in predicates.iter() {
+            let matched: Vec<&ObjectType> = all.iter().filter(|t| predicate(t)).collect();
+            assert_eq!(matched.len(), 1, "{}", name);
+            assert_eq!(matched[0].to_string(), *name);
+        }
+    }
+
+    fn integers(values: &[i64]) -> Vec<Object> {
+        values
+            .iter()
+            .map(|v| Object::Integer { value: *v })
+            .collect()
+    }
+
+    #[test]
+    fn test_inspect_agrees_with_display() {
+        let mut parser = Parser::new(Lexer::new("fn(x) { x; };"));
+        let program = parser.parse_program().expect("fail parse program.");
+        let env = Rc::new(RefCell::new(Environment::new()));
+        let function = Eval::new(Rc::clone(&env)).eval_program(&program);
+        let program = Parser::new(Lexer::new("len;")).parse_program().unwrap();
+        let builtin = Eval::new(Rc::clone(&env)).eval_program(&program);
+
+        let objects = [
+            Object::NULL,
+            Object::integer(-3),
+            Object::Float { value: 2.5 },
+            Object::BOOLEAN_TRUE,
+            Object::Str {
+                value: "a\"b".to_string(),
+            },
+            Object::ReturnValue {
+                value: Box::new(Object::integer(1)),
+            },
+            function,
+            Object::error("boom"),
+            Object::Array {
+                elements: vec![Object::integer(1), Object::NULL],
+            },
+            Object::Hash {
+                pairs: vec![(Object::integer(1), Object::BOOLEAN_FALSE)],
+            },
+            builtin,
+            Object::Break,
+            Object::Continue,
+            Object::Range { start: 1, end: 3 },
+        ];
+        // どのオブジェクトも同じ表示になる
+        for object in objects.iter() {
+            assert_eq!(object.to_string(), object.inspect());
+            assert_eq!(format!("{}", object), object.inspect());
+        }
+    }
+
+    #[test]
+    fn test_inspect_array() {
+        let array = Object::Array {
+            elements: integers(&[1, 2, 3]),
+        };
+        assert_eq!(array.inspect(), "[1, 2, 3]");
+        assert_eq!(Object::Array { elements: vec![] }.inspect(), "[]");
+    }
+
+    #[test]
+    fn test_inspect_hash() {
+        let hash = Object::Hash {
+            pairs: vec![
+                (Object::Integer { value: 1 }, Object::BOOLEAN_TRUE),
+                (Object::BOOLEAN_FALSE, Object::Integer { value: 2 }),
+            ],
+        };
+        assert_eq!(hash.inspect(), "{1: true, false: 2}");
+        assert_eq!(Object::Hash { pairs: vec![] }.inspect(), "{}");
+    }
+
+    #[test]
+    fn test_inspect_nested_array_in_hash() {
+        let hash = Object::Hash {
+            pairs: vec![(
+                Object::Integer { value: 1 },
+                Object::Array {
+                    elements: vec![
+                        Object::Integer { value: 1 },
+                        Object::Array {
+                            elements: integers(&[2, 3]),
+                        },
+                    ],
+                },
+            )],
+        };
+        assert_eq!(hash.inspect(), "{1: [1, [2, 3]]}");
+    }
+
+    #[test]
+    fn test_inspect_function() {
+        let mut parser = Parser::new(Lexer::new("fn(x, y) { x + y; };"));
+        let program = parser.parse_program().expect("fail parse program.");
+        let env = Rc::new(RefCell::new(Environment::new()));
+        let function = Eval::new(Rc::clone(&env)).eval_program(&program);
+
+        assert_eq!(function.inspect(), "fn(x, y) {(x + y);}");
+    }
+
+    #[test]
+    fn test_inspect_string_round_trip() {
+        let value = "line1\n\tline2 \"quoted\" \\ end".to_string();
+        let string = Object::Str {
+            value: value.clone(),
+        };
+        let inspected = string.inspect();
+        assert_eq!(inspected, r#""line1\n\tline2 \"quoted\" \\ end""#);
+
+        let tok = Lexer::new(&inspected).next_token();
+        assert!(tok.token_type_is(TokenType::STRING));
+        assert_eq!(tok.get_literal(), value);
+    }
+}
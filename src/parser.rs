@@ -7,28 +7,282 @@ use crate::token::{Token, TokenType};
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Hash)]
 pub enum Opt {
     LOWEST,
-    EQUALS,
+    // ||
+    OR,
+    // &&
+    AND,
     // ==
-    LESSGREATER,
+    EQUALS,
     // > or <
-    SUM,
+    LESSGREATER,
+    // start..end
+    RANGE,
     // +
+    SUM,
+    // *
     PRODUCT,
-    //*
-    PREFIX,
     // -x or !x
-    CALL, // myFunction(x)
+    PREFIX,
+    // myFunction(x)
+    CALL,
+    // array[index] or hash.field
+    INDEX,
+}
+
+/// パーサーの厳しさを調整するための設定
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct ParserOptions {
+    /// `x;;`のように文の後ろに余分なセミコロンを書けるかどうか
+    pub allow_trailing_semicolons: bool,
+    /// if式の条件を`if (x) {...}`のように括弧で囲む必要があるかどうか。falseなら`if x {...}`も書ける。
+    pub strict_if_parentheses: bool,
+    /// 式の入れ子の深さの上限。深すぎる入力でスタックが溢れる前にエラーにする。
+    pub max_nesting_depth: usize,
+    /// ファイルから読み込むスクリプトとしてパースするかどうか
+    /// trueならプログラム直下の文は、改行の後に次の文を書くか入力の終わりならセミコロンを省略できる。
+    /// ブロックの中の文は今まで通りセミコロンが必要。
+    pub script: bool,
+}
+
+impl ParserOptions {
+    /// 既定の設定を返す関数。`Parser::new`はこの設定を使う。
+    pub fn new() -> Self {
+        return ParserOptions {
+            allow_trailing_semicolons: false,
+            strict_if_parentheses: true,
+            max_nesting_depth: 256,
+            script: false,
+        };
+    }
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        return ParserOptions::new();
+    }
+}
+
+/// パースエラーの種類
+/// ツールが特定のエラーに反応できるように種類ごとに分ける。位置はエラーを検出したトークンの(行, 列)。
+/// `Display`はエラーの説明文だけを表示し、`Parser::get_errors`はその後ろにトークン情報を付ける。
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum ParseError {
+    /// 異常なトークンを読み込んだ
+    IllegalToken { position: (usize, usize) },
+    /// 文をパースできなかった
+    InvalidStatement { position: (usize, usize) },
+    /// 式をパースできなかった
+    InvalidExpression { position: (usize, usize) },
+    /// 識別子をパースできなかった
+    InvalidIdentifier { position: (usize, usize) },
+    /// 整数リテラルをパースできなかった
+    InvalidInteger { position: (usize, usize) },
+    /// 浮動小数点数リテラルをパースできなかった
+    InvalidFloat { position: (usize, usize) },
+    /// 真理値リテラルをパースできなかった
+    InvalidBoolean { position: (usize, usize) },
+    /// 関数リテラルの引数をパースできなかった
+    InvalidParameters { position: (usize, usize) },
+    /// 関数リテラルの引数名が重複している
+    DuplicateParameter {
+        name: String,
+        position: (usize, usize),
+    },
+    /// 前置演算子の式をパースできなかった
+    InvalidPrefixExpression { position: (usize, usize) },
+    /// 中置演算子の式をパースできなかった
+    InvalidInfixExpression { position: (usize, usize) },
+    /// ブロックをパースできなかった
+    InvalidBlock { position: (usize, usize) },
+    /// ブロックが右波括弧で閉じられる前に入力が終わった
+    UnterminatedBlock { position: (usize, usize) },
+    /// 関数呼び出しの引数をパースできなかった
+    InvalidCallArguments { position: (usize, usize) },
+    /// 配列リテラルの要素をパースできなかった
+    InvalidArrayElements { position: (usize, usize) },
+    /// 式があるべき位置で入力が終わった
+    EmptyExpression { position: (usize, usize) },
+    /// ハッシュリテラルのキーと値の組をパースできなかった
+    InvalidHashPairs { position: (usize, usize) },
+    /// switch式の分岐をパースできなかった
+    InvalidSwitchArm { position: (usize, usize) },
+    /// switch式の`_`の分岐の後に分岐が続いた
+    SwitchDefaultNotLast { position: (usize, usize) },
+    /// 式の入れ子が上限より深くなった
+    NestingTooDeep {
+        max_depth: usize,
+        position: (usize, usize),
+    },
+    /// 式の始まりに使えないトークンを読み込んだ
+    UnknownToken {
+        found: TokenType,
+        position: (usize, usize),
+    },
+    /// 期待したトークン型と違うトークンを読み込んだ
+    UnexpectedToken {
+        expected: TokenType,
+        found: TokenType,
+        position: (usize, usize),
+    },
+    /// 期待したトークン型と違うトークンを先読みした
+    UnexpectedPeekToken {
+        expected: TokenType,
+        found: TokenType,
+        position: (usize, usize),
+    },
+}
+
+impl ParseError {
+    /// エラーを検出したトークンの(行, 列)を返す関数
+    pub fn position(&self) -> (usize, usize) {
+        use ParseError::*;
+        match self {
+            IllegalToken { position }
+            | InvalidStatement { position }
+            | InvalidExpression { position }
+            | InvalidIdentifier { position }
+            | InvalidInteger { position }
+            | InvalidFloat { position }
+            | InvalidBoolean { position }
+            | InvalidParameters { position }
+            | DuplicateParameter { name: _, position }
+            | InvalidPrefixExpression { position }
+            | InvalidInfixExpression { position }
+            | InvalidBlock { position }
+            | UnterminatedBlock { position }
+            | InvalidCallArguments { position }
+            | InvalidArrayElements { position }
+            | EmptyExpression { position }
+            | InvalidHashPairs { position }
+            | InvalidSwitchArm { position }
+            | SwitchDefaultNotLast { position }
+            | NestingTooDeep {
+                max_depth: _,
+                position,
+            }
+            | UnknownToken { found: _, position }
+            | UnexpectedToken {
+                expected: _,
+                found: _,
+                position,
+            }
+            | UnexpectedPeekToken {
+                expected: _,
+                found: _,
+                position,
+            } => *position,
+        }
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use ParseError::*;
+        match self {
+            IllegalToken { position: _ } => write!(f, "異常なトークンを検出しました。"),
+            InvalidStatement { position: _ } => write!(f, "文をパースできませんでした。"),
+            InvalidExpression { position: _ } => write!(f, "式をパースできませんでした。"),
+            InvalidIdentifier { position: _ } => {
+                write!(f, "識別子リテラルをパースできませんでした。")
+            }
+            InvalidInteger { position: _ } => write!(f, "整数をパースできませんでした。"),
+            InvalidFloat { position: _ } => {
+                write!(f, "浮動小数点数をパースできませんでした。")
+            }
+            InvalidBoolean { position: _ } => write!(f, "真理値をパースできませんでした。"),
+            InvalidParameters { position: _ } => {
+                write!(f, "関数の引数をパースできませんでした。")
+            }
+            DuplicateParameter { name, position: _ } => {
+                write!(f, "関数の引数名{}が重複しています。", name)
+            }
+            InvalidPrefixExpression { position: _ } => {
+                write!(f, "前置演算子をパースできませんでした。")
+            }
+            InvalidInfixExpression { position: _ } => {
+                write!(f, "中置演算子をパースできませんでした。")
+            }
+            InvalidBlock { position: _ } | UnterminatedBlock { position: _ } => {
+                write!(f, "ブロックをパースできませんでした。")
+            }
+            InvalidCallArguments { position: _ } => write!(f, "引数をパースできませんでした。"),
+            InvalidArrayElements { position: _ } => {
+                write!(f, "配列の要素をパースできませんでした。")
+            }
+            EmptyExpression { position: _ } => write!(
+                f,
+                "式を期待しましたが入力が終わりました。式が空です。"
+            ),
+            InvalidHashPairs { position: _ } => write!(
+                f,
+                "ハッシュのキーと値の組をパースできませんでした。"
+            ),
+            InvalidSwitchArm { position: _ } => {
+                write!(f, "switch式の分岐をパースできませんでした。")
+            }
+            SwitchDefaultNotLast { position: _ } => write!(
+                f,
+                "switch式の`_`の分岐は最後に書く必要があります。"
+            ),
+            NestingTooDeep {
+                max_depth,
+                position: _,
+            } => write!(
+                f,
+                "式の入れ子の深さが上限の{}を超えました。",
+                max_depth
+            ),
+            UnknownToken {
+                found: _,
+                position: _,
+            } => write!(
+                f,
+                "予期せぬトークンを読み込みました。読み取ったトークンが不正です。"
+            ),
+            UnexpectedToken {
+                expected,
+                found,
+                position: _,
+            } => write!(
+                f,
+                "トークン型{:?}を期待して読みましたが、実際に読み込んだトークン型は{:?}でした。",
+                expected, found
+            ),
+            UnexpectedPeekToken {
+                expected,
+                found,
+                position: _,
+            } => write!(
+                f,
+                "トークン型{:?}を期待して先のトークンを読みましたが、実際に読み込んだトークン型は{:?}でした。",
+                expected, found
+            ),
+        }
+    }
 }
 
 /// パーサー(構文解析器)
 pub struct Parser {
-    lexer: Lexer,
     // 入力を元にトークンを返すための字句解析器
-    current_token: Token,
+    lexer: Lexer,
     // 現在読んでいるトークン
-    peek_token: Token,
+    current_token: Token,
     // 一つ先のトークン
-    errors: Vec<String>, // パースして失敗したときのエラー文の集まり
+    peek_token: Token,
+    // 現在読んでいるトークンの直前の行にあったコメント
+    current_doc: Option<String>,
+    // 一つ先のトークンの直前の行にあったコメント
+    peek_doc: Option<String>,
+    // パースして失敗したときのエラーの集まり
+    errors: Vec<ParseError>,
+    // 各エラーを記録したときのトークン情報。`errors`と同じ順に並ぶ
+    error_tokens: Vec<String>,
+    // パーサーの厳しさの設定
+    options: ParserOptions,
+    // 現在パースしている式の入れ子の深さ
+    depth: usize,
+    // 現在パースしている文の入れ子の深さ。プログラム直下の文は1
+    statement_depth: usize,
 }
 
 impl std::fmt::Debug for Parser {
@@ -45,13 +299,17 @@ impl std::fmt::Debug for Parser {
 
 impl Parser {
     /// 中置演算子の優先順位を返す関数
-    fn infix_precedence(token_type: &TokenType) -> Opt {
+    pub fn infix_precedence(token_type: &TokenType) -> Opt {
         match token_type {
+            TokenType::OR => Opt::OR,
+            TokenType::AND => Opt::AND,
             TokenType::EQ | TokenType::NEQ => Opt::EQUALS,
             TokenType::PLUS | TokenType::MINUS => Opt::SUM,
             TokenType::ASTERISK | TokenType::SLASH => Opt::PRODUCT,
             TokenType::LT | TokenType::GT => Opt::LESSGREATER,
+            TokenType::RANGE => Opt::RANGE,
             TokenType::LPAREN => Opt::CALL,
+            TokenType::LBRACKET | TokenType::DOT => Opt::INDEX,
             _ => Opt::LOWEST,
         }
     }
@@ -65,18 +323,51 @@ impl Parser {
     }
     // 基本的な関数群
     /// 初期化関数
-    pub fn new(mut lexer: Lexer) -> Self {
-        let first = lexer.next_token();
-        let second = lexer.next_token();
+    /// ドキュメントモードの字句解析器を渡すと、let文の直前の行にあるコメントをその文のdocに付ける。
+    pub fn new(lexer: Lexer) -> Self {
+        return Parser::with_options(lexer, ParserOptions::new());
+    }
+
+    /// 設定を指定してパーサーを生成する関数
+    pub fn with_options(mut lexer: Lexer, options: ParserOptions) -> Self {
+        let (first, first_doc) = Parser::read_token(&mut lexer, 0);
+        let line = first.get_position().0;
+        let (second, second_doc) = Parser::read_token(&mut lexer, line);
         let parser = Parser {
             lexer,
             current_token: first,
             peek_token: second,
+            current_doc: first_doc,
+            peek_doc: second_doc,
             errors: Vec::new(),
+            error_tokens: Vec::new(),
+            options,
+            depth: 0,
+            statement_depth: 0,
         };
         return parser;
     }
 
+    /// コメント以外のトークンを読んで、その直前にあったコメントと一緒に返す関数
+    /// 直前のトークンと同じ行にあるコメントは前の文の行末のコメントなので付けない。
+    fn read_token(lexer: &mut Lexer, prev_line: usize) -> (Token, Option<String>) {
+        let mut docs: Vec<String> = Vec::new();
+        loop {
+            let tok = lexer.next_token();
+            if !tok.token_type_is(TokenType::COMMENT) {
+                let doc = if docs.is_empty() {
+                    None
+                } else {
+                    Some(docs.join("\n"))
+                };
+                return (tok, doc);
+            }
+            if tok.get_position().0 != prev_line {
+                docs.push(tok.get_literal());
+            }
+        }
+    }
+
     /// 先のトークンの型を確認する関数
     fn peek_token_is(&self, token_type: TokenType) -> bool {
         return self.peek_token.get_token_type() == token_type;
@@ -91,13 +382,21 @@ impl Parser {
     /// 保持している字句解析器を使って一文字読む関数
     pub fn next_token(&mut self) {
         std::mem::swap(&mut self.current_token, &mut self.peek_token);
-        self.peek_token = self.lexer.next_token();
+        std::mem::swap(&mut self.current_doc, &mut self.peek_doc);
+        let line = self.current_token.get_position().0;
+        let (tok, doc) = Parser::read_token(&mut self.lexer, line);
+        self.peek_token = tok;
+        self.peek_doc = doc;
     }
 
-    /// 次に読み込む演算子が前置演算子のトークンか調べる関数
-    fn peek_token_is_prefix(&self) -> bool {
-        // 中置演算子の優先順位表をもちいて最低順位以外に変換できれば中置演算子ではない
-        self.peek_prefix_precedence() == Opt::PREFIX
+    /// 設定で許されていれば文の後ろの余分なセミコロンを読み飛ばす関数
+    fn skip_trailing_semicolons(&mut self) {
+        if !self.options.allow_trailing_semicolons {
+            return;
+        }
+        while self.current_token_is(TokenType::SEMICOLON) {
+            self.next_token();
+        }
     }
 
     /// 次に読み込む演算子が中置演算子のトークンか調べる関数
@@ -121,18 +420,59 @@ impl Parser {
         Parser::prefix_precedence(&self.current_token.get_token_type())
     }
 
-    /// 次に読み込むトークンの優先順位を取得する関数
-    fn peek_prefix_precedence(&self) -> Opt {
-        Parser::prefix_precedence(&self.peek_token.get_token_type())
-    }
     // パース処理
     // パース処理の基本はcurrentから解析を初めて、解析し終わったもので終わる
     // loopで一つ分になっているのでloopで次に来たら現在位置を更新
     /// 字句解析器の結果を元にMonkeyプログラムを表す解釈木を生成する関数
+    /// 一つでもパースエラーがあればNoneを返す。
     pub fn parse_program(&mut self) -> Option<Program> {
+        let program = self.parse_statements();
+        if !self.errors.is_empty() {
+            return None;
+        }
+        return Some(program);
+    }
+
+    /// パースエラーがあってもパースできた文からなる解釈木とエラーの一覧を返す関数
+    /// エディタなどで不完全な入力から最善の解釈木を得たいときに使う。
+    /// パースできた文が一つもなくエラーがあるときだけNoneを返す。
+    pub fn parse_program_lossy(&mut self) -> (Option<Program>, Vec<String>) {
+        let program = self.parse_statements();
+        let errors = self.get_errors();
+        if program.statements.is_empty() && !errors.is_empty() {
+            return (None, errors);
+        }
+        return (Some(program), errors);
+    }
+
+    /// 次の文を一つだけパースする関数
+    /// 解釈木全体を作らずに文ごとに処理したいときに使う。
+    /// 入力の終わりかパースに失敗したときはNoneを返すので、どちらなのかは`get_errors`で判定する。
+    pub fn parse_next_statement(&mut self) -> Option<Statement> {
+        self.skip_trailing_semicolons();
+        if self.current_token.token_type_is(TokenType::EOF) {
+            return None;
+        }
+        if self.current_token.token_type_is(TokenType::ILLEGAL) {
+            self.make_illegal_error();
+            return None;
+        }
+        let stmt = self.parse_statement();
+        if stmt.is_none() {
+            self.make_parse_statement_error();
+            return None;
+        }
+        self.next_token();
+        return stmt;
+    }
+
+    /// 入力の終わりまで文をパースする関数
+    /// パースに失敗した文は読み飛ばしてエラーに記録する。
+    fn parse_statements(&mut self) -> Program {
         let mut program = Program::new();
 
-        loop {
+        'statements: loop {
+            self.skip_trailing_semicolons();
             // 終了処理
             // 正常終了
             if self.current_token.token_type_is(TokenType::EOF) {
@@ -146,34 +486,49 @@ impl Parser {
             }
 
             // パース処理
-            let stmt_opt = self.parse_statement();
-            // 異常終了(後日式にも対応したら変更する必要がある)
-            if stmt_opt.is_none() {
-                self.make_parse_statement_error();
-                while !self.current_token_is(TokenType::SEMICOLON) {
-                    self.next_token();
-                    if self.current_token_is(TokenType::EOF)
-                        || self.current_token_is(TokenType::ILLEGAL)
-                    {
-                        self.make_illegal_error();
-                        break;
+            let start = self.current_token.get_position();
+            let stmt = match self.parse_statement() {
+                Some(stmt) => stmt,
+                // 異常終了(後日式にも対応したら変更する必要がある)
+                None => {
+                    self.make_parse_statement_error();
+                    // 失敗した文の末尾のセミコロンまで読み飛ばす
+                    while !self.current_token_is(TokenType::SEMICOLON) {
+                        // 入力の終わりではそれ以上読み進められないので、回復を試みずに終了する
+                        if self.current_token_is(TokenType::EOF) {
+                            break 'statements;
+                        }
+                        self.next_token();
+                        if self.current_token_is(TokenType::EOF)
+                            || self.current_token_is(TokenType::ILLEGAL)
+                        {
+                            self.make_illegal_error();
+                            break;
+                        }
                     }
+                    self.next_token();
+                    continue;
                 }
-                self.next_token();
-                continue;
-            }
-            let stmt = stmt_opt.unwrap();
-            program.statements.push(stmt);
+            };
+            // 文は末尾のセミコロンまで読み込んで終わる
+            let end = self.current_token.get_position();
+            program.push_statement(stmt, Span { start, end });
             self.next_token();
         }
-        if self.errors.len() != 0 {
-            return None;
-        }
-        return Some(program);
+        return program;
     }
 
     /// 文用のパーサー
+    /// 入れ子になった文は必ずこの関数を再帰的に呼ぶので、ここで文の入れ子の深さを数える。
     pub fn parse_statement(&mut self) -> Option<Statement> {
+        self.statement_depth += 1;
+        let stmt = self.parse_statement_inner();
+        self.statement_depth -= 1;
+        return stmt;
+    }
+
+    /// 入れ子の深さを数えずに文をパースする関数
+    fn parse_statement_inner(&mut self) -> Option<Statement> {
         match &self.current_token {
             tok if tok.token_type_is(TokenType::LET) => {
                 return self.parse_let_statement();
@@ -181,6 +536,23 @@ impl Parser {
             tok if tok.token_type_is(TokenType::RETURN) => {
                 return self.parse_return_statement();
             }
+            tok if tok.token_type_is(TokenType::DO) => {
+                return self.parse_do_while_statement();
+            }
+            tok if tok.token_type_is(TokenType::FOR) => {
+                return self.parse_for_statement();
+            }
+            tok if tok.token_type_is(TokenType::IDENT) && self.peek_token_is(TokenType::ASSIGN) => {
+                return self.parse_assign_statement();
+            }
+            tok if tok.token_type_is(TokenType::BREAK)
+                || tok.token_type_is(TokenType::CONTINUE) =>
+            {
+                return self.parse_loop_control_statement();
+            }
+            tok if tok.token_type_is(TokenType::IMPORT) => {
+                return self.parse_import_statement();
+            }
             _ => {
                 return self.parse_expression_statement();
             }
@@ -193,10 +565,11 @@ impl Parser {
             self.make_current_expect_error(TokenType::LET);
             return None;
         }
-        if !self.peek_token_is(TokenType::IDENT) {
+        if !self.peek_token_is(TokenType::IDENT) && !self.peek_token_is(TokenType::LBRACKET) {
             self.make_peek_expect_error(TokenType::IDENT);
             return None;
         }
+        let doc = self.current_doc.clone();
         // let
         let let_ident = match self.parse_identifier() {
             Some(i) => Some(i),
@@ -206,11 +579,15 @@ impl Parser {
             }
         }?;
         self.next_token();
-        let ident = match self.parse_identifier() {
-            Some(i) => Some(i),
-            None => {
-                self.make_parse_identifier_error();
-                None
+        let ident = if self.current_token_is(TokenType::LBRACKET) {
+            self.parse_array_pattern()
+        } else {
+            match self.parse_identifier() {
+                Some(i) => Some(i),
+                None => {
+                    self.make_parse_identifier_error();
+                    None
+                }
             }
         }?;
         if !self.peek_token_is(TokenType::ASSIGN) {
@@ -221,27 +598,89 @@ impl Parser {
         self.next_token();
         self.next_token();
 
-        let value = match self.parse_expression(Opt::LOWEST) {
+        let mut value = match self.parse_expression(Opt::LOWEST) {
             Some(e) => Some(e),
             None => {
                 self.make_parse_expression_error();
                 None
             }
         }?;
+        // 関数を束縛したら束縛先の名前を関数に覚えさせる
+        if let (
+            Expression::Identifier {
+                token: _,
+                value: ident,
+            },
+            Expression::FunctionLiteral { name, .. },
+        ) = (&ident, &mut value)
+        {
+            *name = Some(ident.to_string());
+        }
 
-        if !self.peek_token_is(TokenType::SEMICOLON) {
-            self.make_peek_expect_error(TokenType::SEMICOLON);
+        if !self.expect_statement_end() {
             return None;
         }
-        self.next_token();
         let let_statement = Statement::LetStatement {
             token: let_ident.get_token(),
             name: Box::new(ident),
             value: Box::new(value),
+            doc,
         };
         return Some(let_statement);
     }
 
+    /// let文の左辺の配列を分解するパターンをパースする関数
+    /// `...`を付けた残りの要素の名前は最後にだけ書ける。
+    fn parse_array_pattern(&mut self) -> Option<Expression> {
+        if !self.current_token_is(TokenType::LBRACKET) {
+            self.make_current_expect_error(TokenType::LBRACKET);
+            return None;
+        }
+        let tok = self.current_token.clone();
+        let mut names = vec![];
+        let mut rest = None;
+        while !self.peek_token_is(TokenType::RBRACKET) {
+            self.next_token();
+            let is_rest = self.current_token_is(TokenType::ELLIPSIS);
+            if is_rest {
+                self.next_token();
+            }
+            if !self.current_token_is(TokenType::IDENT) {
+                self.make_current_expect_error(TokenType::IDENT);
+                return None;
+            }
+            let name = match self.parse_identifier() {
+                Some(i) => Some(i),
+                None => {
+                    self.make_parse_identifier_error();
+                    None
+                }
+            }?;
+            if is_rest {
+                rest = Some(Box::new(name));
+                // 残りの要素の名前の後には閉じ括弧しか書けない
+                if !self.peek_token_is(TokenType::RBRACKET) {
+                    self.make_peek_expect_error(TokenType::RBRACKET);
+                    return None;
+                }
+                break;
+            }
+            names.push(Box::new(name));
+            if self.peek_token_is(TokenType::COMMA) {
+                self.next_token();
+            } else if !self.peek_token_is(TokenType::RBRACKET) {
+                self.make_peek_expect_error(TokenType::RBRACKET);
+                return None;
+            }
+        }
+        self.next_token();
+        return Some(Expression::ArrayPattern {
+            token: tok,
+            names,
+            rest,
+        });
+    }
+
     /// return文をパースするためパーサー
     fn parse_return_statement(&mut self) -> Option<Statement> {
         if !self.current_token_is(TokenType::RETURN) {
@@ -257,7 +696,6 @@ impl Parser {
                 None
             }
         }?;
-        ;
         self.next_token();
 
         let expression = match self.parse_expression(Opt::LOWEST) {
@@ -266,17 +704,286 @@ impl Parser {
                 self.make_parse_expression_error();
                 None
             }
-        }?;
-        if self.peek_token_is(TokenType::SEMICOLON) {
-            // return式のパース成功
-            self.next_token();
-            return Some(Statement::ReturnStatement {
-                token: return_ident.get_token(),
-                return_value: Box::new(expression),
-            });
+        }?;
+        if !self.expect_statement_end() {
+            return None;
+        }
+        // return式のパース成功
+        return Some(Statement::ReturnStatement {
+            token: return_ident.get_token(),
+            return_value: Box::new(expression),
+        });
+    }
+
+    /// do-while文をパースするためのパーサー
+    /// do <block> while (<condition>);
+    fn parse_do_while_statement(&mut self) -> Option<Statement> {
+        if !self.current_token_is(TokenType::DO) {
+            self.make_current_expect_error(TokenType::DO);
+            return None;
+        }
+        let tok = self.current_token.clone();
+        if !self.peek_token_is(TokenType::LBRACE) {
+            self.make_peek_expect_error(TokenType::LBRACE);
+            return None;
+        }
+        self.next_token();
+        let body = self.parse_block_statement()?;
+        if !self.peek_token_is(TokenType::WHILE) {
+            self.make_peek_expect_error(TokenType::WHILE);
+            return None;
+        }
+        self.next_token();
+        if !self.peek_token_is(TokenType::LPAREN) {
+            self.make_peek_expect_error(TokenType::LPAREN);
+            return None;
+        }
+        self.next_token();
+        self.next_token();
+        let condition = match self.parse_expression(Opt::LOWEST) {
+            Some(e) => Some(e),
+            None => {
+                self.make_parse_expression_error();
+                None
+            }
+        }?;
+        if !self.peek_token_is(TokenType::RPAREN) {
+            self.make_peek_expect_error(TokenType::RPAREN);
+            return None;
+        }
+        self.next_token();
+        if !self.expect_statement_end() {
+            return None;
+        }
+        return Some(Statement::DoWhileStatement {
+            token: tok,
+            body: Box::new(body),
+            condition: Box::new(condition),
+        });
+    }
+
+    /// 代入文をパースするためのパーサー
+    fn parse_assign_statement(&mut self) -> Option<Statement> {
+        let statement = self.parse_assignment()?;
+        if !self.expect_statement_end() {
+            return None;
+        }
+        return Some(statement);
+    }
+
+    /// セミコロンを除いた`<name> = <value>`の部分をパースする関数
+    /// 終了時は代入する値の式の最後のトークンを読み込んでいる。
+    fn parse_assignment(&mut self) -> Option<Statement> {
+        let name = match self.parse_identifier() {
+            Some(i) => Some(i),
+            None => {
+                self.make_parse_identifier_error();
+                None
+            }
+        }?;
+        if !self.peek_token_is(TokenType::ASSIGN) {
+            self.make_peek_expect_error(TokenType::ASSIGN);
+            return None;
+        }
+        self.next_token();
+        let tok = self.current_token.clone();
+        self.next_token();
+        let value = match self.parse_expression(Opt::LOWEST) {
+            Some(e) => Some(e),
+            None => {
+                self.make_parse_expression_error();
+                None
+            }
+        }?;
+        return Some(Statement::AssignStatement {
+            token: tok,
+            name: Box::new(name),
+            value: Box::new(value),
+        });
+    }
+
+    /// for文をパースするためのパーサー
+    /// for (<init>; <condition>; <update>) <block>
+    /// 3つの節はどれも省略できる。初期化はlet文か代入文か式文、更新は代入文か式で書く。
+    /// 括弧の中が`<variable> in <iterable>`なら要素を順に取り出すfor文としてパースする。
+    fn parse_for_statement(&mut self) -> Option<Statement> {
+        if !self.current_token_is(TokenType::FOR) {
+            self.make_current_expect_error(TokenType::FOR);
+            return None;
+        }
+        let tok = self.current_token.clone();
+        if !self.peek_token_is(TokenType::LPAREN) {
+            self.make_peek_expect_error(TokenType::LPAREN);
+            return None;
+        }
+        self.next_token();
+        self.next_token();
+        if self.current_token_is(TokenType::IDENT) && self.peek_token_is(TokenType::IN) {
+            return self.parse_for_in_statement(tok);
+        }
+        // 初期化の文はセミコロンまで読む
+        let init = if self.current_token_is(TokenType::SEMICOLON) {
+            None
+        } else if self.current_token_is(TokenType::LET) {
+            Some(Box::new(self.parse_let_statement()?))
+        } else if self.current_token_is(TokenType::IDENT) && self.peek_token_is(TokenType::ASSIGN) {
+            Some(Box::new(self.parse_assign_statement()?))
+        } else {
+            Some(Box::new(self.parse_expression_statement()?))
+        };
+        self.next_token();
+        let condition = if self.current_token_is(TokenType::SEMICOLON) {
+            None
+        } else {
+            let condition = match self.parse_expression(Opt::LOWEST) {
+                Some(e) => Some(e),
+                None => {
+                    self.make_parse_expression_error();
+                    None
+                }
+            }?;
+            if !self.peek_token_is(TokenType::SEMICOLON) {
+                self.make_peek_expect_error(TokenType::SEMICOLON);
+                return None;
+            }
+            self.next_token();
+            Some(Box::new(condition))
+        };
+        self.next_token();
+        let update = if self.current_token_is(TokenType::RPAREN) {
+            None
+        } else {
+            let update = if self.current_token_is(TokenType::IDENT)
+                && self.peek_token_is(TokenType::ASSIGN)
+            {
+                self.parse_assignment()?
+            } else {
+                let c_tok = self.current_token.clone();
+                let expression = match self.parse_expression(Opt::LOWEST) {
+                    Some(e) => Some(e),
+                    None => {
+                        self.make_parse_expression_error();
+                        None
+                    }
+                }?;
+                Statement::ExpressionStatement {
+                    token: c_tok,
+                    expression: Box::new(expression),
+                }
+            };
+            if !self.peek_token_is(TokenType::RPAREN) {
+                self.make_peek_expect_error(TokenType::RPAREN);
+                return None;
+            }
+            self.next_token();
+            Some(Box::new(update))
+        };
+        if !self.peek_token_is(TokenType::LBRACE) {
+            self.make_peek_expect_error(TokenType::LBRACE);
+            return None;
+        }
+        self.next_token();
+        let body = self.parse_block_statement()?;
+        // 本体の後のセミコロンは省略できる
+        if self.peek_token_is(TokenType::SEMICOLON) {
+            self.next_token();
+        }
+        return Some(Statement::ForStatement {
+            token: tok,
+            init,
+            condition,
+            update,
+            body: Box::new(body),
+        });
+    }
+
+    /// for文の括弧の中が`<variable> in <iterable>`の形のときの残りをパースする関数
+    /// 開始時は要素を束縛する識別子を読み込んでいる。
+    fn parse_for_in_statement(&mut self, tok: Token) -> Option<Statement> {
+        let variable = match self.parse_identifier() {
+            Some(i) => Some(i),
+            None => {
+                self.make_parse_identifier_error();
+                None
+            }
+        }?;
+        self.next_token(); // skip IDENT
+        self.next_token(); // skip IN
+        let iterable = match self.parse_expression(Opt::LOWEST) {
+            Some(e) => Some(e),
+            None => {
+                self.make_parse_expression_error();
+                None
+            }
+        }?;
+        if !self.peek_token_is(TokenType::RPAREN) {
+            self.make_peek_expect_error(TokenType::RPAREN);
+            return None;
+        }
+        self.next_token();
+        if !self.peek_token_is(TokenType::LBRACE) {
+            self.make_peek_expect_error(TokenType::LBRACE);
+            return None;
+        }
+        self.next_token();
+        let body = self.parse_block_statement()?;
+        // 本体の後のセミコロンは省略できる
+        if self.peek_token_is(TokenType::SEMICOLON) {
+            self.next_token();
+        }
+        return Some(Statement::ForInStatement {
+            token: tok,
+            variable: Box::new(variable),
+            iterable: Box::new(iterable),
+            body: Box::new(body),
+        });
+    }
+
+    /// break文とcontinue文をパースするためのパーサー
+    /// ループの外に書いたかどうかは評価するときに判定する。
+    fn parse_loop_control_statement(&mut self) -> Option<Statement> {
+        let tok = self.current_token.clone();
+        if !self.expect_statement_end() {
+            return None;
+        }
+        if tok.token_type_is(TokenType::BREAK) {
+            return Some(Statement::BreakStatement { token: tok });
+        }
+        return Some(Statement::ContinueStatement { token: tok });
+    }
+
+    /// import文をパースするためのパーサー
+    /// パスは文字列リテラルで書く。ファイルを読み込むのは評価するときに行う。
+    fn parse_import_statement(&mut self) -> Option<Statement> {
+        let tok = self.current_token.clone();
+        if !self.peek_token_is(TokenType::STRING) {
+            self.make_peek_expect_error(TokenType::STRING);
+            return None;
+        }
+        self.next_token();
+        let path = self.current_token.get_literal();
+        if !self.expect_statement_end() {
+            return None;
+        }
+        return Some(Statement::ImportStatement { token: tok, path });
+    }
+
+    /// 文の終わりのセミコロンを読み込む関数。成功ならtrue
+    /// スクリプトモードのプログラム直下の文は、次のトークンが改行の後にあるか入力の終わりならセミコロンを省略できる。
+    /// その場合は次の行が式の続きとして読めない文だけが区切られる。
+    fn expect_statement_end(&mut self) -> bool {
+        if self.peek_token_is(TokenType::SEMICOLON) {
+            self.next_token();
+            return true;
+        }
+        if self.options.script && self.statement_depth == 1 {
+            let next_line = self.peek_token.get_position().0 > self.current_token.get_position().0;
+            if next_line || self.peek_token_is(TokenType::EOF) {
+                return true;
+            }
         }
         self.make_peek_expect_error(TokenType::SEMICOLON);
-        return None;
+        return false;
     }
 
     /// 式文をパースするためのパーサー
@@ -289,11 +996,9 @@ impl Parser {
                 None
             }
         }?;
-        if !self.peek_token_is(TokenType::SEMICOLON) {
-            self.make_peek_expect_error(TokenType::SEMICOLON);
+        if !self.expect_statement_end() {
             return None;
         }
-        self.next_token();
         return Some(Statement::ExpressionStatement {
             token: c_tok,
             expression: Box::new(expression),
@@ -301,15 +1006,39 @@ impl Parser {
     }
 
     /// 式をパースする関数
+    /// 入れ子になった式は必ずこの関数を再帰的に呼ぶので、ここで入れ子の深さを数える。
     fn parse_expression(&mut self, precedence: Opt) -> Option<Expression> {
+        if self.depth >= self.options.max_nesting_depth {
+            self.make_nesting_depth_error();
+            return None;
+        }
+        self.depth += 1;
+        let exp = self.parse_expression_inner(precedence);
+        self.depth -= 1;
+        return exp;
+    }
+
+    /// 入れ子の深さを数えずに式をパースする関数
+    fn parse_expression_inner(&mut self, precedence: Opt) -> Option<Expression> {
+        // 式を読む前に入力が終わった
+        if self.current_token_is(TokenType::EOF) {
+            self.make_empty_expression_error();
+            return None;
+        }
         let mut left = match self.current_token.get_token_type() {
             TokenType::IF => self.parse_if_expression(),
+            TokenType::SWITCH => self.parse_switch_expression(),
             TokenType::FUNCTION => self.parse_function_literal(),
             TokenType::IDENT => self.parse_identifier(),
             TokenType::INT => self.parse_integer_literal(),
+            TokenType::FLOAT => self.parse_float_literal(),
             TokenType::TRUE | TokenType::FALSE => self.parse_boolean_literal(),
-            TokenType::BANG | TokenType::MINUS => self.parse_prefix_expression(),
+            TokenType::STRING => self.parse_string_literal(),
+            TokenType::NULL => self.parse_null_literal(),
+            TokenType::BANG | TokenType::MINUS | TokenType::PLUS => self.parse_prefix_expression(),
             TokenType::LPAREN => self.parse_grouped_expression(),
+            TokenType::LBRACKET => self.parse_array_literal(),
+            TokenType::LBRACE => self.parse_hash_literal(),
             _ => {
                 self.make_unknown_token_error();
                 None
@@ -326,13 +1055,19 @@ impl Parser {
             }
 
             if self.peek_token_is_infix() {
-                if self.peek_token_is(TokenType::LPAREN)
-                    && (left.get_token().token_type_is(TokenType::FUNCTION)
-                        || left.get_token().token_type_is(TokenType::IDENT))
-                {
+                if self.peek_token_is(TokenType::LPAREN) {
                     self.next_token();
-                    // 関数呼び出しの時
+                    // 関数呼び出しの時。呼び出せるかどうかは評価時に判定する
                     left = self.parse_call_expression(left)?;
+                } else if self.peek_token_is(TokenType::LBRACKET) {
+                    self.next_token();
+                    left = self.parse_index_expression(left)?;
+                } else if self.peek_token_is(TokenType::DOT) {
+                    self.next_token();
+                    left = self.parse_member_expression(left)?;
+                } else if self.peek_token_is(TokenType::RANGE) {
+                    self.next_token();
+                    left = self.parse_range_literal(left)?;
                 } else {
                     self.next_token();
                     left = self.parse_infix_expression(left)?;
@@ -358,7 +1093,7 @@ impl Parser {
 
     /// 整数リテラルのパーサー
     fn parse_integer_literal(&mut self) -> Option<Expression> {
-        let lit = match self.current_token.get_literal().parse::<i64>().ok() {
+        let lit = match self.current_token.literal().parse::<i64>().ok() {
             Some(i) => Some(i),
             None => {
                 self.make_parse_integer_literal_error();
@@ -371,9 +1106,22 @@ impl Parser {
         });
     }
 
+    /// 浮動小数点数リテラルのパーサー
+    fn parse_float_literal(&mut self) -> Option<Expression> {
+        let literal = self.current_token.get_literal();
+        if literal.parse::<f64>().is_err() {
+            self.make_parse_float_literal_error();
+            return None;
+        }
+        return Some(Expression::FloatLiteral {
+            token: self.current_token.clone(),
+            value: literal,
+        });
+    }
+
     /// 真理値リテラルのパーサー
     fn parse_boolean_literal(&mut self) -> Option<Expression> {
-        let lit = match self.current_token.get_literal().parse::<bool>().ok() {
+        let lit = match self.current_token.literal().parse::<bool>().ok() {
             Some(b) => Some(b),
             None => {
                 self.make_parse_boolean_literal_error();
@@ -386,6 +1134,29 @@ impl Parser {
         });
     }
 
+    /// 文字列リテラルのパーサー
+    fn parse_string_literal(&mut self) -> Option<Expression> {
+        if !self.current_token_is(TokenType::STRING) {
+            self.make_current_expect_error(TokenType::STRING);
+            return None;
+        }
+        return Some(Expression::StringLiteral {
+            token: self.current_token.clone(),
+            value: self.current_token.get_literal(),
+        });
+    }
+
+    /// nullリテラルのパーサー
+    fn parse_null_literal(&mut self) -> Option<Expression> {
+        if !self.current_token_is(TokenType::NULL) {
+            self.make_current_expect_error(TokenType::NULL);
+            return None;
+        }
+        return Some(Expression::NullLiteral {
+            token: self.current_token.clone(),
+        });
+    }
+
     /// 関数リテラルのパーサー
     fn parse_function_literal(&mut self) -> Option<Expression> {
         // ここに来るときはFUNCTIONトークン型を読み込んでいる
@@ -401,12 +1172,20 @@ impl Parser {
         self.next_token();
 
         let mut parameters = vec![];
-        // パラメーター用に開始位置を調整
+        // '('の次のトークンから引数を読み始める
         self.next_token();
         if !self.parse_function_parameters(&mut parameters) {
             self.make_parse_parameters_error();
             return None;
         };
+        // 同じ関数の引数名の重複は呼び出し時に黙って上書きされるのでエラーにする
+        for (i, parameter) in parameters.iter().enumerate() {
+            let name = parameter.get_value();
+            if parameters[..i].iter().any(|p| p.get_value() == name) {
+                self.make_duplicate_parameter_error(&name);
+                return None;
+            }
+        }
         if !self.peek_token_is(TokenType::LBRACE) {
             self.make_peek_expect_error(TokenType::LBRACE);
             return None;
@@ -424,20 +1203,52 @@ impl Parser {
             token: tok,
             parameters,
             body,
+            name: None,
         });
     }
 
     /// 関数リテラルの引数部分のパーサー。成功時にtrueを返す。
+    /// 呼び出し時のcurrentは'('の次のトークン(引数がなければ')')で、成功時のcurrentは')'になる。
+    /// `fn(x,)`のような末尾のカンマは許さない。
+    /// 最後の引数だけは`...rest`と書いて残りの引数を配列として受け取れる。
+    #[allow(clippy::vec_box)]
     fn parse_function_parameters(&mut self, parameters: &mut Vec<Box<Expression>>) -> bool {
         if self.current_token_is(TokenType::RPAREN) {
             return true;
         }
         loop {
-            let ident_opt = self.parse_identifier();
-            if ident_opt.is_none() {
+            if self.current_token_is(TokenType::ELLIPSIS) {
+                let tok = self.current_token.clone();
+                self.next_token();
+                if !self.current_token_is(TokenType::IDENT) {
+                    self.make_current_expect_error(TokenType::IDENT);
+                    return false;
+                }
+                let rest = match self.parse_identifier() {
+                    Some(i) => i,
+                    None => return false,
+                };
+                parameters.push(Box::new(Expression::SpreadExpression {
+                    token: tok,
+                    value: Box::new(rest),
+                }));
+                // 残りの引数の後には閉じ括弧しか書けない
+                if !self.peek_token_is(TokenType::RPAREN) {
+                    self.make_peek_expect_error(TokenType::RPAREN);
+                    return false;
+                }
+                self.next_token();
+                return true;
+            }
+            // 引数名にはIDENT以外のトークンを使えない
+            if !self.current_token_is(TokenType::IDENT) {
+                self.make_current_expect_error(TokenType::IDENT);
                 return false;
             }
-            parameters.push(Box::new(ident_opt.unwrap()));
+            match self.parse_identifier() {
+                Some(ident) => parameters.push(Box::new(ident)),
+                None => return false,
+            }
             if self.peek_token_is(TokenType::COMMA) {
                 self.next_token();
                 self.next_token();
@@ -472,32 +1283,180 @@ impl Parser {
         })
     }
 
+    /// 添字式をパースする関数。呼び出し時のcurrentは'['で、成功時のcurrentは']'になる。
+    fn parse_index_expression(&mut self, left: Expression) -> Option<Expression> {
+        if !self.current_token_is(TokenType::LBRACKET) {
+            self.make_current_expect_error(TokenType::LBRACKET);
+            return None;
+        }
+        let tok = self.current_token.clone();
+        self.next_token();
+        let index = match self.parse_expression(Opt::LOWEST) {
+            Some(e) => Some(e),
+            None => {
+                self.make_parse_expression_error();
+                None
+            }
+        }?;
+        if !self.peek_token_is(TokenType::RBRACKET) {
+            self.make_peek_expect_error(TokenType::RBRACKET);
+            return None;
+        }
+        self.next_token();
+        return Some(Expression::IndexExpression {
+            token: tok,
+            left: Box::new(left),
+            index: Box::new(index),
+        });
+    }
+
+    /// メンバー参照式をパースする関数。呼び出し時のcurrentは'.'で、成功時のcurrentはメンバー名になる。
+    fn parse_member_expression(&mut self, object: Expression) -> Option<Expression> {
+        if !self.current_token_is(TokenType::DOT) {
+            self.make_current_expect_error(TokenType::DOT);
+            return None;
+        }
+        let tok = self.current_token.clone();
+        if !self.peek_token_is(TokenType::IDENT) {
+            self.make_peek_expect_error(TokenType::IDENT);
+            return None;
+        }
+        self.next_token();
+        let property = self.parse_identifier()?;
+        return Some(Expression::MemberExpression {
+            token: tok,
+            object: Box::new(object),
+            property: Box::new(property),
+        });
+    }
+
+    /// 範囲リテラルをパースする関数。呼び出し時のcurrentは'..'で、成功時のcurrentは終わりの式の最後になる。
+    fn parse_range_literal(&mut self, start: Expression) -> Option<Expression> {
+        if !self.current_token_is(TokenType::RANGE) {
+            self.make_current_expect_error(TokenType::RANGE);
+            return None;
+        }
+        let tok = self.current_token.clone();
+        let precedence = self.current_infix_precedence();
+        self.next_token();
+        let end = match self.parse_expression(precedence) {
+            Some(e) => Some(e),
+            None => {
+                self.make_parse_expression_error();
+                None
+            }
+        }?;
+        return Some(Expression::RangeLiteral {
+            token: tok,
+            start: Box::new(start),
+            end: Box::new(end),
+        });
+    }
+
     /// 関数呼び出しの引数をパースする関数
     /// 成功ならtrue
+    #[allow(clippy::vec_box)]
     fn parse_call_arguments(&mut self, arguments: &mut Vec<Box<Expression>>) -> bool {
-        if self.current_token_is(TokenType::RPAREN) {
-            return true;
+        return self.parse_expression_list(arguments, TokenType::RPAREN, true);
+    }
+
+    /// 配列リテラルのパーサー
+    fn parse_array_literal(&mut self) -> Option<Expression> {
+        if !self.current_token_is(TokenType::LBRACKET) {
+            self.make_current_expect_error(TokenType::LBRACKET);
+            return None;
+        }
+        let tok = self.current_token.clone();
+        self.next_token();
+        let mut elements = vec![];
+        if !self.parse_expression_list(&mut elements, TokenType::RBRACKET, false) {
+            self.make_parse_array_elements_error();
+            return None;
         }
+        Some(Expression::ArrayLiteral {
+            token: tok,
+            elements,
+        })
+    }
 
-        loop {
-            let arg_opt = match self.parse_expression(Opt::LOWEST) {
+    /// ハッシュリテラルをパースする関数
+    fn parse_hash_literal(&mut self) -> Option<Expression> {
+        if !self.current_token_is(TokenType::LBRACE) {
+            self.make_current_expect_error(TokenType::LBRACE);
+            return None;
+        }
+        let tok = self.current_token.clone();
+        let mut pairs = vec![];
+        while !self.peek_token_is(TokenType::RBRACE) {
+            self.next_token();
+            let key = match self.parse_expression(Opt::LOWEST) {
                 Some(e) => Some(e),
                 None => {
-                    self.make_parse_expression_error();
+                    self.make_parse_hash_pairs_error();
+                    None
+                }
+            }?;
+            if !self.peek_token_is(TokenType::COLON) {
+                self.make_peek_expect_error(TokenType::COLON);
+                return None;
+            }
+            self.next_token();
+            self.next_token();
+            let value = match self.parse_expression(Opt::LOWEST) {
+                Some(e) => Some(e),
+                None => {
+                    self.make_parse_hash_pairs_error();
                     None
                 }
+            }?;
+            pairs.push((Box::new(key), Box::new(value)));
+            if !self.peek_token_is(TokenType::RBRACE) && !self.peek_token_is(TokenType::COMMA) {
+                self.make_peek_expect_error(TokenType::RBRACE);
+                return None;
+            }
+            if self.peek_token_is(TokenType::COMMA) {
+                self.next_token();
+            }
+        }
+        self.next_token();
+        Some(Expression::HashLiteral { token: tok, pairs })
+    }
+
+    /// カンマ区切りの式をendのトークンまでパースする関数
+    /// 開始時は最初の式かendのトークンを読み込んでいて、終了時はendのトークンを読み込んでいる。
+    /// allow_spreadがtrueなら`...arr`のように配列を展開する要素も読む。
+    /// 成功ならtrue
+    #[allow(clippy::vec_box)]
+    fn parse_expression_list(
+        &mut self,
+        list: &mut Vec<Box<Expression>>,
+        end: TokenType,
+        allow_spread: bool,
+    ) -> bool {
+        if self.current_token_is(end.clone()) {
+            return true;
+        }
+
+        loop {
+            let element = if allow_spread && self.current_token_is(TokenType::ELLIPSIS) {
+                self.parse_spread_expression()
+            } else {
+                self.parse_expression(Opt::LOWEST)
             };
-            if arg_opt.is_none() {
-                return false;
+            match element {
+                Some(e) => list.push(Box::new(e)),
+                None => {
+                    self.make_parse_expression_error();
+                    return false;
+                }
             }
-            arguments.push(Box::new(arg_opt.unwrap()));
             if self.peek_token_is(TokenType::COMMA) {
                 self.next_token();
                 self.next_token();
                 continue;
             }
 
-            if self.peek_token_is(TokenType::RPAREN) {
+            if self.peek_token_is(end.clone()) {
                 self.next_token();
                 return true;
             }
@@ -506,6 +1465,21 @@ impl Parser {
         }
     }
 
+    /// `...`に続く式を展開する式をパースする関数。呼び出し時のcurrentは`...`になる。
+    fn parse_spread_expression(&mut self) -> Option<Expression> {
+        if !self.current_token_is(TokenType::ELLIPSIS) {
+            self.make_current_expect_error(TokenType::ELLIPSIS);
+            return None;
+        }
+        let tok = self.current_token.clone();
+        self.next_token();
+        let value = self.parse_expression(Opt::LOWEST)?;
+        return Some(Expression::SpreadExpression {
+            token: tok,
+            value: Box::new(value),
+        });
+    }
+
     /// 前置演算子付きの式をパースする関数
     fn parse_prefix_expression(&mut self) -> Option<Expression> {
         // ここに来るということは前置演算子を持つ式だと確定してるはず
@@ -523,7 +1497,7 @@ impl Parser {
             }
         }?;
         let expression = Expression::PrefixExpression {
-            operator: tok.get_literal().clone(),
+            operator: tok.literal().to_string(),
             token: tok,
             right_exp: Box::new(exp),
         };
@@ -563,12 +1537,16 @@ impl Parser {
         }
         // ここに入ってきたときにはIFトークンを読み込んでいる状態なので読み進める
         let tok = self.current_token.clone();
-        if !self.peek_token_is(TokenType::LPAREN) {
+        let strict = self.options.strict_if_parentheses;
+        if strict && !self.peek_token_is(TokenType::LPAREN) {
             self.make_peek_expect_error(TokenType::LPAREN);
             return None;
         }
         self.next_token(); // skip IF
-        self.next_token(); // skip LPAREN
+        if strict {
+            self.next_token(); // skip LPAREN
+        }
+        // 括弧が必須でなければ括弧で囲んだ条件はグループ化された式として読む
         let condition = match self.parse_expression(Opt::LOWEST) {
             Some(e) => Some(e),
             None => {
@@ -576,11 +1554,13 @@ impl Parser {
                 None
             }
         }?;
-        if !self.peek_token_is(TokenType::RPAREN) {
+        if strict && !self.peek_token_is(TokenType::RPAREN) {
             self.make_peek_expect_error(TokenType::RPAREN);
             return None;
         } else {
-            self.next_token();
+            if strict {
+                self.next_token();
+            }
             if !self.peek_token_is(TokenType::LBRACE) {
                 self.make_peek_expect_error(TokenType::LBRACE);
                 return None;
@@ -602,11 +1582,89 @@ impl Parser {
                 token: tok,
                 condition: Box::new(condition),
                 consequence: Box::new(consequence),
-                alternative: Box::new(alt),
+                alternative: alt.map(Box::new),
             });
         }
     }
 
+    /// switch式をパースする関数
+    /// 分岐は`<pattern> => <value>`をカンマで区切って並べ、`_`の分岐は最後にだけ書ける。
+    fn parse_switch_expression(&mut self) -> Option<Expression> {
+        if !self.current_token_is(TokenType::SWITCH) {
+            self.make_current_expect_error(TokenType::SWITCH);
+            return None;
+        }
+        let tok = self.current_token.clone();
+        self.next_token(); // skip SWITCH
+
+        // 括弧で囲んだ値はグループ化された式として読む
+        let subject = match self.parse_expression(Opt::LOWEST) {
+            Some(e) => Some(e),
+            None => {
+                self.make_parse_expression_error();
+                None
+            }
+        }?;
+        if !self.peek_token_is(TokenType::LBRACE) {
+            self.make_peek_expect_error(TokenType::LBRACE);
+            return None;
+        }
+        self.next_token();
+        let mut arms = vec![];
+        let mut default = None;
+        while !self.peek_token_is(TokenType::RBRACE) {
+            self.next_token();
+            if default.is_some() {
+                self.make_switch_default_not_last_error();
+                return None;
+            }
+            let is_default =
+                self.current_token_is(TokenType::IDENT) && self.current_token.literal() == "_";
+            let pattern = if is_default {
+                None
+            } else {
+                match self.parse_expression(Opt::LOWEST) {
+                    Some(e) => Some(e),
+                    None => {
+                        self.make_parse_switch_arm_error();
+                        return None;
+                    }
+                }
+            };
+            if !self.peek_token_is(TokenType::ARROW) {
+                self.make_peek_expect_error(TokenType::ARROW);
+                return None;
+            }
+            self.next_token();
+            self.next_token();
+            let value = match self.parse_expression(Opt::LOWEST) {
+                Some(e) => Some(e),
+                None => {
+                    self.make_parse_switch_arm_error();
+                    None
+                }
+            }?;
+            match pattern {
+                Some(pattern) => arms.push((Box::new(pattern), Box::new(value))),
+                None => default = Some(Box::new(value)),
+            }
+            if !self.peek_token_is(TokenType::RBRACE) && !self.peek_token_is(TokenType::COMMA) {
+                self.make_peek_expect_error(TokenType::RBRACE);
+                return None;
+            }
+            if self.peek_token_is(TokenType::COMMA) {
+                self.next_token();
+            }
+        }
+        self.next_token();
+        return Some(Expression::SwitchExpression {
+            token: tok,
+            subject: Box::new(subject),
+            arms,
+            default,
+        });
+    }
+
     /// 波括弧に囲まれた部分をパースする
     fn parse_block_statement(&mut self) -> Option<Statement> {
         // ここに来るときは左波括弧のトークンを読み込んだ時
@@ -616,6 +1674,7 @@ impl Parser {
         }
         let brace_tok = self.current_token.clone();
         self.next_token();
+        self.skip_trailing_semicolons();
         let mut statements = vec![];
         if self.current_token_is(TokenType::RBRACE) {
             return Some(Statement::BlockStatement {
@@ -632,18 +1691,17 @@ impl Parser {
                 }
             }?;
             statements.push(Box::new(stmt));
-            if self.peek_token_is(TokenType::RBRACE) {
-                self.next_token();
+            // 文の終わりのセミコロンから次の文の始まりか右波括弧へ進める
+            self.next_token();
+            self.skip_trailing_semicolons();
+            if self.current_token_is(TokenType::RBRACE) {
                 break;
             }
 
-            if self.peek_token_is(TokenType::SEMICOLON) {
-                self.next_token();
-                self.next_token();
-                continue;
+            if self.current_token_is(TokenType::EOF) || self.current_token_is(TokenType::ILLEGAL) {
+                self.make_unterminated_block_error();
+                return None;
             }
-            self.make_parse_block_statement_error();
-            return None;
         }
         return Some(Statement::BlockStatement {
             token: brace_tok,
@@ -675,138 +1733,199 @@ impl Parser {
             self.current_token, self.peek_token
         );
     }
-    /// パースエラーを返す関数
+    /// パースエラーを説明文とトークン情報をつなげた文字列にして返す関数
     pub fn get_errors(&self) -> Vec<String> {
-        return self.errors.clone();
+        return self
+            .errors
+            .iter()
+            .zip(self.error_tokens.iter())
+            .map(|(error, tokens)| format!("{}{}", error, tokens))
+            .collect();
+    }
+
+    /// パースエラーを種類の分かる形で返す関数
+    pub fn errors(&self) -> &[ParseError] {
+        return &self.errors;
+    }
+
+    /// 現在のトークン情報と一緒にエラーを記録する関数
+    fn push_error(&mut self, error: ParseError) {
+        let tokens = self.get_tokens_str();
+        self.errors.push(error);
+        self.error_tokens.push(tokens);
+    }
+
+    /// 現在読み込んでいるトークンの(行, 列)
+    fn current_position(&self) -> (usize, usize) {
+        return self.current_token.get_position();
     }
-    ///  異常なトークンを検出した場合のエラー
+
+    /// 異常なトークンを検出した場合のエラー
     fn make_illegal_error(&mut self) {
-        let msg = format!(
-            "異常なトークンを検出しました。{}",
-            self.get_tokens_str()
-        );
-        self.errors.push(msg);
+        self.push_error(ParseError::IllegalToken {
+            position: self.current_position(),
+        });
     }
 
     /// 文のパースに失敗した場合のエラー
     fn make_parse_statement_error(&mut self) {
-        let msg = format!(
-            "文をパースできませんでした。{}",
-            self.get_tokens_str()
-        );
-        self.errors.push(msg);
+        self.push_error(ParseError::InvalidStatement {
+            position: self.current_position(),
+        });
     }
 
     /// 式のパースに失敗した場合のエラー
     fn make_parse_expression_error(&mut self) {
-        let msg = format!(
-            "式をパースできませんでした。{}",
-            self.get_tokens_str()
-        );
-        self.errors.push(msg);
+        self.push_error(ParseError::InvalidExpression {
+            position: self.current_position(),
+        });
     }
 
     /// 識別子のパースに失敗した場合のエラー
     fn make_parse_identifier_error(&mut self) {
-        let msg = format!(
-            "識別子リテラルをパースできませんでした。{}",
-            self.get_tokens_str()
-        );
-        self.errors.push(msg);
+        self.push_error(ParseError::InvalidIdentifier {
+            position: self.current_position(),
+        });
     }
 
     /// 整数リテラルのパースに失敗した場合のエラー
     fn make_parse_integer_literal_error(&mut self) {
-        let msg = format!(
-            "整数をパースできませんでした。{}",
-            self.get_tokens_str()
-        );
-        self.errors.push(msg);
+        self.push_error(ParseError::InvalidInteger {
+            position: self.current_position(),
+        });
+    }
+
+    /// 浮動小数点数リテラルのパースに失敗した場合のエラー
+    fn make_parse_float_literal_error(&mut self) {
+        self.push_error(ParseError::InvalidFloat {
+            position: self.current_position(),
+        });
     }
 
     /// 真理値リテラルのパースに失敗した場合のエラー
     fn make_parse_boolean_literal_error(&mut self) {
-        let msg = format!(
-            "真理値をパースできませんでした。{}",
-            self.get_tokens_str()
-        );
-        self.errors.push(msg);
+        self.push_error(ParseError::InvalidBoolean {
+            position: self.current_position(),
+        });
+    }
+
+    /// 関数パラメーター用のパースエラー
+    fn make_parse_parameters_error(&mut self) {
+        self.push_error(ParseError::InvalidParameters {
+            position: self.current_position(),
+        });
+    }
+
+    /// 関数の引数名が重複している場合のエラー
+    fn make_duplicate_parameter_error(&mut self, name: &str) {
+        self.push_error(ParseError::DuplicateParameter {
+            name: name.to_string(),
+            position: self.current_position(),
+        });
+    }
+
+    /// 前置演算子パーサー用のエラー
+    fn make_parse_prefix_expression(&mut self) {
+        self.push_error(ParseError::InvalidPrefixExpression {
+            position: self.current_position(),
+        });
+    }
+
+    /// 中置演算子パーサー用のエラー
+    fn make_parse_infix_expression(&mut self) {
+        self.push_error(ParseError::InvalidInfixExpression {
+            position: self.current_position(),
+        });
+    }
+
+    /// ブロック文のパースに失敗した場合のエラー
+    fn make_parse_block_statement_error(&mut self) {
+        self.push_error(ParseError::InvalidBlock {
+            position: self.current_position(),
+        });
+    }
+
+    /// ブロックが閉じられる前に入力が終わった場合のエラー
+    fn make_unterminated_block_error(&mut self) {
+        self.push_error(ParseError::UnterminatedBlock {
+            position: self.current_position(),
+        });
+    }
+
+    /// 関数を呼び出すときの引数のパースエラー
+    fn make_parse_call_arguments_error(&mut self) {
+        self.push_error(ParseError::InvalidCallArguments {
+            position: self.current_position(),
+        });
     }
 
-    /// 関数パラメーター用のパースエラー
-    fn make_parse_parameters_error(&mut self) {
-        let msg = format!(
-            "関数の引数をパースできませんでした。{}",
-            self.get_tokens_str()
-        );
-        self.errors.push(msg);
+    /// 配列リテラルの要素のパースエラー
+    fn make_parse_array_elements_error(&mut self) {
+        self.push_error(ParseError::InvalidArrayElements {
+            position: self.current_position(),
+        });
     }
 
-    ///  前置演算子パーサー用のエラー
-    fn make_parse_prefix_expression(&mut self) {
-        let msg = format!(
-            "前置演算子をパースできませんでした。{}",
-            self.get_tokens_str()
-        );
-        self.errors.push(msg);
+    /// 式があるべき位置で入力が終わった場合のエラー
+    fn make_empty_expression_error(&mut self) {
+        self.push_error(ParseError::EmptyExpression {
+            position: self.current_position(),
+        });
     }
 
-    ///  中置演算子パーサー用のエラー
-    fn make_parse_infix_expression(&mut self) {
-        let msg = format!(
-            "中置演算子をパースできませんでした。{}",
-            self.get_tokens_str()
-        );
-        self.errors.push(msg);
+    /// ハッシュリテラルのキーと値の組のパースエラー
+    fn make_parse_hash_pairs_error(&mut self) {
+        self.push_error(ParseError::InvalidHashPairs {
+            position: self.current_position(),
+        });
     }
 
-    /// ブロック文のパースに失敗した場合のエラー
-    fn make_parse_block_statement_error(&mut self) {
-        let msg = format!(
-            "ブロックをパースできませんでした。{}",
-            self.get_tokens_str()
-        );
-        self.errors.push(msg);
+    /// switch式の分岐のパースエラー
+    fn make_parse_switch_arm_error(&mut self) {
+        self.push_error(ParseError::InvalidSwitchArm {
+            position: self.current_position(),
+        });
     }
 
-    /// 関数を呼び出すときの引数のパースエラー
-    fn make_parse_call_arguments_error(&mut self) {
-        let msg = format!(
-            "引数をパースできませんでした。{}",
-            self.get_tokens_str()
-        );
-        self.errors.push(msg);
+    /// switch式で`_`の分岐の後に分岐が続く場合のエラー
+    fn make_switch_default_not_last_error(&mut self) {
+        self.push_error(ParseError::SwitchDefaultNotLast {
+            position: self.current_position(),
+        });
+    }
+
+    /// 式の入れ子が深すぎる場合のエラー
+    fn make_nesting_depth_error(&mut self) {
+        self.push_error(ParseError::NestingTooDeep {
+            max_depth: self.options.max_nesting_depth,
+            position: self.current_position(),
+        });
     }
 
     /// 分岐の時に予期せぬトークンを取得したときのエラー
     fn make_unknown_token_error(&mut self) {
-        let msg = format!(
-            "予期せぬトークンを読み込みました。読み取ったトークンが不正です。{}",
-            self.get_tokens_str()
-        );
-        self.errors.push(msg);
+        self.push_error(ParseError::UnknownToken {
+            found: self.current_token.get_token_type(),
+            position: self.current_position(),
+        });
     }
 
     /// 先読み時に発生したエラー用をフォーマットを使って生成して追加する。
     fn make_current_expect_error(&mut self, expect_type: TokenType) {
-        let msg = format!(
-            "トークン型{:?}を期待して読みましたが、実際に読み込んだトークン型は{:?}でした。{}",
-            expect_type,
-            self.current_token.get_token_type(),
-            self.get_tokens_str()
-        );
-        self.errors.push(msg);
+        self.push_error(ParseError::UnexpectedToken {
+            expected: expect_type,
+            found: self.current_token.get_token_type(),
+            position: self.current_position(),
+        });
     }
 
     /// 先読み時に発生したエラー用をフォーマットを使って生成して追加する。
     fn make_peek_expect_error(&mut self, expect_type: TokenType) {
-        let msg = format!(
-            "トークン型{:?}を期待して先のトークンを読みましたが、実際に読み込んだトークン型は{:?}でした。{}",
-            expect_type,
-            self.peek_token.get_token_type(),
-            self.get_tokens_str()
-        );
-        self.errors.push(msg);
+        self.push_error(ParseError::UnexpectedPeekToken {
+            expected: expect_type,
+            found: self.peek_token.get_token_type(),
+            position: self.peek_token.get_position(),
+        });
     }
 }
 
@@ -821,19 +1940,141 @@ mod test {
     /// パースエラーがあれば出力する関数
     fn check_parser_errors(parser: &Parser) {
         let errors = parser.get_errors();
-        if errors.len() == 0 {
+        if errors.is_empty() {
             return;
         }
-        eprintln!(
-            "\n\nパースエラーが{}件発生しました。",
-            errors.len()
-        );
+        eprintln!("\n\nパースエラーが{}件発生しました。", errors.len());
         for error in errors {
             eprintln!("{}", error);
         }
         eprintln!();
     }
 
+    /// エラーがあってもパースできた文を返すテスト
+    #[test]
+    fn test_parse_program_lossy() {
+        let input = "let x = 5; let = 10; let y = x;";
+
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let (program_opt, errors) = parser.parse_program_lossy();
+
+        assert!(!errors.is_empty(), "エラーが記録されていません。");
+        let program = program_opt.expect("パースできた文が返されていません。");
+        assert_eq!(program.to_string(), "let x = 5;let y = x;");
+
+        // 厳密なパースではエラーがあるとNoneを返す
+        let mut parser = Parser::new(Lexer::new(input));
+        assert!(parser.parse_program().is_none());
+
+        // パースできた文がなければNone
+        let mut parser = Parser::new(Lexer::new("let = 10;"));
+        let (program_opt, errors) = parser.parse_program_lossy();
+        assert!(program_opt.is_none());
+        assert!(!errors.is_empty());
+    }
+
+    /// エラーを種類ごとに判別できるかのテスト
+    #[test]
+    fn test_parse_error_kinds() {
+        // 識別子の代わりに`=`を先読みした
+        let mut parser = Parser::new(Lexer::new("let = 10;"));
+        assert!(parser.parse_program().is_none());
+        match &parser.errors()[0] {
+            ParseError::UnexpectedPeekToken {
+                expected,
+                found,
+                position,
+            } => {
+                assert_eq!(*expected, TokenType::IDENT);
+                assert_eq!(*found, TokenType::ASSIGN);
+                assert_eq!(*position, (1, 5));
+            }
+            error => panic!("想定外のエラーです。{:?}", error),
+        }
+        assert_eq!(
+            parser.errors().last(),
+            Some(&ParseError::InvalidStatement { position: (1, 1) })
+        );
+
+        // 閉じられていないブロック
+        let mut parser = Parser::new(Lexer::new("fn(x) { x;"));
+        assert!(parser.parse_program().is_none());
+        assert!(parser
+            .errors()
+            .iter()
+            .any(|e| matches!(e, ParseError::UnterminatedBlock { .. })));
+
+        // 重複した引数名
+        let mut parser = Parser::new(Lexer::new("fn(a, a) { a; };"));
+        assert!(parser.parse_program().is_none());
+        assert!(parser.errors().contains(&ParseError::DuplicateParameter {
+            name: "a".to_string(),
+            position: (1, 8),
+        }));
+
+        // 文字列の形は説明文の後ろにトークン情報を付けたもの
+        let strings = parser.get_errors();
+        assert_eq!(strings.len(), parser.errors().len());
+        for (string, error) in strings.iter().zip(parser.errors()) {
+            assert!(string.starts_with(&error.to_string()));
+            assert!(string.contains("\n\tcurrent: "));
+        }
+        assert!(strings[0].starts_with("関数の引数名aが重複しています。"));
+    }
+
+    /// 空の入力をパースするテスト
+    #[test]
+    fn test_empty_input() {
+        for input in ["", "  \n\t "].iter() {
+            let mut parser = Parser::new(Lexer::new(input));
+            let program = parser
+                .parse_program()
+                .expect("空のプログラムになりません。");
+            assert!(program.statements.is_empty());
+        }
+
+        // 入力の終わりで式をパースすると空の式のエラーになる
+        let mut parser = Parser::new(Lexer::new(""));
+        assert!(parser.parse_expression(Opt::LOWEST).is_none());
+        let errors = parser.get_errors();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("式を期待しましたが入力が終わりました。"));
+
+        // 式の途中で入力が終わったときも同じエラーになる
+        let mut parser = Parser::new(Lexer::new("let x = "));
+        assert!(parser.parse_program().is_none());
+        assert!(parser
+            .get_errors()
+            .iter()
+            .any(|e| e.starts_with("式を期待しましたが入力が終わりました。")));
+    }
+
+    /// 入力の終わりの直前で文のパースに失敗しても止まるかのテスト
+    #[test]
+    fn test_recover_at_eof() {
+        let inputs = [
+            "let",
+            "let x",
+            "return",
+            "x = 1; let",
+            "fn(",
+            "if (x",
+            "switch (x) {",
+            "for (",
+            "import",
+            "break",
+            "[1, 2",
+            "{\"a\": ",
+            "do {} while",
+        ];
+        for input in inputs.iter() {
+            let mut parser = Parser::new(Lexer::new(input));
+            assert!(parser.parse_program().is_none(), "{}", input);
+            assert!(!parser.get_errors().is_empty(), "{}", input);
+        }
+    }
+
     /// return 文の構文解析用のテスト
     #[test]
     fn test_return_statements() {
@@ -850,20 +2091,12 @@ mod test {
         check_parser_errors(&parser);
 
         if program_opt.is_none() {
-            assert!(
-                false,
-                "return文のパースに失敗しました。{}",
-                input
-            );
+            panic!("return文のパースに失敗しました。{}", input);
         }
         let program = program_opt.unwrap();
         let statements = &program.statements;
         if statements.len() != 3 {
-            assert!(
-                false,
-                "return文の個数が不適切です。{:?}",
-                statements
-            );
+            panic!("return文の個数が不適切です。{:?}", statements);
         }
 
         let tests = ["1", "(x + y)", "z"];
@@ -884,9 +2117,189 @@ mod test {
                 assert_eq!(return_value.to_string(), expect.to_string());
             }
             _ => {
-                assert!(false, "return文ではありません。{:?}", stmt);
+                panic!("return文ではありません。{:?}", stmt);
+            }
+        }
+    }
+
+    #[test]
+    fn test_do_while_statement() {
+        let tests = [
+            // (input, expect)
+            ("do { x; } while (x < 3);", "do{x;}while (x < 3);"),
+            ("do {} while (false);", "do{}while false;"),
+            (
+                "do { let i = i + 1; f(i); } while (i);",
+                "do{let i = (i + 1);f(i);}while i;",
+            ),
+            (
+                "do { break; continue; } while (true);",
+                "do{break;continue;}while true;",
+            ),
+        ];
+        for (input, expect) in tests.iter() {
+            let mut parser = Parser::new(Lexer::new(input));
+            let program_opt = parser.parse_program();
+            check_parser_errors(&parser);
+            let program = program_opt.expect("fail parse program.");
+            assert_eq!(program.statements.len(), 1);
+            assert_eq!(program.to_string(), expect.to_string());
+            if let Statement::DoWhileStatement {
+                token,
+                body,
+                condition: _,
+            } = &program.statements[0]
+            {
+                assert!(token.token_type_is(TokenType::DO));
+                assert!(matches!(**body, Statement::BlockStatement { .. }));
+            } else {
+                panic!("do-while文ではありません。{}", input);
+            }
+        }
+
+        for input in [
+            "do x; while (x);",
+            "do { x; } (x);",
+            "do { x; } while x;",
+            "do { x; } while (x)",
+            "while (x) { x; };",
+            "do { break } while (x);",
+            "do { continue 1; } while (x);",
+        ]
+        .iter()
+        {
+            let mut parser = Parser::new(Lexer::new(input));
+            assert!(parser.parse_program().is_none(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_for_statement() {
+        let tests = [
+            // (input, expect)
+            (
+                "for (let i = 0; i < 10; i = i + 1) { f(i); }",
+                "for (let i = 0; (i < 10); i = (i + 1)){f(i);}",
+            ),
+            (
+                "for (i = 0; i < 3; f(i)) {};",
+                "for (i = 0; (i < 3); f(i)){}",
+            ),
+            ("for (;;) { break; }", "for (; ;){break;}"),
+            ("for (f(); ; ) {}", "for (f(); ;){}"),
+        ];
+        for (input, expect) in tests.iter() {
+            let mut parser = Parser::new(Lexer::new(input));
+            let program_opt = parser.parse_program();
+            check_parser_errors(&parser);
+            let program = program_opt.expect("fail parse program.");
+            assert_eq!(program.statements.len(), 1, "{}", input);
+            assert_eq!(program.to_string(), expect.to_string());
+            if let Statement::ForStatement {
+                token,
+                init: _,
+                condition: _,
+                update: _,
+                body,
+            } = &program.statements[0]
+            {
+                assert!(token.token_type_is(TokenType::FOR));
+                assert!(matches!(**body, Statement::BlockStatement { .. }));
+            } else {
+                panic!("for文ではありません。{}", input);
             }
         }
+
+        for input in [
+            "for let i = 0; i < 3; i = i + 1 {}",
+            "for (let i = 0; i < 3) {}",
+            "for (let i = 0 i < 3; i = i + 1) {}",
+            "for (let i = 0; i < 3; i = i + 1;) {}",
+            "for (let i = 0; i < 3; i = i + 1) i;",
+        ]
+        .iter()
+        {
+            let mut parser = Parser::new(Lexer::new(input));
+            assert!(parser.parse_program().is_none(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_for_in_statement() {
+        let mut parser = Parser::new(Lexer::new("for (x in [1, 2] + a) { f(x); }"));
+        let program = parser.parse_program().expect("fail parse program.");
+        assert_eq!(program.to_string(), "for (x in ([1, 2] + a)){f(x);}");
+        if let Statement::ForInStatement {
+            token,
+            variable,
+            iterable: _,
+            body: _,
+        } = &program.statements[0]
+        {
+            assert!(token.token_type_is(TokenType::FOR));
+            assert_eq!(variable.to_string(), "x");
+        } else {
+            panic!("for文ではありません。{:?}", program.statements[0]);
+        }
+
+        for input in [
+            "for (x in) {}",
+            "for (1 in xs) {}",
+            "for (x in xs {}",
+            "for (x in xs) x;",
+        ]
+        .iter()
+        {
+            let mut parser = Parser::new(Lexer::new(input));
+            assert!(parser.parse_program().is_none(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_assign_statement() {
+        let mut parser = Parser::new(Lexer::new("x = 1 + 2; x == 3; f = fn() { x = 1; };"));
+        let program = parser.parse_program().expect("fail parse program.");
+        assert_eq!(
+            program.to_string(),
+            "x = (1 + 2);(x == 3);f = fn(){x = 1;};"
+        );
+        assert!(matches!(
+            program.statements[0],
+            Statement::AssignStatement { .. }
+        ));
+
+        for input in ["x = ;", "x = 1", "1 = x;"].iter() {
+            let mut parser = Parser::new(Lexer::new(input));
+            assert!(parser.parse_program().is_none(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_import_statement() {
+        let mut parser = Parser::new(Lexer::new("import \"lib/math.mk\"; import \"a\\\"b\";"));
+        let program = parser.parse_program().expect("fail parse program.");
+        assert_eq!(
+            program.to_string(),
+            "import \"lib/math.mk\";import \"a\\\"b\";"
+        );
+        if let Statement::ImportStatement { token, path } = &program.statements[0] {
+            assert!(token.token_type_is(TokenType::IMPORT));
+            assert_eq!(path, "lib/math.mk");
+        } else {
+            panic!("import文ではありません。{:?}", program.statements[0]);
+        }
+
+        for input in [
+            "import math;",
+            "import \"math\"",
+            "import;",
+            "import (\"math\");",
+        ]
+        .iter()
+        {
+            let mut parser = Parser::new(Lexer::new(input));
+            assert!(parser.parse_program().is_none(), "{}", input);
+        }
     }
 
     /// let文の構文解析用のテスト
@@ -905,16 +2318,12 @@ mod test {
         check_parser_errors(&parser);
 
         if program_opt.is_none() {
-            assert!(false, "let文のパースに失敗しました。{}", input);
+            panic!("let文のパースに失敗しました。{}", input);
         }
         let program = program_opt.unwrap();
         let statements = &program.statements;
         if statements.len() != 3 {
-            assert!(
-                false,
-                "let文の個数が不適切です。{:?}",
-                statements
-            );
+            panic!("let文の個数が不適切です。{:?}", statements);
         }
 
         let tests = [("x", "5"), ("y", "(a + b)"), ("foobar", "c")];
@@ -928,7 +2337,12 @@ mod test {
     // 束縛される値は後でやるとして、束縛時の変数名をテストする関数
     fn test_let_statement(stmt: &Statement, name_expect: &str, value_expect: &str) {
         match stmt {
-            Statement::LetStatement { token, name, value } => {
+            Statement::LetStatement {
+                token,
+                name,
+                value,
+                doc: _,
+            } => {
                 // トークンのletで始まってるか確認
                 assert_eq!(token.get_literal(), "let");
                 // 束縛変数名の確認
@@ -936,9 +2350,244 @@ mod test {
                 assert_eq!(value.to_string(), value_expect);
             }
             _ => {
-                assert!(false, "let文ではありません。");
+                panic!("let文ではありません。");
+            }
+        }
+    }
+
+    /// 設定によってパースの厳しさが変わるテスト
+    #[test]
+    fn test_parser_options() {
+        let input = "let x = 1;; if x { x; };; if (x) { 2;; };";
+
+        // 既定の設定では余分なセミコロンも括弧のないif式も許さない
+        let mut parser = Parser::new(Lexer::new(input));
+        assert!(parser.parse_program().is_none());
+
+        let options = ParserOptions {
+            allow_trailing_semicolons: true,
+            strict_if_parentheses: false,
+            ..ParserOptions::new()
+        };
+        let mut parser = Parser::with_options(Lexer::new(input), options);
+        let program = parser.parse_program().expect("fail parse program.");
+        check_parser_errors(&parser);
+        assert_eq!(program.to_string(), "let x = 1;if x{x;};if x{2;};");
+
+        // 片方だけ緩めても括弧のないif式はエラーになる
+        let options = ParserOptions {
+            allow_trailing_semicolons: true,
+            strict_if_parentheses: true,
+            max_nesting_depth: 256,
+            script: false,
+        };
+        let mut parser = Parser::with_options(Lexer::new(input), options);
+        assert!(parser.parse_program().is_none());
+    }
+
+    /// スクリプトモードでプログラム直下の文のセミコロンを省略するテスト
+    #[test]
+    fn test_script_mode() {
+        let script = ParserOptions {
+            script: true,
+            ..ParserOptions::new()
+        };
+        let input = "let x = 1
+let add = fn(a, b) { a + b; }
+x = add(x,
+  2)
+if (x > 2) { x; } else { 0; }
+for (i in 0..x) { f(i); }
+x * 10";
+        let mut parser = Parser::with_options(Lexer::new(input), script.clone());
+        let program = parser.parse_program();
+        check_parser_errors(&parser);
+        assert_eq!(
+            program.expect("fail parse program.").to_string(),
+            "let x = 1;let add = fn(a, b){(a + b);};x = add(x, 2);if (x > 2){x;} else{0;};\
+             for (i in (0..x)){f(i);}(x * 10);"
+        );
+
+        // 厳しいモードでは同じ入力はパースできない
+        let mut parser = Parser::new(Lexer::new(input));
+        assert!(parser.parse_program().is_none());
+
+        for input in [
+            // 同じ行の文はセミコロンで区切る
+            "let x = 1 let y = 2",
+            // ブロックの中の文は省略できない
+            "let f = fn() {\n  1\n  2\n}",
+            "for (i in xs) {\n  f(i)\n}",
+        ]
+        .iter()
+        {
+            let mut parser = Parser::with_options(Lexer::new(input), script.clone());
+            assert!(parser.parse_program().is_none(), "{}", input);
+        }
+
+        // 次の行が式の続きとして読めるときは一つの文になる
+        let mut parser = Parser::with_options(Lexer::new("let x = 1\n- 2"), script);
+        let program = parser.parse_program().expect("fail parse program.");
+        assert_eq!(program.to_string(), "let x = (1 - 2);");
+    }
+
+    /// 深すぎる入れ子でスタックを溢れさせずにエラーにするテスト
+    #[test]
+    fn test_max_nesting_depth() {
+        let n = 100_000;
+        let input = format!("{}1{};", "(".repeat(n), ")".repeat(n));
+        let mut parser = Parser::new(Lexer::new(&input));
+        assert!(parser.parse_program().is_none());
+        assert!(parser
+            .get_errors()
+            .iter()
+            .any(|e| e.starts_with("式の入れ子の深さが上限の256を超えました。")));
+
+        let input = format!("{}1;", "-".repeat(n));
+        let mut parser = Parser::new(Lexer::new(&input));
+        assert!(parser.parse_program().is_none());
+
+        // 上限以内の入れ子はパースできる
+        let options = ParserOptions {
+            max_nesting_depth: 10,
+            ..ParserOptions::new()
+        };
+        let input = "((((1))));";
+        let mut parser = Parser::with_options(Lexer::new(input), options.clone());
+        assert!(parser.parse_program().is_some());
+        let input = "((((((((((1))))))))));";
+        let mut parser = Parser::with_options(Lexer::new(input), options);
+        assert!(parser.parse_program().is_none());
+    }
+
+    /// 文を一つずつパースするテスト
+    #[test]
+    fn test_parse_next_statement() {
+        let mut parser = Parser::new(Lexer::new("let x = 1; x + 2; let = 3; x;"));
+        let first = parser
+            .parse_next_statement()
+            .expect("fail parse statement.");
+        assert_eq!(first.to_string(), "let x = 1;");
+        let second = parser
+            .parse_next_statement()
+            .expect("fail parse statement.");
+        assert_eq!(second.to_string(), "(x + 2);");
+        assert!(parser.parse_next_statement().is_none());
+        assert!(!parser.get_errors().is_empty());
+
+        let mut parser = Parser::new(Lexer::new("1;"));
+        assert!(parser.parse_next_statement().is_some());
+        assert!(parser.parse_next_statement().is_none());
+        assert!(parser.get_errors().is_empty());
+    }
+
+    /// 配列を分解するlet文のテスト
+    #[test]
+    fn test_let_array_pattern() {
+        let tests = [
+            // (input, expect, names, has_rest)
+            ("let [a, b] = [1, 2];", "let [a, b] = [1, 2];", 2, false),
+            (
+                "let [head, ...tail] = arr;",
+                "let [head, ...tail] = arr;",
+                1,
+                true,
+            ),
+            ("let [...all] = arr;", "let [...all] = arr;", 0, true),
+            ("let [] = arr;", "let [] = arr;", 0, false),
+        ];
+        for (input, expect, length, has_rest) in tests.iter() {
+            let mut parser = Parser::new(Lexer::new(input));
+            let program = parser.parse_program();
+            check_parser_errors(&parser);
+            let program = program.expect("fail parse program.");
+            assert_eq!(program.to_string(), expect.to_string());
+            if let Statement::LetStatement {
+                token: _,
+                name,
+                value: _,
+                doc: _,
+            } = &program.statements[0]
+            {
+                if let Expression::ArrayPattern {
+                    token: _,
+                    names,
+                    rest,
+                } = &**name
+                {
+                    assert_eq!(names.len(), *length);
+                    assert_eq!(rest.is_some(), *has_rest);
+                } else {
+                    panic!("配列のパターンではありません。{}", input);
+                }
+            } else {
+                panic!("let文ではありません。{}", input);
+            }
+        }
+
+        for input in [
+            "let [a, ...b, c] = x;",
+            "let [1] = x;",
+            "let [a b] = x;",
+            "let [...] = x;",
+        ]
+        .iter()
+        {
+            let mut parser = Parser::new(Lexer::new(input));
+            assert!(parser.parse_program().is_none(), "{}", input);
+        }
+    }
+
+    /// let文の直前のコメントを付けるテスト
+    #[test]
+    fn test_doc_comment() {
+        let input = "
+            // doc
+            let x = 5;
+            let y = x; // 行末のコメントは付けない
+            // 複数行の
+            // コメント
+            let f = fn() { 1; };
+            // 式文のコメントは捨てる
+            x;
+            let z = 1;
+        ";
+        let mut lexer = Lexer::new(input);
+        lexer.set_doc_mode(true);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program().expect("fail parse program.");
+        check_parser_errors(&parser);
+
+        let expects = [Some("doc"), None, Some("複数行の\nコメント"), None, None];
+        assert_eq!(program.statements.len(), expects.len());
+        for (stmt, expect) in program.statements.iter().zip(expects.iter()) {
+            match stmt {
+                Statement::LetStatement {
+                    token: _,
+                    name: _,
+                    value: _,
+                    doc,
+                } => assert_eq!(doc.as_deref(), *expect),
+                Statement::ExpressionStatement {
+                    token: _,
+                    expression: _,
+                } => assert!(expect.is_none()),
+                _ => panic!("想定外の文です。{:?}", stmt),
             }
         }
+
+        // ドキュメントモードでなければコメントは読み飛ばされる
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = parser.parse_program().expect("fail parse program.");
+        if let Statement::LetStatement {
+            token: _,
+            name: _,
+            value: _,
+            doc,
+        } = &program.statements[0]
+        {
+            assert_eq!(doc, &None);
+        }
     }
 
     /// 識別子をパースするテスト
@@ -955,17 +2604,12 @@ mod test {
         let program_opt = parser.parse_program();
         check_parser_errors(&parser);
         if program_opt.is_none() {
-            assert!(
-                false,
-                "プログラムのパースに失敗しました。{}",
-                input
-            );
+            panic!("プログラムのパースに失敗しました。{}", input);
         }
         let program = program_opt.unwrap();
 
         if program.statements.len() != 1 {
-            assert!(
-                false,
+            panic!(
                 "適切な個数の識別子をパースすることができませんでした。{:?}",
                 program.statements
             );
@@ -983,23 +2627,15 @@ mod test {
                 ref value,
             } = **expression
             {
-                if &token.get_literal() != res {
-                    assert!(
-                        false,
-                        "入力から\"{}\"識別子を得ることができませんでした",
-                        res
-                    );
+                if token.get_literal() != res {
+                    panic!("入力から\"{}\"識別子を得ることができませんでした", res);
                 }
                 if value != res {
-                    assert!(
-                        false,
-                        "トークンのリテラルが\"{}\"でありませんでした。",
-                        res
-                    );
+                    panic!("トークンのリテラルが\"{}\"でありませんでした。", res);
                 }
             }
         } else {
-            assert!(false, "入力が式文ではありません{}", input);
+            panic!("入力が式文ではありません{}", input);
         }
     }
 
@@ -1013,17 +2649,12 @@ mod test {
         let program_opt = parser.parse_program();
         check_parser_errors(&parser);
         if program_opt.is_none() {
-            assert!(
-                false,
-                "プログラムのパースに失敗しました。{}",
-                input
-            );
+            panic!("プログラムのパースに失敗しました。{}", input);
         }
         let program = program_opt.unwrap();
 
         if program.statements.len() != 1 {
-            assert!(
-                false,
+            panic!(
                 "適切な個数の整数リテラルをパースすることができませんでした。{:?}",
                 program.statements
             );
@@ -1041,7 +2672,33 @@ mod test {
                 assert_eq!(value, 5_i64);
             }
         } else {
-            assert!(false, "入力が式文ではありません{}", input);
+            panic!("入力が式文ではありません{}", input);
+        }
+    }
+
+    /// 浮動小数点数リテラルをパースするテスト
+    #[test]
+    fn test_float_literal_expression() {
+        let tests = [("2.5;", "2.5"), ("1e10;", "1e10"), ("1.5e-3;", "1.5e-3")];
+        for (input, literal) in tests.iter() {
+            let mut parser = Parser::new(Lexer::new(input));
+            let program = parser.parse_program().expect("fail parse program.");
+            check_parser_errors(&parser);
+            if let Statement::ExpressionStatement {
+                token: _,
+                expression,
+            } = &program.statements[0]
+            {
+                if let Expression::FloatLiteral { token, value } = &**expression {
+                    assert!(token.token_type_is(TokenType::FLOAT));
+                    assert_eq!(value, literal);
+                } else {
+                    panic!("浮動小数点数リテラルではありません。{:?}", expression);
+                }
+            } else {
+                panic!("入力が式文ではありません{}", input);
+            }
+            assert_eq!(program.to_string(), *input);
         }
     }
 
@@ -1050,23 +2707,18 @@ mod test {
     fn test_boolean_literal_expression() {
         let tests = [("false;", false), ("true;", true)];
 
-        for (input, res) in tests.to_vec().into_iter() {
+        for &(input, res) in tests.iter() {
             let lexer = Lexer::new(input);
             let mut parser = Parser::new(lexer);
             let program_opt = parser.parse_program();
             check_parser_errors(&parser);
             if program_opt.is_none() {
-                assert!(
-                    false,
-                    "プログラムのパースに失敗しました。{}",
-                    input
-                );
+                panic!("プログラムのパースに失敗しました。{}", input);
             }
             let program = program_opt.unwrap();
 
             if program.statements.len() != 1 {
-                assert!(
-                    false,
+                panic!(
                     "適切な個数のリテラルをパースすることができませんでした。{:?}",
                     program.statements
                 );
@@ -1079,13 +2731,83 @@ mod test {
                 expression,
             } = stmt
             {
-                if let Expression::BooleanLiteral { ref token, value } = **expression {
-                    assert_eq!(token.get_literal(), res.to_string());
-                    assert_eq!(value, res);
-                }
+                if let Expression::BooleanLiteral { ref token, value } = **expression {
+                    assert_eq!(token.get_literal(), res.to_string());
+                    assert_eq!(value, res);
+                }
+            } else {
+                panic!("入力が式文ではありません。{}", input);
+            }
+        }
+    }
+
+    ///  文字列リテラルをパースするテスト
+    #[test]
+    fn test_string_literal_expression() {
+        let input = r#""hello\tworld";"#;
+
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program_opt = parser.parse_program();
+        check_parser_errors(&parser);
+        if program_opt.is_none() {
+            panic!("プログラムのパースに失敗しました。{}", input);
+        }
+        let program = program_opt.unwrap();
+        assert_eq!(program.statements.len(), 1);
+
+        let stmt = &program.statements[0];
+        assert_eq!(stmt.to_string(), input);
+        if let Statement::ExpressionStatement {
+            token: _,
+            expression,
+        } = stmt
+        {
+            if let Expression::StringLiteral {
+                ref token,
+                ref value,
+            } = **expression
+            {
+                assert_eq!(token.get_token_type(), TokenType::STRING);
+                assert_eq!(value, "hello\tworld");
+            } else {
+                panic!("文字列リテラルではありません。{}", input);
+            }
+        } else {
+            panic!("入力が式文ではありません。{}", input);
+        }
+    }
+
+    ///  nullリテラルをパースするテスト
+    #[test]
+    fn test_null_literal_expression() {
+        let input = "null;";
+
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program_opt = parser.parse_program();
+        check_parser_errors(&parser);
+        if program_opt.is_none() {
+            panic!("プログラムのパースに失敗しました。{}", input);
+        }
+        let program = program_opt.unwrap();
+        assert_eq!(program.statements.len(), 1);
+
+        let stmt = &program.statements[0];
+        assert_eq!(stmt.to_string(), input);
+        if let Statement::ExpressionStatement {
+            token: _,
+            expression,
+        } = stmt
+        {
+            if let Expression::NullLiteral { ref token } = **expression {
+                assert_eq!(token.get_token_type(), TokenType::NULL);
+                assert_eq!(token.get_literal(), "null");
             } else {
-                assert!(false, "入力が式文ではありません。{}", input);
+                panic!("nullリテラルではありません。{}", input);
             }
+        } else {
+            panic!("入力が式文ではありません。{}", input);
         }
     }
 
@@ -1096,6 +2818,7 @@ mod test {
             // (input, operator_lit, int_val, expect)
             ("!5;", "!", 5_i64, "(!5);"),
             ("-15;", "-", 15_i64, "(-15);"),
+            ("+15;", "+", 15_i64, "(+15);"),
         ];
 
         for (input, prefix, v, expect) in prefix_tests {
@@ -1104,17 +2827,12 @@ mod test {
             let program_opt = parser.parse_program();
             check_parser_errors(&parser);
             if program_opt.is_none() {
-                assert!(
-                    false,
-                    "プログラムのパースに失敗しました。{}",
-                    input
-                );
+                panic!("プログラムのパースに失敗しました。{}", input);
             }
             let program = program_opt.unwrap();
 
             if program.statements.len() != 1 {
-                assert!(
-                    false,
+                panic!(
                     "適切な個数の整数リテラルをパースすることができませんでした。{:?}",
                     program.statements
                 );
@@ -1135,10 +2853,10 @@ mod test {
                 {
                     assert_eq!(&token.get_literal(), operator);
                     assert_eq!(operator, prefix);
-                    test_integer_literal(v, &**exp);
+                    test_integer_literal(v, exp);
                 }
             } else {
-                assert!(false, "入力が式文ではありません.{}", input);
+                panic!("入力が式文ではありません.{}", input);
             }
         }
     }
@@ -1150,8 +2868,7 @@ mod test {
             assert_eq!(token.get_literal(), format!("{}", v));
             assert_eq!(*value, v);
         } else {
-            assert!(
-                false,
+            panic!(
                 "整数リテラルではありませんでした。{}",
                 exp.get_token().get_literal()
             )
@@ -1179,17 +2896,12 @@ mod test {
             let program_opt = parser.parse_program();
             check_parser_errors(&parser);
             if program_opt.is_none() {
-                assert!(
-                    false,
-                    "プログラムのパースに失敗しました。{}",
-                    input
-                );
+                panic!("プログラムのパースに失敗しました。{}", input);
             }
             let program = program_opt.unwrap();
 
             if program.statements.len() != 1 {
-                assert!(
-                    false,
+                panic!(
                     "適切な個数の整数リテラルをパースすることができませんでした。: {:?}",
                     program.statements
                 );
@@ -1210,11 +2922,11 @@ mod test {
                 {
                     assert_eq!(&token.get_literal(), operator);
                     assert_eq!(operator, infix_op);
-                    test_integer_literal(left_value, &**left_exp);
-                    test_integer_literal(right_value, &**right_exp);
+                    test_integer_literal(left_value, left_exp);
+                    test_integer_literal(right_value, right_exp);
                 }
             } else {
-                assert!(false, "入力が式文ではありません。{}",);
+                panic!("入力が式文ではありません。{}", input);
             }
         }
     }
@@ -1229,16 +2941,11 @@ mod test {
         let program_opt = parser.parse_program();
         check_parser_errors(&parser);
         if program_opt.is_none() {
-            assert!(
-                false,
-                "プログラムのパースに失敗しました。{}",
-                input
-            );
+            panic!("プログラムのパースに失敗しました。{}", input);
         }
         let program = program_opt.unwrap();
         if program.statements.len() != 1 {
-            assert!(
-                false,
+            panic!(
                 "適切な個数の文をパースすることができませんでした。: {:?}",
                 program.statements
             );
@@ -1260,15 +2967,13 @@ mod test {
                 assert_eq!(consequence.to_string(), "{x;}");
                 assert!(alternative.is_none(), "else節が存在しています。");
             } else {
-                assert!(
-                    false,
+                panic!(
                     "パース結果がif文ではありませんでした。{}",
                     expression.get_token().get_literal()
                 );
             }
         } else {
-            assert!(
-                false,
+            panic!(
                 "入力が式文ではありません。{}",
                 program.statements[0].get_token().get_literal()
             );
@@ -1285,16 +2990,11 @@ mod test {
         let program_opt = parser.parse_program();
         check_parser_errors(&parser);
         if program_opt.is_none() {
-            assert!(
-                false,
-                "プログラムのパースに失敗しました。{}",
-                input
-            );
+            panic!("プログラムのパースに失敗しました。{}", input);
         }
         let program = program_opt.unwrap();
         if program.statements.len() != 1 {
-            assert!(
-                false,
+            panic!(
                 "適切な個数の文をパースすることができませんでした。: {:?}",
                 program.statements
             );
@@ -1314,21 +3014,19 @@ mod test {
             {
                 assert_eq!(condition.to_string(), "(x > y)");
                 assert_eq!(consequence.to_string(), "{x;}");
-                if let Some(alt) = &**alternative {
+                if let Some(alt) = alternative {
                     assert_eq!(alt.to_string(), "{y;}")
                 } else {
-                    assert!(false, "else節がうまく読み込めません。");
+                    panic!("else節がうまく読み込めません。");
                 }
             } else {
-                assert!(
-                    false,
+                panic!(
                     "パース結果がif文ではありませんでした。{}",
                     expression.get_token().get_literal()
                 );
             }
         } else {
-            assert!(
-                false,
+            panic!(
                 "入力が式文ではありません。{}",
                 program.statements[0].get_token().get_literal()
             );
@@ -1344,25 +3042,21 @@ mod test {
             ("fn(x){};", "fn(x){};"),
             ("fn(x, y) {};", "fn(x, y){};"),
             ("fn(x, y) {x+y;};", "fn(x, y){(x + y);};"),
+            ("fn(x) {let y = x; y;};", "fn(x){let y = x;y;};"),
         ];
 
-        for (input, expect) in tests.into_iter() {
+        for (input, expect) in tests.iter() {
             let lexer = Lexer::new(input);
             let mut parser = Parser::new(lexer);
             let program_opt = parser.parse_program();
             check_parser_errors(&parser);
 
             if program_opt.is_none() {
-                assert!(
-                    false,
-                    "プログラムをパースできませんでした。{}",
-                    input
-                );
+                panic!("プログラムをパースできませんでした。{}", input);
             }
             let program = program_opt.unwrap();
             if program.statements.len() != 1 {
-                assert!(
-                    false,
+                panic!(
                     "適切な個数の文をパースすることができませんでした。: {:?}",
                     program.statements
                 );
@@ -1377,19 +3071,233 @@ mod test {
                     token,
                     parameters: _,
                     body: _,
+                    name: _,
                 } = &**expression
                 {
                     assert!(token.token_type_is(TokenType::FUNCTION));
                 } else {
-                    assert!(
-                        false,
+                    panic!(
                         "関数リテラルではありませんでした。{}",
                         expression.get_token().get_literal()
                     );
                 }
             } else {
-                assert!(false, "入力が式文ではありません。{}", input);
+                panic!("入力が式文ではありません。{}", input);
+            }
+        }
+    }
+
+    /// 関数の引数をパースした後の位置のテスト
+    #[test]
+    fn test_function_parameters() {
+        let tests = [
+            // (input, parameters)
+            ("fn() { 1; };", vec![]),
+            ("fn(x) { 1; };", vec!["x"]),
+            ("fn(x, y, z) { 1; };", vec!["x", "y", "z"]),
+        ];
+        for (input, expect) in tests.iter() {
+            let mut parser = Parser::new(Lexer::new(input));
+            let program = parser.parse_program();
+            check_parser_errors(&parser);
+            let program = program.expect("fail parse program.");
+            if let Statement::ExpressionStatement {
+                token: _,
+                expression,
+            } = &program.statements[0]
+            {
+                if let Expression::FunctionLiteral {
+                    token: _,
+                    parameters,
+                    body,
+                    name: _,
+                } = &**expression
+                {
+                    let names: Vec<String> = parameters.iter().map(|p| p.to_string()).collect();
+                    assert_eq!(&names, expect);
+                    // 引数の後の')'を読み終えて本体から正しくパースできている
+                    assert_eq!(body.to_string(), "{1;}");
+                } else {
+                    panic!("関数リテラルではありません。{}", input);
+                }
+            } else {
+                panic!("入力が式文ではありません。{}", input);
+            }
+        }
+
+        let invalids = [
+            "fn(x,) {};",
+            "fn(,) {};",
+            "fn(1) {};",
+            "fn(x y) {};",
+            "fn(x, 2) {};",
+            "fn(x",
+        ];
+        for input in invalids.iter() {
+            let mut parser = Parser::new(Lexer::new(input));
+            assert!(parser.parse_program().is_none(), "{}", input);
+        }
+    }
+
+    /// 配列の展開と残りの引数のパーステスト
+    #[test]
+    fn test_spread_and_rest() {
+        let tests = [
+            // (input, expect)
+            ("f(...arr);", "f(...arr);"),
+            ("f(1, ...[2, 3], ...g(x));", "f(1, ...[2, 3], ...g(x));"),
+            ("f(...a + b);", "f(...(a + b));"),
+            ("fn(...rest) {};", "fn(...rest){};"),
+            ("fn(head, ...tail) { tail; };", "fn(head, ...tail){tail;};"),
+        ];
+        for (input, expect) in tests.iter() {
+            let mut parser = Parser::new(Lexer::new(input));
+            let program = parser.parse_program();
+            check_parser_errors(&parser);
+            assert_eq!(
+                program.expect("fail parse program.").to_string(),
+                expect.to_string()
+            );
+        }
+
+        let invalids = [
+            "[...a];",
+            "...a;",
+            "f(...);",
+            "fn(...rest, x) {};",
+            "fn(...1) {};",
+            "fn(x, ...x) {};",
+        ];
+        for input in invalids.iter() {
+            let mut parser = Parser::new(Lexer::new(input));
+            assert!(parser.parse_program().is_none(), "{}", input);
+        }
+    }
+
+    /// 関数の引数名の重複を検出するテスト
+    #[test]
+    fn test_duplicate_function_parameters() {
+        let tests = ["fn(x, x){};", "fn(x, y, x){};"];
+        for input in tests.iter() {
+            let lexer = Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let program_opt = parser.parse_program();
+
+            assert!(
+                program_opt.is_none(),
+                "重複した引数名を検出できませんでした。{}",
+                input
+            );
+            assert!(parser
+                .get_errors()
+                .iter()
+                .any(|e| e.starts_with("関数の引数名xが重複しています。")));
+        }
+
+        // 引数名の重複は関数ごとに判定する
+        let input = "fn(x){ fn(x){}; };";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program_opt = parser.parse_program();
+        check_parser_errors(&parser);
+        assert!(
+            program_opt.is_some(),
+            "プログラムをパースできませんでした。{}",
+            input
+        );
+    }
+
+    /// 添字式のパーステスト
+    #[test]
+    fn test_index_expression() {
+        let tests = [
+            // (input, expect)
+            ("myArray[1 + 1];", "(myArray[(1 + 1)]);"),
+            (
+                "a * [1, 2, 3, 4][b * c] * d;",
+                "((a * ([1, 2, 3, 4][(b * c)])) * d);",
+            ),
+            (
+                "add(a * b[2], b[1], 2 * [1, 2][1]);",
+                "add((a * (b[2])), (b[1]), (2 * ([1, 2][1])));",
+            ),
+            ("\"abc\"[0][0];", "((\"abc\"[0])[0]);"),
+            ("f(x)[0];", "(f(x)[0]);"),
+        ];
+        for (input, expect) in tests.iter() {
+            let mut parser = Parser::new(Lexer::new(input));
+            let program = parser.parse_program();
+            check_parser_errors(&parser);
+            assert_eq!(
+                program.expect("fail parse program.").to_string(),
+                expect.to_string()
+            );
+        }
+
+        for input in ["a[];", "a[1;", "a[1, 2];"].iter() {
+            let mut parser = Parser::new(Lexer::new(input));
+            assert!(parser.parse_program().is_none(), "{}", input);
+        }
+    }
+
+    /// 範囲リテラルのパーステスト
+    #[test]
+    fn test_range_literal() {
+        let mut parser = Parser::new(Lexer::new("for (i in 1..len(xs)) {}"));
+        let program = parser.parse_program().expect("fail parse program.");
+        if let Statement::ForInStatement {
+            token: _,
+            variable: _,
+            iterable,
+            body: _,
+        } = &program.statements[0]
+        {
+            if let Expression::RangeLiteral { token, start, end } = &**iterable {
+                assert!(token.token_type_is(TokenType::RANGE));
+                assert_eq!(start.to_string(), "1");
+                assert_eq!(end.to_string(), "len(xs)");
+            } else {
+                panic!("範囲リテラルではありません。{:?}", iterable);
             }
+        } else {
+            panic!("for文ではありません。{:?}", program.statements[0]);
+        }
+
+        for input in ["1..;", "..2;", "1.. ..2;"].iter() {
+            let mut parser = Parser::new(Lexer::new(input));
+            assert!(parser.parse_program().is_none(), "{}", input);
+        }
+    }
+
+    /// メンバー参照式のパーステスト
+    #[test]
+    fn test_member_expression() {
+        let mut parser = Parser::new(Lexer::new("math.square;"));
+        let program = parser.parse_program().expect("fail parse program.");
+        if let Statement::ExpressionStatement {
+            token: _,
+            expression,
+        } = &program.statements[0]
+        {
+            if let Expression::MemberExpression {
+                token,
+                object,
+                property,
+            } = &**expression
+            {
+                assert!(token.token_type_is(TokenType::DOT));
+                assert_eq!(object.to_string(), "math");
+                assert_eq!(property.to_string(), "square");
+            } else {
+                panic!("メンバー参照式ではありません。{:?}", expression);
+            }
+        } else {
+            panic!("式文ではありません。{:?}", program.statements[0]);
+        }
+
+        for input in ["a.;", "a.1;", "a.\"b\";", "a.b.;", ".a;"].iter() {
+            let mut parser = Parser::new(Lexer::new(input));
+            assert!(parser.parse_program().is_none(), "{}", input);
         }
     }
 
@@ -1411,26 +3319,20 @@ mod test {
             ),
             ("fn(a, b) {a + b;}(3, 4);", "fn(a, b){(a + b);}(3, 4);"),
         ];
-        for (input, expect) in tests.to_vec().into_iter() {
+        for (input, expect) in tests.iter() {
             let lexer = Lexer::new(input);
             let mut parser = Parser::new(lexer);
             let program_opt = parser.parse_program();
             check_parser_errors(&parser);
 
             if program_opt.is_none() {
-                assert!(
-                    false,
-                    "プログラムをパースできませんでした。{}",
-                    input
-                );
+                panic!("プログラムをパースできませんでした。{}", input);
             }
             let program = program_opt.unwrap();
             if program.statements.len() != 1 {
-                assert!(
-                    false,
+                panic!(
                     "適切な個数の文をパースすることができませんでした。: {:?} => {}",
-                    program.statements,
-                    program.to_string()
+                    program.statements, program
                 );
             }
             if let Statement::ExpressionStatement {
@@ -1440,9 +3342,176 @@ mod test {
             {
                 assert_eq!(program.to_string(), expect.to_string());
             } else {
-                assert!(false, "入力が式文ではありません。{}", input);
+                panic!("入力が式文ではありません。{}", input);
+            }
+        }
+    }
+
+    /// 配列リテラルのパーステスト
+    #[test]
+    fn test_array_literal() {
+        let tests = [
+            // (input, expect, length)
+            ("[];", "[];", 0),
+            ("[1];", "[1];", 1),
+            ("[1, 2 * 2, 3 + 3];", "[1, (2 * 2), (3 + 3)];", 3),
+            ("[[1, 2], fn(x){x;}];", "[[1, 2], fn(x){x;}];", 2),
+        ];
+        for (input, expect, length) in tests.iter() {
+            let lexer = Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let program_opt = parser.parse_program();
+            check_parser_errors(&parser);
+
+            if program_opt.is_none() {
+                panic!("プログラムをパースできませんでした。{}", input);
+            }
+            let program = program_opt.unwrap();
+            assert_eq!(program.to_string(), expect.to_string());
+            if let Statement::ExpressionStatement {
+                token: _,
+                expression,
+            } = &program.statements[0]
+            {
+                if let Expression::ArrayLiteral { token, elements } = &**expression {
+                    assert!(token.token_type_is(TokenType::LBRACKET));
+                    assert_eq!(elements.len(), *length);
+                } else {
+                    panic!("配列リテラルではありませんでした。{}", input);
+                }
+            } else {
+                panic!("入力が式文ではありません。{}", input);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hash_literal() {
+        let tests = [
+            // (input, expect, length)
+            ("{};", "{};", 0),
+            (
+                r#"{"one": 1, "two": 2, "three": 3};"#,
+                r#"{"one": 1, "two": 2, "three": 3};"#,
+                3,
+            ),
+            (
+                "{1 + 1: 2 * 2, true: fn(x){x;}};",
+                "{(1 + 1): (2 * 2), true: fn(x){x;}};",
+                2,
+            ),
+        ];
+        for (input, expect, length) in tests.iter() {
+            let lexer = Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let program_opt = parser.parse_program();
+            check_parser_errors(&parser);
+
+            if program_opt.is_none() {
+                panic!("プログラムをパースできませんでした。{}", input);
+            }
+            let program = program_opt.unwrap();
+            // 書かれた順に表示されるので何度変換しても同じ文字列になる
+            assert_eq!(program.to_string(), expect.to_string());
+            assert_eq!(program.to_string(), expect.to_string());
+            if let Statement::ExpressionStatement {
+                token: _,
+                expression,
+            } = &program.statements[0]
+            {
+                if let Expression::HashLiteral { token, pairs } = &**expression {
+                    assert!(token.token_type_is(TokenType::LBRACE));
+                    assert_eq!(pairs.len(), *length);
+                } else {
+                    panic!("ハッシュリテラルではありませんでした。{}", input);
+                }
+            } else {
+                panic!("入力が式文ではありません。{}", input);
+            }
+        }
+
+        for input in ["{1};", "{1: };", "{1: 2 3: 4};"].iter() {
+            let mut parser = Parser::new(Lexer::new(input));
+            assert!(parser.parse_program().is_none(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_switch_expression() {
+        let tests = [
+            // (input, expect, arms length, has default)
+            (
+                r#"switch (x) { 1 => "one", 2 => "two", _ => "other" };"#,
+                r#"switch x { 1 => "one", 2 => "two", _ => "other" };"#,
+                2,
+                true,
+            ),
+            (
+                "switch x + 1 { 2 => a * 2 };",
+                "switch (x + 1) { 2 => (a * 2) };",
+                1,
+                false,
+            ),
+            (
+                "switch (b) { true => 1, false => 0, };",
+                "switch b { true => 1, false => 0 };",
+                2,
+                false,
+            ),
+            (
+                "switch (x) { _ => f(x) };",
+                "switch x { _ => f(x) };",
+                0,
+                true,
+            ),
+            ("switch (x) {};", "switch x {};", 0, false),
+        ];
+        for (input, expect, length, has_default) in tests.iter() {
+            let lexer = Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let program_opt = parser.parse_program();
+            check_parser_errors(&parser);
+
+            if program_opt.is_none() {
+                panic!("プログラムをパースできませんでした。{}", input);
+            }
+            let program = program_opt.unwrap();
+            assert_eq!(program.to_string(), expect.to_string());
+            if let Statement::ExpressionStatement {
+                token: _,
+                expression,
+            } = &program.statements[0]
+            {
+                if let Expression::SwitchExpression {
+                    token,
+                    subject: _,
+                    arms,
+                    default,
+                } = &**expression
+                {
+                    assert!(token.token_type_is(TokenType::SWITCH));
+                    assert_eq!(arms.len(), *length);
+                    assert_eq!(default.is_some(), *has_default);
+                } else {
+                    panic!("switch式ではありませんでした。{}", input);
+                }
+            } else {
+                panic!("入力が式文ではありません。{}", input);
             }
         }
+
+        for input in [
+            "switch (x) { 1 };",
+            "switch (x) { 1 => };",
+            "switch (x) { 1 => 2 3 => 4 };",
+            "switch (x) { _ => 1, 2 => 3 };",
+            "switch (x) 1 => 2;",
+        ]
+        .iter()
+        {
+            let mut parser = Parser::new(Lexer::new(input));
+            assert!(parser.parse_program().is_none(), "{}", input);
+        }
     }
 
     /// 括弧と関数を除いて、異なる優先度で式をパースできているかのテスト
@@ -1475,6 +3544,23 @@ mod test {
             ("2 / ( 5 - 5);", "(2 / (5 - 5));"),
             ("-(5 + 5);", "(-(5 + 5));"),
             ("!(true == true);", "(!(true == true));"),
+            ("a || b && c;", "(a || (b && c));"),
+            ("a && b || c;", "((a && b) || c);"),
+            ("a == b && c < d;", "((a == b) && (c < d));"),
+            ("!a || b;", "((!a) || b);"),
+            ("+-5;", "(+(-5));"),
+            ("-+a * +b;", "((-(+a)) * (+b));"),
+            ("a + +b;", "(a + (+b));"),
+            ("a.b.c;", "((a.b).c);"),
+            ("-a.b * c.d;", "((-(a.b)) * (c.d));"),
+            ("a.b[0].c;", "(((a.b)[0]).c);"),
+            ("m.f(1) + x.y;", "((m.f)(1) + (x.y));"),
+            ("!h.ok || h.n < 2;", "((!(h.ok)) || ((h.n) < 2));"),
+            ("0..n + 1;", "(0..(n + 1));"),
+            ("a * 2..b - 1 < c;", "(((a * 2)..(b - 1)) < c);"),
+            ("x == 1..2;", "(x == (1..2));"),
+            ("-1..-a[0];", "((-1)..(-(a[0])));"),
+            ("a..b..c;", "((a..b)..c);"),
         ];
 
         for (input, expect) in tests.iter() {
@@ -1483,15 +3569,12 @@ mod test {
             let program_opt = parser.parse_program();
             check_parser_errors(&parser);
             if program_opt.is_none() {
-                assert!(
-                    false,
-                    "プログラムをパースすることができませんでした。"
-                );
+                panic!("プログラムをパースすることができませんでした。");
             }
             let program = program_opt.unwrap();
             let actual = program.to_string();
             assert!(
-                &actual == *expect,
+                actual == *expect,
                 "{} => {:?}\n{} ?= {}",
                 input,
                 program,
@@ -1501,4 +3584,68 @@ mod test {
             assert_eq!(&actual, *expect);
         }
     }
+
+    #[test]
+    fn test_prefix_minus_precedence() {
+        // 前置の`-`は直後の被演算子だけに付き、後ろの中置演算子より先に結び付く
+        let tests = [
+            // (input, expect, 一番外側の中置演算子)
+            ("-a + b;", "((-a) + b);", Some("+")),
+            ("-a * b;", "((-a) * b);", Some("*")),
+            ("-(a + b);", "(-(a + b));", None),
+        ];
+
+        for (input, expect, outer) in tests.iter() {
+            let mut parser = Parser::new(Lexer::new(input));
+            let program = parser.parse_program().expect("fail parse program.");
+            check_parser_errors(&parser);
+            assert_eq!(&program.to_string(), expect);
+
+            let expression = match &program.statements[0] {
+                Statement::ExpressionStatement {
+                    token: _,
+                    expression,
+                } => expression.as_ref(),
+                statement => {
+                    panic!("式文ではありません。{:?}", statement);
+                }
+            };
+            match (expression, outer) {
+                (
+                    Expression::InfixExpression {
+                        token: _,
+                        operator,
+                        left_exp,
+                        right_exp: _,
+                    },
+                    Some(outer),
+                ) => {
+                    assert_eq!(operator, outer);
+                    match left_exp.as_ref() {
+                        Expression::PrefixExpression {
+                            token: _,
+                            operator,
+                            right_exp: _,
+                        } => assert_eq!(operator, "-"),
+                        left => panic!("左辺が前置演算子の式ではありません。{:?}", left),
+                    }
+                }
+                (
+                    Expression::PrefixExpression {
+                        token: _,
+                        operator,
+                        right_exp,
+                    },
+                    None,
+                ) => {
+                    assert_eq!(operator, "-");
+                    assert!(matches!(
+                        right_exp.as_ref(),
+                        Expression::InfixExpression { .. }
+                    ));
+                }
+                (expression, _) => panic!("{} => {:?}", input, expression),
+            }
+        }
+    }
 }
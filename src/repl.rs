@@ -1,14 +1,29 @@
+use std::cell::RefCell;
 use std::io::{BufRead, BufReader, LineWriter, Read, Write};
+use std::path::Path;
+use std::rc::Rc;
 
-use crate::evaluator::Eval;
-use crate::lexer::Lexer;
-use crate::parser::Parser;
+use crate::ast::Program;
+use crate::environment::Environment;
+use crate::evaluator::{Eval, EvalOptions};
+use crate::lexer::{escape_string, Lexer};
+use crate::parser::{Parser, ParserOptions};
 use crate::token::TokenType;
 
 /// 入力促進メッセージ
 const PROMPT: &str = ">> ";
 /// REPL終了用の入力記号
 const FINISH_KEY: &str = "\u{4}";
+/// 環境のスナップショットを取るコマンド
+const SNAPSHOT_COMMAND: &str = ":snapshot";
+/// 環境をスナップショット(なければ空)に戻すコマンド
+const RESET_COMMAND: &str = ":reset";
+/// 入力したソースの履歴を表示するコマンド
+const HISTORY_COMMAND: &str = ":history";
+/// 履歴のN番目(1始まり)を再実行するコマンドの接頭辞。`:!N`の形で使う。
+const HISTORY_RECALL_PREFIX: &str = ":!";
+/// 字句解析の結果を表示するかどうかを切り替えるコマンド
+const VERBOSE_COMMAND: &str = ":verbose";
 /// 区切りの繰り返し数
 const REPEAT_COUNT: usize = 30;
 
@@ -16,59 +31,494 @@ const REPEAT_COUNT: usize = 30;
 pub fn start(reader: impl Read, writer: impl Write) {
     let mut r = BufReader::new(reader);
     let mut w = LineWriter::new(writer);
+    // パイプが閉じられたなどで書き込めなくなったらパニックせずに終了する
+    let _ = run(&mut r, &mut w);
+}
+
+/// 評価器の`puts`と`eputs`の出力を溜めておく書き込み先
+/// 評価器は書き込み先を持ち続けるので、REPLの書き込み先を直接渡さずに評価のたびにREPLの書き込み先に移す。
+#[derive(Clone)]
+struct OutputBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl OutputBuffer {
+    /// 溜まった出力を書き込み先に移す関数
+    fn drain_to(&self, w: &mut impl Write) -> std::io::Result<()> {
+        let mut buffer = self.0.borrow_mut();
+        w.write_all(&buffer)?;
+        buffer.clear();
+        return Ok(());
+    }
+}
+
+impl Write for OutputBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// REPLの本体。書き込みに失敗したらそのエラーを返して終了する。
+fn run(r: &mut impl BufRead, w: &mut impl Write) -> std::io::Result<()> {
+    let mut env = Rc::new(RefCell::new(Environment::new()));
+    // 出力とエラー出力は入力した順に並ぶように同じ書き込み先に溜める
+    let output = OutputBuffer(Rc::new(RefCell::new(Vec::new())));
+    // 評価器は一つを使い続け、`:reset`では環境だけを入れ替える
+    let mut eval = Eval::with_writers(
+        Rc::clone(&env),
+        EvalOptions::new(),
+        Box::new(output.clone()),
+        Box::new(output.clone()),
+    );
+    let mut snapshot: Option<Environment> = None;
+    // 入力されたソースの履歴。コマンドは含まない
+    let mut history: Vec<String> = Vec::new();
+    // 入力のトークン列を表示するかどうか
+    let mut verbose = true;
 
     'main: loop {
-        write!(w, "{}", PROMPT).unwrap();
-        w.flush().unwrap();
+        write!(w, "{}", PROMPT)?;
+        w.flush()?;
         let mut line = "".to_string();
-        let res = r.read_line(&mut line);
-        if res.is_err() {
-            continue 'main;
+        match r.read_line(&mut line) {
+            // 入力の終わり(パイプの終端や行頭でのCtrl+D)に達したら終了する
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(_) => continue 'main,
         }
         if line.trim() == FINISH_KEY {
             break;
         }
+        // 空白だけの行はパースせずに入力を促し直す
+        if line.trim().is_empty() {
+            continue 'main;
+        }
+        if line.trim() == SNAPSHOT_COMMAND {
+            snapshot = Some(env.borrow().snapshot());
+            writeln!(w, "環境のスナップショットを保存しました。")?;
+            continue 'main;
+        }
+        if line.trim() == RESET_COMMAND {
+            env = reset_environment(&snapshot);
+            eval.set_env(Rc::clone(&env));
+            writeln!(w, "環境をリセットしました。")?;
+            continue 'main;
+        }
+        if line.trim() == VERBOSE_COMMAND {
+            verbose = !verbose;
+            if verbose {
+                writeln!(w, "トークン列を表示します。")?;
+            } else {
+                writeln!(w, "トークン列を表示しません。")?;
+            }
+            continue 'main;
+        }
+        if line.trim() == HISTORY_COMMAND {
+            for (i, entry) in history.iter().enumerate() {
+                writeln!(w, "{}: {}", i + 1, entry)?;
+            }
+            continue 'main;
+        }
 
-        writeln!(w, "start Lexer: {}", "-".repeat(REPEAT_COUNT)).unwrap();
+        let source = match line.trim().strip_prefix(HISTORY_RECALL_PREFIX) {
+            Some(n) => match recall_history(&history, n) {
+                Some(entry) => {
+                    // 何を再実行したのか分かるように表示する
+                    writeln!(w, "{}", entry)?;
+                    entry.to_string()
+                }
+                None => {
+                    writeln!(w, "履歴{}はありません。", n)?;
+                    continue 'main;
+                }
+            },
+            None => line.trim().to_string(),
+        };
+        if !source.is_empty() {
+            history.push(source.clone());
+        }
 
-        let mut lexer = Lexer::new(&line);
-        while let tok = lexer.next_token() {
-            if tok.token_type_is(TokenType::EOF) {
-                break;
-            }
-            if tok.token_type_is(TokenType::ILLEGAL) {
-                writeln!(w, "異常な入力を検知しました。").unwrap();
+        let line = complete_source(&source);
+
+        if verbose {
+            writeln!(w, "start Lexer: {}", "-".repeat(REPEAT_COUNT))?;
+            if !dump_tokens(&line, w)? {
                 continue 'main;
             }
-            write!(w, "{:?}\n", tok).unwrap();
+            writeln!(w, "end Lexer: {}", "-".repeat(REPEAT_COUNT))?;
         }
-        writeln!(w, "end Lexer: {}", "-".repeat(REPEAT_COUNT)).unwrap();
 
-        writeln!(w, "start parser: {}", "-".repeat(REPEAT_COUNT)).unwrap();
-        let mut parser = Parser::new(Lexer::new(&line));
-        let program_opt = parser.parse_program();
-        if program_opt.is_none() {
+        writeln!(w, "start parser: {}", "-".repeat(REPEAT_COUNT))?;
+        let program = match dump_ast(&line, w)? {
+            Some(program) => program,
+            None => continue 'main,
+        };
+        writeln!(w, "end parser: {}", "-".repeat(REPEAT_COUNT))?;
+
+        writeln!(w, "start evaluator: {}", "-".repeat(REPEAT_COUNT))?;
+        // 空のプログラムは評価結果を出力しない
+        if !program.statements.is_empty() {
+            let evaluated = eval.eval_program(&program);
+            output.drain_to(w)?;
+            writeln!(w, "evaluated: {}", evaluated.inspect())?;
+            if let Some((line, column)) = evaluated.error_position() {
+                writeln!(w, "error at line {}, column {}", line, column)?;
+            }
+        }
+        writeln!(w, "end evaluator: {}", "-".repeat(REPEAT_COUNT))?;
+    }
+    Ok(())
+}
+
+/// ファイルを評価せずに何を出力するかの種類
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub enum DumpMode {
+    /// トークン列
+    Tokens,
+    /// 解釈木
+    Ast,
+}
+
+/// ファイルのソースを評価せずにトークン列か解釈木として出力する関数
+/// パースの結果を確認したいときに使う。
+/// 不正なトークンかパースエラーがあればエラーを出力してfalseを返す。
+pub fn dump_file(path: &Path, mode: DumpMode, w: &mut impl Write) -> std::io::Result<bool> {
+    let source = std::fs::read_to_string(path)?;
+    match mode {
+        DumpMode::Tokens => {
+            return dump_tokens(&source, w);
+        }
+        DumpMode::Ast => {
+            // ファイルはスクリプトとしてパースする
+            let options = ParserOptions {
+                script: true,
+                ..ParserOptions::new()
+            };
+            return Ok(dump_ast_with_options(&source, options, w)?.is_some());
+        }
+    }
+}
+
+/// ソースのトークン列を一行に一つずつ`1:1 LET "let"`のように行:列、トークン型、リテラルの順で出力する関数
+/// 不正なトークンがあればそこで止めてfalseを返す。
+fn dump_tokens(source: &str, w: &mut impl Write) -> std::io::Result<bool> {
+    let mut lexer = Lexer::new(source);
+    loop {
+        let tok = lexer.next_token();
+        if tok.token_type_is(TokenType::EOF) {
+            break;
+        }
+        if tok.token_type_is(TokenType::ILLEGAL) {
+            writeln!(w, "異常な入力を検知しました。")?;
+            return Ok(false);
+        }
+        let (line, column) = tok.get_position();
+        writeln!(
+            w,
+            "{}:{} {} {}",
+            line,
+            column,
+            tok.get_token_type(),
+            escape_string(tok.literal())
+        )?;
+    }
+    Ok(true)
+}
+
+/// ソースをパースして解釈木を出力する関数
+/// パースエラーがあればエラーを出力してNoneを返す。
+fn dump_ast(source: &str, w: &mut impl Write) -> std::io::Result<Option<Program>> {
+    dump_ast_with_options(source, ParserOptions::new(), w)
+}
+
+/// 設定を指定してソースをパースして解釈木を出力する関数
+fn dump_ast_with_options(
+    source: &str,
+    options: ParserOptions,
+    w: &mut impl Write,
+) -> std::io::Result<Option<Program>> {
+    let mut parser = Parser::with_options(Lexer::new(source), options);
+    let program = match parser.parse_program() {
+        Some(program) => program,
+        None => {
             let errors = parser.get_errors();
-            writeln!(
-                w,
-                "パースエラーが{}件発生しました。",
-                errors.len()
-            )
-            .unwrap();
+            writeln!(w, "パースエラーが{}件発生しました。", errors.len())?;
             for error in errors {
-                writeln!(w, "{}", error).unwrap();
+                writeln!(w, "{}", error)?;
             }
-            continue 'main;
+            return Ok(None);
+        }
+    };
+    writeln!(w, "Program string: {}", program)?;
+    writeln!(w, "AST: {:?}", program)?;
+    Ok(Some(program))
+}
+
+/// 最後の文のセミコロンを省略した入力を補完する関数
+/// 空白とコメントとセミコロンだけの入力は空のプログラムとして扱う。
+/// `5 // hi`のように行末にコメントがあってもコメントの中に入れないように、セミコロンはコメントではない最後のトークンの直後に入れる。
+fn complete_source(line: &str) -> String {
+    let trimmed = line.trim();
+    let mut lexer = Lexer::new(trimmed);
+    // コメントではない最後のトークンの型とその終わりのバイト位置
+    let mut last: Option<(TokenType, usize)> = None;
+    let mut has_statement = false;
+    loop {
+        let token = lexer.next_token();
+        if token.token_type_is(TokenType::EOF) {
+            break;
         }
-        let program = program_opt.unwrap();
-        let program_str = program.to_string();
-        writeln!(w, "Program string: {}", program_str).unwrap();
-        writeln!(w, "AST: {:?}", program).unwrap();
-        writeln!(w, "end parser: {}", "-".repeat(REPEAT_COUNT)).unwrap();
+        has_statement |= !token.token_type_is(TokenType::SEMICOLON);
+        last = Some((token.get_token_type(), lexer.get_position()));
+    }
+    match last {
+        Some((token_type, end)) if has_statement && token_type != TokenType::SEMICOLON => {
+            return format!("{};{}", &trimmed[..end], &trimmed[end..]);
+        }
+        Some(_) if has_statement => return trimmed.to_string(),
+        _ => return "".to_string(),
+    }
+}
+
+/// 1始まりの履歴番号の文字列から履歴の入力を取り出す関数
+fn recall_history<'a>(history: &'a [String], n: &str) -> Option<&'a str> {
+    let index = n.trim().parse::<usize>().ok()?.checked_sub(1)?;
+    return history.get(index).map(|entry| entry.as_str());
+}
+
+/// スナップショットがあればその複製を、なければ空の環境を返す関数
+/// リセット前に生成された関数は元の環境を保持し続けるので引き続き呼び出せる。
+fn reset_environment(snapshot: &Option<Environment>) -> Rc<RefCell<Environment>> {
+    let env = match snapshot {
+        Some(s) => s.snapshot(),
+        None => Environment::new(),
+    };
+    Rc::new(RefCell::new(env))
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use crate::repl::{dump_file, dump_tokens, start, DumpMode, PROMPT};
+
+    /// 指定したバイト数を書き込むとエラーを返すライター
+    struct FailingWriter {
+        remaining: usize,
+    }
+
+    impl Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.remaining == 0 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::BrokenPipe,
+                    "broken pipe",
+                ));
+            }
+            let n = buf.len().min(self.remaining);
+            self.remaining -= n;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn run(input: &str) -> String {
+        let mut output = Vec::new();
+        start(input.as_bytes(), &mut output);
+        String::from_utf8(output).unwrap()
+    }
+
+    fn evaluated_lines(output: &str) -> Vec<&str> {
+        output
+            .lines()
+            .filter(|l| l.starts_with("evaluated: "))
+            .collect()
+    }
+
+    #[test]
+    fn test_print_result_with_semicolon() {
+        let output = run("5+5;\n\u{4}\n");
+        assert_eq!(evaluated_lines(&output), vec!["evaluated: 10"]);
+    }
+
+    #[test]
+    fn test_print_result_without_semicolon() {
+        let output = run("5+5\nif (true) {1;}\n\u{4}\n");
+        assert_eq!(
+            evaluated_lines(&output),
+            vec!["evaluated: 10", "evaluated: 1"]
+        );
+        assert!(!output.contains("パースエラー"));
+    }
+
+    #[test]
+    fn test_print_result_with_trailing_comment() {
+        // 省略したセミコロンは行末のコメントの中ではなくコメントの前に補う
+        let output =
+            run("5 // hi\nlet x = 1 // c\nx + \"a\" /* c */\nx; // done\n// only\n\u{4}\n");
+        assert_eq!(
+            evaluated_lines(&output),
+            vec![
+                "evaluated: 5",
+                "evaluated: null",
+                "evaluated: ERROR: type mismatch: INTEGER + STRING",
+                "evaluated: 1"
+            ]
+        );
+        assert!(!output.contains("パースエラー"));
+    }
+
+    #[test]
+    fn test_print_nothing_for_empty_program() {
+        let output = run("\n;\n ; ; \n\u{4}\n");
+        assert!(evaluated_lines(&output).is_empty());
+        assert!(!output.contains("パースエラー"));
+    }
+
+    #[test]
+    fn test_skip_blank_lines() {
+        let output = run("\n  \t\n5;\n\u{4}\n");
+        assert_eq!(evaluated_lines(&output), vec!["evaluated: 5"]);
+        assert_eq!(output.matches("start Lexer").count(), 1);
+        assert_eq!(output.matches(PROMPT).count(), 4);
+    }
+
+    #[test]
+    fn test_stop_at_end_of_input() {
+        // 入力がすぐに終わっても入力を促して終了する
+        assert_eq!(run(""), PROMPT);
+        // 終了キーのない入力も最後まで評価してから終了する
+        let output = run("5;\n6;");
+        assert_eq!(
+            evaluated_lines(&output),
+            vec!["evaluated: 5", "evaluated: 6"]
+        );
+        assert!(output.ends_with(PROMPT));
+    }
+
+    #[test]
+    fn test_stop_on_write_error() {
+        // 終了キーを含まない入力でも書き込みに失敗した時点で終了する
+        let input = "5 + 5;\n".repeat(100);
+        start(input.as_bytes(), FailingWriter { remaining: 64 });
+    }
+
+    #[test]
+    fn test_dump_tokens() {
+        let mut output = Vec::new();
+        assert!(dump_tokens("let s = \"a b\";", &mut output).unwrap());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "1:1 LET \"let\"\n\
+             1:5 IDENT \"s\"\n\
+             1:7 ASSIGN \"=\"\n\
+             1:9 STRING \"a b\"\n\
+             1:14 SEMICOLON \";\"\n"
+        );
+    }
+
+    #[test]
+    fn test_toggle_verbose() {
+        let output = run("1;\n:verbose\n2;\n:verbose\n3;\n\u{4}\n");
+        assert_eq!(
+            evaluated_lines(&output),
+            vec!["evaluated: 1", "evaluated: 2", "evaluated: 3"]
+        );
+        assert_eq!(output.matches("start Lexer").count(), 2);
+        assert!(output.contains("トークン列を表示しません。"));
+    }
+
+    #[test]
+    fn test_reset() {
+        let output = run("let x = 5;\nx;\n:reset\nx;\n\u{4}\n");
+        assert_eq!(
+            evaluated_lines(&output),
+            vec![
+                "evaluated: null",
+                "evaluated: 5",
+                "evaluated: ERROR: identifier not found: x"
+            ]
+        );
+        assert!(output.contains("error at line 1, column 1\n"));
+    }
+
+    #[test]
+    fn test_puts_writes_to_repl_output() {
+        // `puts`と`eputs`の出力は評価結果の前にREPLの書き込み先に出る
+        let output = run("puts(\"hi\"); eputs(2)\n\u{4}\n");
+        assert!(output
+            .contains("start evaluator: ------------------------------\nhi\n2\nevaluated: null\n"));
+        // 評価器を使い続けてもリセット後は新しい環境で評価する
+        let output = run("let p = puts;\n:reset\np(1);\nputs(3);\n\u{4}\n");
+        assert_eq!(
+            evaluated_lines(&output),
+            vec![
+                "evaluated: null",
+                "evaluated: ERROR: identifier not found: p",
+                "evaluated: null"
+            ]
+        );
+        assert!(output.contains("\n3\nevaluated: null\n"));
+    }
+
+    #[test]
+    fn test_history() {
+        let output = run("1 + 1\n2 * 3;\n:history\n:!1\n:!9\n:history\n\u{4}\n");
+        assert_eq!(
+            evaluated_lines(&output),
+            vec!["evaluated: 2", "evaluated: 6", "evaluated: 2"]
+        );
+        assert!(output.contains("1: 1 + 1\n2: 2 * 3;\n"));
+        assert!(output.contains("履歴9はありません。"));
+        // 再実行した入力も履歴に残る
+        assert!(output.contains("2: 2 * 3;\n3: 1 + 1\n"));
+    }
+
+    #[test]
+    fn test_reset_to_snapshot() {
+        let output = run("let x = 5;\n:snapshot\nlet x = 10;\n:reset\nx;\n\u{4}\n");
+        assert_eq!(evaluated_lines(&output).last(), Some(&"evaluated: 5"));
+    }
+
+    #[test]
+    fn test_dump_file() {
+        let path = std::env::temp_dir().join(format!("monkey_rs_dump_{}.mk", std::process::id()));
+        std::fs::write(&path, "let x = 1 + 2;").unwrap();
+
+        let mut tokens = Vec::new();
+        assert!(dump_file(&path, DumpMode::Tokens, &mut tokens).unwrap());
+        let tokens = String::from_utf8(tokens).unwrap();
+        assert_eq!(tokens.lines().count(), 7);
+        assert!(tokens.starts_with("1:1 LET \"let\"\n"));
+
+        let mut ast = Vec::new();
+        assert!(dump_file(&path, DumpMode::Ast, &mut ast).unwrap());
+        let ast = String::from_utf8(ast).unwrap();
+        assert!(ast.contains("Program string: let x = (1 + 2);"));
+        assert!(ast.contains("AST: "));
+        // 評価はしない
+        assert!(!ast.contains("evaluated"));
+
+        // ファイルはスクリプトとしてパースするので文末のセミコロンを省略できる
+        std::fs::write(&path, "let x = 1\nx * 2").unwrap();
+        let mut ast = Vec::new();
+        assert!(dump_file(&path, DumpMode::Ast, &mut ast).unwrap());
+        let ast = String::from_utf8(ast).unwrap();
+        assert!(ast.contains("Program string: let x = 1;(x * 2);"));
+
+        // 不正なトークンやパースエラーがあれば失敗を返す
+        std::fs::write(&path, "let x = 1 @ 2;").unwrap();
+        assert!(!dump_file(&path, DumpMode::Tokens, &mut Vec::new()).unwrap());
+        std::fs::write(&path, "let = 1;").unwrap();
+        assert!(dump_file(&path, DumpMode::Tokens, &mut Vec::new()).unwrap());
+        assert!(!dump_file(&path, DumpMode::Ast, &mut Vec::new()).unwrap());
 
-        writeln!(w, "start evaluator: {}", "-".repeat(REPEAT_COUNT)).unwrap();
-        let evaluated = Eval::eval_program(&program);
-        writeln!(w, "evaluated: {}", evaluated.to_string()).unwrap();
-        writeln!(w, "end evaluator: {}", "-".repeat(REPEAT_COUNT)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(dump_file(&path, DumpMode::Ast, &mut Vec::new()).is_err());
     }
 }
@@ -1,4 +1,6 @@
 use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
 
 /// Tokenとして便指揮できる識別句の一覧
 #[derive(Debug, Eq, PartialEq, Clone, Hash)]
@@ -6,10 +8,14 @@ pub enum TokenType {
     // 特殊な状態
     ILLEGAL,
     EOF,
+    // ドキュメントモードの字句解析器だけが返すコメント
+    COMMENT,
 
     //識別子とリテラル
     IDENT,
     INT,
+    FLOAT,
+    STRING,
 
     // 演算子
     ASSIGN,
@@ -24,16 +30,25 @@ pub enum TokenType {
     GT,
     EQ,
     NEQ,
+    AND,
+    OR,
 
     // デリミタ
     COMMA,
     SEMICOLON,
+    COLON,
+    ELLIPSIS,
+    DOT,
+    RANGE,
+    ARROW,
 
     // 括弧
     LPAREN,
     RPAREN,
     LBRACE,
     RBRACE,
+    LBRACKET,
+    RBRACKET,
 
     // キーワード
     FUNCTION,
@@ -42,10 +57,83 @@ pub enum TokenType {
     FALSE,
     IF,
     ELSE,
+    DO,
+    WHILE,
+    FOR,
+    IN,
+    BREAK,
+    CONTINUE,
+    SWITCH,
     RETURN,
+    IMPORT,
+    NULL,
 }
 
 impl TokenType {
+    /// 全てのトークン型の一覧。トークン型を追加したらここにも追加する。
+    const ALL: &'static [TokenType] = &[
+        // 特殊な状態
+        TokenType::ILLEGAL,
+        TokenType::EOF,
+        TokenType::COMMENT,
+        //識別子とリテラル
+        TokenType::IDENT,
+        TokenType::INT,
+        TokenType::FLOAT,
+        TokenType::STRING,
+        // 演算子
+        TokenType::ASSIGN,
+        TokenType::PLUS,
+        TokenType::MINUS,
+        TokenType::BANG,
+        TokenType::ASTERISK,
+        TokenType::SLASH,
+        // 論理演算子
+        TokenType::LT,
+        TokenType::GT,
+        TokenType::EQ,
+        TokenType::NEQ,
+        TokenType::AND,
+        TokenType::OR,
+        // デリミタ
+        TokenType::COMMA,
+        TokenType::SEMICOLON,
+        TokenType::COLON,
+        TokenType::ELLIPSIS,
+        TokenType::DOT,
+        TokenType::RANGE,
+        TokenType::ARROW,
+        // 括弧
+        TokenType::LPAREN,
+        TokenType::RPAREN,
+        TokenType::LBRACE,
+        TokenType::RBRACE,
+        TokenType::LBRACKET,
+        TokenType::RBRACKET,
+        // キーワード
+        TokenType::FUNCTION,
+        TokenType::LET,
+        TokenType::TRUE,
+        TokenType::FALSE,
+        TokenType::IF,
+        TokenType::ELSE,
+        TokenType::DO,
+        TokenType::WHILE,
+        TokenType::FOR,
+        TokenType::IN,
+        TokenType::BREAK,
+        TokenType::CONTINUE,
+        TokenType::SWITCH,
+        TokenType::RETURN,
+        TokenType::IMPORT,
+        TokenType::NULL,
+    ];
+
+    /// 全てのトークン型を宣言順に取得する
+    pub fn all() -> &'static [TokenType] {
+        return TokenType::ALL;
+    }
+
     /// 予約語一覧を取得する
     pub fn keywords() -> HashMap<String, TokenType> {
         return vec![
@@ -53,9 +141,18 @@ impl TokenType {
             ("let".to_string(), TokenType::LET),
             ("if".to_string(), TokenType::IF),
             ("else".to_string(), TokenType::ELSE),
+            ("switch".to_string(), TokenType::SWITCH),
+            ("do".to_string(), TokenType::DO),
+            ("while".to_string(), TokenType::WHILE),
+            ("for".to_string(), TokenType::FOR),
+            ("in".to_string(), TokenType::IN),
+            ("break".to_string(), TokenType::BREAK),
+            ("continue".to_string(), TokenType::CONTINUE),
             ("return".to_string(), TokenType::RETURN),
+            ("import".to_string(), TokenType::IMPORT),
             ("true".to_string(), TokenType::TRUE),
             ("false".to_string(), TokenType::FALSE),
+            ("null".to_string(), TokenType::NULL),
         ]
         .into_iter()
         .collect();
@@ -71,27 +168,73 @@ impl TokenType {
     }
 }
 
+/// トークン型の名前をそのまま表示する
+impl fmt::Display for TokenType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
 /// 読んだ文字とそれに対応する識別句からなるトークン
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, Clone)]
 pub struct Token {
     token_type: TokenType,
     literal: String,
+    // 入力中の(行, 列)。どちらも1始まりで、位置を持たないときは(0, 0)
+    position: (usize, usize),
+}
+
+/// 位置は比較せずにトークン型とリテラルだけで比較する
+impl PartialEq for Token {
+    fn eq(&self, other: &Self) -> bool {
+        return self.token_type == other.token_type && self.literal == other.literal;
+    }
+}
+
+/// 比較と同じく位置を含めずにハッシュ値を計算する
+impl Hash for Token {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.token_type.hash(state);
+        self.literal.hash(state);
+    }
 }
 
 impl Token {
     /// 初期化関数
     pub fn new(token_type: TokenType, literal: &str) -> Self {
+        return Token::with_position(token_type, literal, (0, 0));
+    }
+
+    /// 位置情報付きの初期化関数
+    pub fn with_position(token_type: TokenType, literal: &str, position: (usize, usize)) -> Self {
         return Token {
             token_type,
             literal: literal.to_string(),
+            position,
         };
     }
 
+    /// 入力中の(行, 列)を返す
+    pub fn get_position(&self) -> (usize, usize) {
+        return self.position;
+    }
+
+    /// 位置も含めて等しいかの判定
+    pub fn eq_with_position(&self, other: &Token) -> bool {
+        return self == other && self.position == other.position;
+    }
+
     /// 束縛した値を返す
     pub fn get_literal(&self) -> String {
         return self.literal.to_string();
     }
 
+    /// 束縛した値を借用して返す
+    /// 比較や数値への変換だけなら`get_literal`と違って文字列を複製しない。
+    pub fn literal(&self) -> &str {
+        return &self.literal;
+    }
+
     /// トークン型を返す
     pub fn get_token_type(&self) -> TokenType {
         return self.token_type.clone();
@@ -105,6 +248,8 @@ impl Token {
 
 #[cfg(test)]
 mod test {
+    use std::collections::{HashMap, HashSet};
+
     use crate::lexer::Lexer;
     use crate::token::Token;
     use crate::token::TokenType;
@@ -124,6 +269,7 @@ mod test {
 
     #[test]
     fn test_next_token() {
+        // `/*`はコメントの始まりになるので`/`と`*`は空白で区切る
         let input = "
         =+(){},;
         let five = 5;
@@ -135,7 +281,7 @@ mod test {
 
         let result = add(five, ten);
 
-        !-/*5;
+        !-/ *5;
         5 < 10 > 5;
 
         if (5 < 10) {
@@ -242,4 +388,605 @@ mod test {
             assert_eq!(tok.literal, tt.literal);
         }
     }
+
+    #[test]
+    fn test_null_keyword() {
+        let input = "let x = null;";
+        let tests = [
+            Token::new(TokenType::LET, "let"),
+            Token::new(TokenType::IDENT, "x"),
+            Token::new(TokenType::ASSIGN, "="),
+            Token::new(TokenType::NULL, "null"),
+            Token::new(TokenType::SEMICOLON, ";"),
+            Token::new(TokenType::EOF, ""),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for tt in tests.iter() {
+            let tok = lexer.next_token();
+
+            assert_eq!(tok.token_type, tt.token_type);
+            assert_eq!(tok.literal, tt.literal);
+        }
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut lexer = Lexer::new("let five = 5;");
+        let first = [
+            Token::new(TokenType::LET, "let"),
+            Token::new(TokenType::IDENT, "five"),
+            Token::new(TokenType::ASSIGN, "="),
+            Token::new(TokenType::INT, "5"),
+            Token::new(TokenType::SEMICOLON, ";"),
+            Token::new(TokenType::EOF, ""),
+        ];
+        for tt in first.iter() {
+            let tok = lexer.next_token();
+
+            assert_eq!(tok.token_type, tt.token_type);
+            assert_eq!(tok.literal, tt.literal);
+        }
+
+        lexer.reset("x != 10");
+        let second = [
+            Token::new(TokenType::IDENT, "x"),
+            Token::new(TokenType::NEQ, "!="),
+            Token::new(TokenType::INT, "10"),
+            Token::new(TokenType::EOF, ""),
+        ];
+        for tt in second.iter() {
+            let tok = lexer.next_token();
+
+            assert_eq!(tok.token_type, tt.token_type);
+            assert_eq!(tok.literal, tt.literal);
+        }
+    }
+
+    #[test]
+    fn test_display_token_type() {
+        assert_eq!(TokenType::LET.to_string(), "LET");
+        assert_eq!(format!("{}", TokenType::NEQ), "NEQ");
+    }
+
+    #[test]
+    fn test_literal_borrow() {
+        let tok = Token::new(TokenType::IDENT, "foo");
+        assert_eq!(tok.literal(), "foo");
+        assert_eq!(tok.literal(), tok.get_literal());
+
+        let mut lexer = Lexer::new("let x = 10;");
+        for expect in ["let", "x", "=", "10", ";", ""].iter() {
+            let tok = lexer.next_token();
+            assert_eq!(tok.literal(), *expect);
+        }
+    }
+
+    #[test]
+    fn test_eq_ignores_position() {
+        let a = Token::with_position(TokenType::IDENT, "x", (1, 1));
+        let b = Token::with_position(TokenType::IDENT, "x", (3, 5));
+        let c = Token::new(TokenType::IDENT, "x");
+
+        assert_eq!(a, b);
+        assert_eq!(a, c);
+        assert!(!a.eq_with_position(&b));
+        assert!(a.eq_with_position(&a.clone()));
+        assert_ne!(a, Token::with_position(TokenType::IDENT, "y", (1, 1)));
+    }
+
+    #[test]
+    fn test_token_position() {
+        let input = "let x = 5;\n  x + 10;";
+        let tests = [
+            Token::with_position(TokenType::LET, "let", (1, 1)),
+            Token::with_position(TokenType::IDENT, "x", (1, 5)),
+            Token::with_position(TokenType::ASSIGN, "=", (1, 7)),
+            Token::with_position(TokenType::INT, "5", (1, 9)),
+            Token::with_position(TokenType::SEMICOLON, ";", (1, 10)),
+            Token::with_position(TokenType::IDENT, "x", (2, 3)),
+            Token::with_position(TokenType::PLUS, "+", (2, 5)),
+            Token::with_position(TokenType::INT, "10", (2, 7)),
+            Token::with_position(TokenType::SEMICOLON, ";", (2, 9)),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for tt in tests.iter() {
+            let tok = lexer.next_token();
+
+            assert!(tok.eq_with_position(tt), "{:?} != {:?}", tok, tt);
+        }
+    }
+
+    #[test]
+    fn test_token_position_with_crlf() {
+        let input = "let x = 5;\r\n  x;\r\n\r\nx + 1;\r\n";
+        let tests = [
+            Token::with_position(TokenType::LET, "let", (1, 1)),
+            Token::with_position(TokenType::IDENT, "x", (1, 5)),
+            Token::with_position(TokenType::ASSIGN, "=", (1, 7)),
+            Token::with_position(TokenType::INT, "5", (1, 9)),
+            Token::with_position(TokenType::SEMICOLON, ";", (1, 10)),
+            Token::with_position(TokenType::IDENT, "x", (2, 3)),
+            Token::with_position(TokenType::SEMICOLON, ";", (2, 4)),
+            Token::with_position(TokenType::IDENT, "x", (4, 1)),
+            Token::with_position(TokenType::PLUS, "+", (4, 3)),
+            Token::with_position(TokenType::INT, "1", (4, 5)),
+            Token::with_position(TokenType::SEMICOLON, ";", (4, 6)),
+            Token::with_position(TokenType::EOF, "", (5, 1)),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for tt in tests.iter() {
+            let tok = lexer.next_token();
+
+            assert!(tok.eq_with_position(tt), "{:?} != {:?}", tok, tt);
+        }
+    }
+
+    #[test]
+    fn test_logical_operators() {
+        let input = "true && false || x; & |";
+        let tests = [
+            Token::new(TokenType::TRUE, "true"),
+            Token::new(TokenType::AND, "&&"),
+            Token::new(TokenType::FALSE, "false"),
+            Token::new(TokenType::OR, "||"),
+            Token::new(TokenType::IDENT, "x"),
+            Token::new(TokenType::SEMICOLON, ";"),
+            Token::new(TokenType::ILLEGAL, "&"),
+            Token::new(TokenType::ILLEGAL, "|"),
+            Token::new(TokenType::EOF, ""),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for tt in tests.iter() {
+            let tok = lexer.next_token();
+
+            assert_eq!(tok.token_type, tt.token_type);
+            assert_eq!(tok.literal, tt.literal);
+        }
+    }
+
+    #[test]
+    fn test_brackets() {
+        let input = "[1, 2];";
+        let tests = [
+            Token::new(TokenType::LBRACKET, "["),
+            Token::new(TokenType::INT, "1"),
+            Token::new(TokenType::COMMA, ","),
+            Token::new(TokenType::INT, "2"),
+            Token::new(TokenType::RBRACKET, "]"),
+            Token::new(TokenType::SEMICOLON, ";"),
+            Token::new(TokenType::EOF, ""),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for tt in tests.iter() {
+            let tok = lexer.next_token();
+
+            assert_eq!(tok.token_type, tt.token_type);
+            assert_eq!(tok.literal, tt.literal);
+        }
+    }
+
+    #[test]
+    fn test_float() {
+        let valids = ["1.5", "1e10", "1.5e-3", "2E+5", "0.25E2"];
+        for input in valids.iter() {
+            let mut lexer = Lexer::new(input);
+            let tok = lexer.next_token();
+            assert_eq!(tok.token_type, TokenType::FLOAT, "{}", input);
+            assert_eq!(tok.literal, input.to_string());
+            assert!(tok.literal.parse::<f64>().is_ok(), "{}", input);
+            assert!(lexer.next_token().token_type_is(TokenType::EOF));
+            assert!(lexer.errors().is_empty(), "{}", input);
+        }
+
+        let invalids = ["1e", "1e+", "1E-", "1.2e3.4", "1.2.3"];
+        for input in invalids.iter() {
+            let mut lexer = Lexer::new(input);
+            let tok = lexer.next_token();
+            assert_eq!(tok.token_type, TokenType::ILLEGAL, "{}", input);
+            assert_eq!(tok.literal, input.to_string());
+            assert_eq!(
+                lexer.errors(),
+                &[format!("1行1列目: 不正な数値リテラル{}があります。", input)][..]
+            );
+        }
+
+        // 数字が続かない小数点は数値の一部にしない
+        let input = "1.x";
+        let tests = [
+            Token::new(TokenType::INT, "1"),
+            Token::new(TokenType::DOT, "."),
+            Token::new(TokenType::IDENT, "x"),
+            Token::new(TokenType::EOF, ""),
+        ];
+        let mut lexer = Lexer::new(input);
+        for tt in tests.iter() {
+            let tok = lexer.next_token();
+
+            assert_eq!(tok.token_type, tt.token_type);
+            assert_eq!(tok.literal, tt.literal);
+        }
+    }
+
+    #[test]
+    fn test_ellipsis() {
+        let input = "[a, ...b] . .. ....é..";
+        let tests = [
+            Token::new(TokenType::LBRACKET, "["),
+            Token::new(TokenType::IDENT, "a"),
+            Token::new(TokenType::COMMA, ","),
+            Token::new(TokenType::ELLIPSIS, "..."),
+            Token::new(TokenType::IDENT, "b"),
+            Token::new(TokenType::RBRACKET, "]"),
+            Token::new(TokenType::DOT, "."),
+            Token::new(TokenType::RANGE, ".."),
+            // 3文字先まで読んで判定する
+            Token::new(TokenType::ELLIPSIS, "..."),
+            Token::new(TokenType::DOT, "."),
+            Token::new(TokenType::IDENT, "é"),
+            // 入力の終わりを越えて先読みしない
+            Token::new(TokenType::RANGE, ".."),
+            Token::new(TokenType::EOF, ""),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for tt in tests.iter() {
+            let tok = lexer.next_token();
+
+            assert_eq!(tok.token_type, tt.token_type);
+            assert_eq!(tok.literal, tt.literal);
+        }
+    }
+
+    #[test]
+    fn test_range() {
+        let input = "1..10 a..b 1.5..2 1...";
+        let tests = [
+            // 数字が続かない小数点は数値の一部にしない
+            Token::new(TokenType::INT, "1"),
+            Token::new(TokenType::RANGE, ".."),
+            Token::new(TokenType::INT, "10"),
+            Token::new(TokenType::IDENT, "a"),
+            Token::new(TokenType::RANGE, ".."),
+            Token::new(TokenType::IDENT, "b"),
+            Token::new(TokenType::FLOAT, "1.5"),
+            Token::new(TokenType::RANGE, ".."),
+            Token::new(TokenType::INT, "2"),
+            Token::new(TokenType::INT, "1"),
+            Token::new(TokenType::ELLIPSIS, "..."),
+            Token::new(TokenType::EOF, ""),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for tt in tests.iter() {
+            let tok = lexer.next_token();
+
+            assert_eq!(tok.token_type, tt.token_type);
+            assert_eq!(tok.literal, tt.literal);
+        }
+    }
+
+    #[test]
+    fn test_arrow() {
+        let input = "switch (x) { 1 => a, _ => b } = > ===>";
+        let tests = [
+            Token::new(TokenType::SWITCH, "switch"),
+            Token::new(TokenType::LPAREN, "("),
+            Token::new(TokenType::IDENT, "x"),
+            Token::new(TokenType::RPAREN, ")"),
+            Token::new(TokenType::LBRACE, "{"),
+            Token::new(TokenType::INT, "1"),
+            Token::new(TokenType::ARROW, "=>"),
+            Token::new(TokenType::IDENT, "a"),
+            Token::new(TokenType::COMMA, ","),
+            Token::new(TokenType::IDENT, "_"),
+            Token::new(TokenType::ARROW, "=>"),
+            Token::new(TokenType::IDENT, "b"),
+            Token::new(TokenType::RBRACE, "}"),
+            Token::new(TokenType::ASSIGN, "="),
+            Token::new(TokenType::GT, ">"),
+            // 先に`==`として読む
+            Token::new(TokenType::EQ, "=="),
+            Token::new(TokenType::ARROW, "=>"),
+            Token::new(TokenType::EOF, ""),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for tt in tests.iter() {
+            let tok = lexer.next_token();
+
+            assert_eq!(tok.token_type, tt.token_type);
+            assert_eq!(tok.literal, tt.literal);
+        }
+    }
+
+    #[test]
+    fn test_string() {
+        let input = r#""foobar" "foo bar" "a\nb\t\"c\"\\" "bad\q" "unterminated"#;
+        let tests = [
+            Token::new(TokenType::STRING, "foobar"),
+            Token::new(TokenType::STRING, "foo bar"),
+            Token::new(TokenType::STRING, "a\nb\t\"c\"\\"),
+            Token::new(TokenType::ILLEGAL, "\""),
+            Token::new(TokenType::ILLEGAL, "\""),
+            Token::new(TokenType::EOF, ""),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for tt in tests.iter() {
+            let tok = lexer.next_token();
+
+            assert_eq!(tok.token_type, tt.token_type);
+            assert_eq!(tok.literal, tt.literal);
+        }
+    }
+
+    #[test]
+    fn test_multibyte_input() {
+        let input = "let s = \"こんにちは、café\"; @ 10; caf\u{e9}x; \"ü\\n\"";
+        let tests = [
+            Token::with_position(TokenType::LET, "let", (1, 1)),
+            Token::with_position(TokenType::IDENT, "s", (1, 5)),
+            Token::with_position(TokenType::ASSIGN, "=", (1, 7)),
+            Token::with_position(TokenType::STRING, "こんにちは、café", (1, 9)),
+            Token::with_position(TokenType::SEMICOLON, ";", (1, 21)),
+            Token::with_position(TokenType::ILLEGAL, "@", (1, 23)),
+            Token::with_position(TokenType::INT, "10", (1, 25)),
+            Token::with_position(TokenType::SEMICOLON, ";", (1, 27)),
+            Token::with_position(TokenType::IDENT, "caféx", (1, 29)),
+            Token::with_position(TokenType::SEMICOLON, ";", (1, 34)),
+            Token::with_position(TokenType::STRING, "ü\n", (1, 36)),
+            Token::with_position(TokenType::EOF, "", (1, 41)),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for tt in tests.iter() {
+            let tok = lexer.next_token();
+
+            assert!(tok.eq_with_position(tt), "{:?} != {:?}", tok, tt);
+        }
+        assert_eq!(lexer.get_position(), input.len());
+    }
+
+    #[test]
+    fn test_unicode_identifier() {
+        let input = "let π = 3; 名前 + _x; lét; 1abc";
+        let tests = [
+            Token::new(TokenType::LET, "let"),
+            Token::new(TokenType::IDENT, "π"),
+            Token::new(TokenType::ASSIGN, "="),
+            Token::new(TokenType::INT, "3"),
+            Token::new(TokenType::SEMICOLON, ";"),
+            Token::new(TokenType::IDENT, "名前"),
+            Token::new(TokenType::PLUS, "+"),
+            Token::new(TokenType::IDENT, "_x"),
+            Token::new(TokenType::SEMICOLON, ";"),
+            // キーワードに似ていても別の文字を含めば識別子になる
+            Token::new(TokenType::IDENT, "lét"),
+            Token::new(TokenType::SEMICOLON, ";"),
+            // 数字から始まる識別子は作れない
+            Token::new(TokenType::INT, "1"),
+            Token::new(TokenType::IDENT, "abc"),
+            Token::new(TokenType::EOF, ""),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for tt in tests.iter() {
+            let tok = lexer.next_token();
+
+            assert_eq!(tok.token_type, tt.token_type);
+            assert_eq!(tok.literal, tt.literal);
+        }
+    }
+
+    #[test]
+    fn test_comment() {
+        let input = "// 先頭\nlet x = 10 / 2; // 行末\n//";
+        let expects = [
+            Token::new(TokenType::LET, "let"),
+            Token::new(TokenType::IDENT, "x"),
+            Token::new(TokenType::ASSIGN, "="),
+            Token::new(TokenType::INT, "10"),
+            Token::new(TokenType::SLASH, "/"),
+            Token::new(TokenType::INT, "2"),
+            Token::new(TokenType::SEMICOLON, ";"),
+            Token::new(TokenType::EOF, ""),
+        ];
+        let mut lexer = Lexer::new(input);
+        for tt in expects.iter() {
+            let tok = lexer.next_token();
+
+            assert_eq!(tok.token_type, tt.token_type);
+            assert_eq!(tok.literal, tt.literal);
+        }
+
+        // ドキュメントモードではコメントもトークンとして返す
+        let mut lexer = Lexer::new(input);
+        lexer.set_doc_mode(true);
+        let mut tokens = vec![];
+        loop {
+            let tok = lexer.next_token();
+            if tok.token_type_is(TokenType::EOF) {
+                break;
+            }
+            tokens.push(tok);
+        }
+        assert_eq!(tokens[0], Token::new(TokenType::COMMENT, "先頭"));
+        assert_eq!(tokens[8], Token::new(TokenType::COMMENT, "行末"));
+        assert_eq!(tokens[9], Token::new(TokenType::COMMENT, ""));
+        assert_eq!(tokens.len(), 10);
+    }
+
+    #[test]
+    fn test_skip_trivia() {
+        let input = "  // c\n  /* b */ 5 /* 複数\n行 */+/**/6 // 終わり\n/* 閉じない";
+        let tests = [
+            Token::with_position(TokenType::INT, "5", (2, 11)),
+            Token::with_position(TokenType::PLUS, "+", (3, 5)),
+            Token::with_position(TokenType::INT, "6", (3, 10)),
+            Token::with_position(TokenType::EOF, "", (4, 8)),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for tt in tests.iter() {
+            let tok = lexer.next_token();
+
+            assert!(tok.eq_with_position(tt), "{:?} != {:?}", tok, tt);
+        }
+        assert_eq!(
+            lexer.errors(),
+            &["4行1列目: コメントが閉じられていません。"]
+        );
+
+        // ドキュメントモードでは両方のコメントを返す
+        let mut lexer = Lexer::new(input);
+        lexer.set_doc_mode(true);
+        let expects = [
+            Token::new(TokenType::COMMENT, "c"),
+            Token::new(TokenType::COMMENT, "b"),
+            Token::new(TokenType::INT, "5"),
+            Token::new(TokenType::COMMENT, "複数\n行"),
+            Token::new(TokenType::PLUS, "+"),
+            Token::new(TokenType::COMMENT, ""),
+            Token::new(TokenType::INT, "6"),
+            Token::new(TokenType::COMMENT, "終わり"),
+            Token::new(TokenType::COMMENT, "閉じない"),
+            Token::new(TokenType::EOF, ""),
+        ];
+        for tt in expects.iter() {
+            assert_eq!(&lexer.next_token(), tt);
+        }
+    }
+
+    #[test]
+    fn test_lexer_errors() {
+        let input = "let x = 1 & 2;\n\"a\\qb\" @ \"open";
+        let mut lexer = Lexer::new(input);
+        loop {
+            if lexer.next_token().token_type_is(TokenType::EOF) {
+                break;
+            }
+        }
+
+        let expects = [
+            "1行11列目: 不正な文字&があります。",
+            "2行1列目: 不正なエスケープシーケンス\\qがあります。",
+            "2行8列目: 不正な文字@があります。",
+            "2行10列目: 文字列リテラルが閉じられていません。",
+        ];
+        assert_eq!(lexer.errors(), &expects[..]);
+
+        // 新しい入力で使いまわすとエラーも消える
+        lexer.reset("let x = 1;");
+        assert!(lexer.errors().is_empty());
+    }
+
+    #[test]
+    fn test_position_after_eof() {
+        for input in ["", "let x = 5;", "\"é\" \n"].iter() {
+            let mut lexer = Lexer::new(input);
+            loop {
+                if lexer.next_token().token_type_is(TokenType::EOF) {
+                    break;
+                }
+            }
+            assert_eq!(lexer.get_position(), input.len(), "{:?}", input);
+            // EOFの後に読み進めても位置は変わらない
+            for _ in 0..3 {
+                assert!(lexer.next_token().token_type_is(TokenType::EOF));
+                assert_eq!(lexer.get_position(), input.len(), "{:?}", input);
+            }
+        }
+    }
+
+    #[test]
+    fn test_remaining() {
+        let mut lexer = Lexer::new("let x = 5; x;");
+        assert_eq!(lexer.remaining(), "let x = 5; x;");
+        for _ in 0..2 {
+            lexer.next_token();
+        }
+        // 読んだトークンの直後からが残りになる
+        assert_eq!(lexer.remaining(), " = 5; x;");
+        for _ in 0..3 {
+            lexer.next_token();
+        }
+        assert_eq!(lexer.remaining(), " x;");
+
+        // マルチバイト文字の前後でも文字の境界で切り出す
+        let mut lexer = Lexer::new("\"é\" ü; café");
+        lexer.next_token();
+        assert_eq!(lexer.remaining(), " ü; café");
+        lexer.next_token();
+        assert_eq!(lexer.remaining(), "; café");
+        lexer.next_token();
+        lexer.next_token();
+        assert_eq!(lexer.remaining(), "");
+        assert!(lexer.next_token().token_type_is(TokenType::EOF));
+        assert_eq!(lexer.remaining(), "");
+    }
+
+    #[test]
+    fn test_terminate_on_malformed_input() {
+        let inputs = [
+            "\u{0}",
+            "@#$%^",
+            "\"unterminated",
+            "\"bad\\",
+            "& | &",
+            "é",
+            "let 名前 = 5;",
+            "let = = ;;; ) (",
+            "\"\\q\" ~ ?",
+        ];
+        for input in inputs.iter() {
+            let mut lexer = Lexer::new(input);
+            // 呼び出しごとに少なくとも1文字進むので入力の長さより多くトークンを返すことはない
+            let limit = input.len() + 1;
+            let mut eof_count = 0;
+            for _ in 0..limit {
+                if lexer.next_token().token_type_is(TokenType::EOF) {
+                    eof_count += 1;
+                    break;
+                }
+            }
+            assert_eq!(eof_count, 1, "EOFに到達しませんでした。{:?}", input);
+            // EOFの後は何度呼んでもEOFを返し続ける
+            assert!(lexer.next_token().token_type_is(TokenType::EOF));
+        }
+    }
+
+    #[test]
+    fn test_all_token_types() {
+        let all = TokenType::all();
+        // 重複なく並んでいる
+        let unique: HashSet<&TokenType> = all.iter().collect();
+        assert_eq!(unique.len(), all.len());
+        // 表示した名前から同じトークン型に戻せる
+        let by_name: HashMap<String, &TokenType> = all.iter().map(|t| (t.to_string(), t)).collect();
+        assert_eq!(by_name.len(), all.len());
+        for token_type in all.iter() {
+            assert_eq!(by_name.get(&token_type.to_string()), Some(&token_type));
+        }
+        // キーワードのトークン型も全て含む
+        for token_type in TokenType::keywords().values() {
+            assert!(unique.contains(token_type), "{:?}", token_type);
+        }
+    }
 }